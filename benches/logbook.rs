@@ -0,0 +1,86 @@
+//! Parse and write throughput, one logbook at a time and in batches.
+//!
+//! `cargo bench`, then compare runs in `target/criterion`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+
+use bms_logcat::logbook::LOGBOOK_LEN;
+use bms_logcat::{layout, Keys, Logbook, LogbookBuilder, Medals, Rank, Validation};
+
+/// Roughly a squadron's worth of logbooks
+const BATCH: usize = 1000;
+
+fn sample() -> Logbook {
+    LogbookBuilder::new("Jane Doe", "Viper")
+        .unwrap()
+        .password("hunter2")
+        .unwrap()
+        .rank(Rank::Major)
+        .medal(Medals::AirMedal)
+        .squadron("80th FS")
+        .unwrap()
+        .personal_text("x".repeat(100))
+        .unwrap()
+        .build()
+}
+
+fn single(c: &mut Criterion) {
+    let book = sample();
+    let bytes = book.to_bytes().unwrap();
+
+    let mut g = c.benchmark_group("single");
+    g.throughput(Throughput::Bytes(LOGBOOK_LEN as u64));
+    g.bench_function("parse_bytes", |b| {
+        b.iter(|| Logbook::parse_bytes(&bytes).unwrap())
+    });
+    g.bench_function("parse (Read)", |b| {
+        b.iter(|| Logbook::parse(&bytes[..]).unwrap())
+    });
+    g.bench_function("write", |b| {
+        b.iter_batched_ref(
+            || Vec::with_capacity(LOGBOOK_LEN),
+            |out| book.write(out, Validation::Strict).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    g.bench_function("layout::events", |b| {
+        b.iter(|| layout::events(&bytes, &Keys::default()).unwrap().count())
+    });
+    g.finish();
+}
+
+fn batch(c: &mut Criterion) {
+    let books: Vec<Logbook> = (0..BATCH)
+        .map(|i| {
+            LogbookBuilder::from_logbook(sample())
+                .unwrap()
+                .callsign(format!("Viper{i}"))
+                .unwrap()
+                .build()
+        })
+        .collect();
+    let files: Vec<Vec<u8>> = books.iter().map(|b| b.to_bytes().unwrap()).collect();
+
+    let mut g = c.benchmark_group("batch");
+    g.throughput(Throughput::Bytes((BATCH * LOGBOOK_LEN) as u64));
+    g.bench_function("parse", |b| {
+        b.iter(|| {
+            files
+                .iter()
+                .map(|f| Logbook::parse_bytes(f).unwrap())
+                .collect::<Vec<_>>()
+        })
+    });
+    g.bench_function("write", |b| {
+        b.iter(|| {
+            books
+                .iter()
+                .map(|book| book.to_bytes().unwrap())
+                .collect::<Vec<_>>()
+        })
+    });
+    g.finish();
+}
+
+criterion_group!(benches, single, batch);
+criterion_main!(benches);
@@ -0,0 +1,20 @@
+//! Untrusted bytes shouldn't panic the parser, however they're read.
+
+#![no_main]
+
+use bms_logcat::{layout, Logbook, Validation};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|bytes: &[u8]| {
+    for validation in [Validation::Strict, Validation::Lenient] {
+        if let Ok((book, _warnings)) = Logbook::parse_with_report(bytes, validation) {
+            // Whatever parses leniently should write leniently.
+            let mut out = Vec::new();
+            book.write(&mut out, Validation::Lenient).unwrap();
+        }
+    }
+    let _ = Logbook::parse(bytes);
+    if let Ok(events) = layout::events(bytes) {
+        events.for_each(drop);
+    }
+});
@@ -0,0 +1,110 @@
+//! Any logbook that writes should read back the same.
+
+#![no_main]
+
+use std::collections::BTreeSet;
+
+use arbitrary::Arbitrary;
+use bms_logcat::{CampaignStats, DogfightStats, Logbook, Medals, Rank, Validation};
+use libfuzzer_sys::fuzz_target;
+
+/// The logbook's fields, as types `arbitrary` can make
+#[derive(Debug, Arbitrary)]
+struct Fields {
+    name: String,
+    callsign: String,
+    password: String,
+    commissioned: String,
+    options_file: String,
+    flight_hours: f32,
+    ace_factor: f32,
+    rank: i32,
+    dogfight: [i16; 8],
+    campaign: [i16; 15],
+    scores: [i32; 2],
+    medals: [bool; 6],
+    picture_file: String,
+    patch_file: String,
+    personal_text: String,
+    squadron: String,
+    voice: i16,
+}
+
+fn logbook(f: Fields) -> Option<Logbook> {
+    let [w, l, wh, lh, k, kd, hk, kh] = f.dogfight;
+    let [gw, gl, gt, m, cm, ck, ckd, chk, ckh, sk, agk, stk, nk, fk, msf] = f.campaign;
+    let medals: BTreeSet<Medals> = [
+        Medals::AirForceCross,
+        Medals::SilverStar,
+        Medals::DistinguishedFlyingCross,
+        Medals::AirMedal,
+        Medals::KoreaCampaign,
+        Medals::Longevity,
+    ]
+    .into_iter()
+    .zip(f.medals)
+    .filter_map(|(medal, has)| has.then_some(medal))
+    .collect();
+
+    Some(Logbook {
+        name: f.name,
+        callsign: f.callsign,
+        password: f.password,
+        commissioned: f.commissioned,
+        options_file: f.options_file.into(),
+        flight_hours: f.flight_hours,
+        ace_factor: f.ace_factor,
+        rank: Rank::try_from(f.rank).ok()?,
+        dogfight_stats: DogfightStats {
+            matches_won: w,
+            matches_lost: l,
+            matches_won_versus_humans: wh,
+            matches_lost_versus_humans: lh,
+            kills: k,
+            killed: kd,
+            human_kills: hk,
+            killed_versus_humans: kh,
+        },
+        campaign_stats: CampaignStats {
+            games_won: gw,
+            game_lost: gl,
+            games_tied: gt,
+            missions: m,
+            total_score: f.scores[0],
+            total_mission_score: f.scores[1],
+            consecutive_missions: cm,
+            kills: ck,
+            killed: ckd,
+            human_kills: chk,
+            killed_versus_humans: ckh,
+            self_kills: sk,
+            air_to_ground_kills: agk,
+            static_kills: stk,
+            naval_kills: nk,
+            friendly_kills: fk,
+            missions_since_last_friendly_kill: msf,
+        },
+        medals,
+        picture_file: f.picture_file.into(),
+        patch_file: f.patch_file.into(),
+        personal_text: f.personal_text,
+        squadron: f.squadron,
+        voice: f.voice,
+    })
+}
+
+fuzz_target!(|fields: Fields| {
+    let Some(book) = logbook(fields) else {
+        return;
+    };
+    let Ok(bytes) = book.to_bytes() else {
+        return;
+    };
+    let parsed = Logbook::parse_bytes_with(&bytes, Validation::Strict)
+        .expect("a logbook that wrote strictly should parse strictly");
+    assert_eq!(parsed.to_bytes().unwrap(), bytes);
+    assert_eq!(
+        serde_json::to_value(&parsed).ok(),
+        serde_json::to_value(&book).ok()
+    );
+});
@@ -0,0 +1,90 @@
+#ifndef BMS_LOGCAT_H
+#define BMS_LOGCAT_H
+
+/* Generated by cbindgen from src/ffi.rs. Don't edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The size of a logbook on disk, and of the buffer [`bms_logbook_write`] needs
+ */
+#define BMS_LOGBOOK_LEN 372
+
+/**
+ * A parsed logbook. Callers only ever see pointers to it.
+ */
+typedef struct BmsLogbook BmsLogbook;
+
+/**
+ * The last error on this thread, or `NULL` if there hasn't been one.
+ *
+ * The string is valid until the next call into this library on the same thread.
+ */
+const char *bms_last_error(void);
+
+/**
+ * Parse a logbook from `len` bytes (a whole `.lbk` file).
+ *
+ * # Safety
+ *
+ * `bytes` must point to `len` readable bytes.
+ */
+struct BmsLogbook *bms_logbook_parse(const uint8_t *bytes, size_t len);
+
+/**
+ * Encrypt the logbook into `out`, which must hold at least [`BMS_LOGBOOK_LEN`] bytes.
+ *
+ * Returns how many bytes were written, or -1 on error.
+ *
+ * # Safety
+ *
+ * `book` must come from this library, and `out` must point to `out_len` writable bytes.
+ */
+ptrdiff_t bms_logbook_write(const struct BmsLogbook *book, uint8_t *out, size_t out_len);
+
+/**
+ * The logbook as JSON: the fields `bms-logcat read` gives, in snake case,
+ * but without its `format_version`.
+ * Free it with [`bms_string_free`].
+ *
+ * # Safety
+ *
+ * `book` must come from this library.
+ */
+char *bms_logbook_to_json(const struct BmsLogbook *book);
+
+/**
+ * Make a logbook from JSON, as [`bms_logbook_to_json`] gives it.
+ *
+ * Unlike `bms-logcat write`, this takes neither JSON5 nor JSON from older
+ * `format_version`s, and every field must be given.
+ *
+ * # Safety
+ *
+ * `json` must be a NUL-terminated string.
+ */
+struct BmsLogbook *bms_logbook_from_json(const char *json);
+
+/**
+ * Free a logbook from [`bms_logbook_parse`] or [`bms_logbook_from_json`].
+ *
+ * # Safety
+ *
+ * `book` must come from this library (or be `NULL`), and not be used afterwards.
+ */
+void bms_logbook_free(struct BmsLogbook *book);
+
+/**
+ * Free a string from this library.
+ *
+ * # Safety
+ *
+ * `s` must come from this library (or be `NULL`), and not be used afterwards.
+ */
+void bms_string_free(char *s);
+
+#endif  /* BMS_LOGCAT_H */
@@ -0,0 +1,98 @@
+//! The ace factor a pilot's record earns, for `calc-ace` and `edit --auto-ace`.
+//!
+//! This is the formula squadrons commonly use (and `generate` follows):
+//! one plus the margin of matches won over matches lost, from 0 for losing every match
+//! to 2 for winning every one, rounded to hundredths. Dogfight matches count if the pilot
+//! has flown any, then campaign games (where ties are neither won nor lost),
+//! and a pilot with neither is an even 1.
+
+use std::io::prelude::*;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::logbook::Logbook;
+
+/// Which record the ace factor came from
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Record {
+    Dogfight,
+    Campaign,
+    None,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Calculation {
+    pub ace_factor: f32,
+    /// What the logbook has now
+    pub current: f32,
+    pub from: Record,
+    pub won: i32,
+    pub lost: i32,
+}
+
+pub fn calculate(book: &Logbook) -> Calculation {
+    let d = &book.dogfight_stats;
+    let c = &book.campaign_stats;
+    let (from, won, lost) = if d.matches_won > 0 || d.matches_lost > 0 {
+        (Record::Dogfight, d.matches_won, d.matches_lost)
+    } else if c.games_won > 0 || c.game_lost > 0 {
+        (Record::Campaign, c.games_won, c.game_lost)
+    } else {
+        (Record::None, 0, 0)
+    };
+    Calculation {
+        ace_factor: from_matches(won as i32, lost as i32),
+        current: book.ace_factor,
+        from,
+        won: won as i32,
+        lost: lost as i32,
+    }
+}
+
+/// The ace factor for a record of matches won and lost
+pub fn from_matches(won: i32, lost: i32) -> f32 {
+    let (won, lost) = (won.max(0), lost.max(0));
+    let matches = won + lost;
+    if matches == 0 {
+        return 1.0;
+    }
+    let margin = (won - lost) as f32 / matches as f32;
+    ((1.0 + margin) * 100.0).round() / 100.0
+}
+
+pub fn write_text<W: Write>(w: &mut W, calc: &Calculation) -> Result<()> {
+    let from = match calc.from {
+        Record::Dogfight => format!("{} dogfight matches won and {} lost", calc.won, calc.lost),
+        Record::Campaign => format!("{} campaign games won and {} lost", calc.won, calc.lost),
+        Record::None => "no matches or games yet".to_owned(),
+    };
+    writeln!(
+        w,
+        "{:.2} from {from} (the logbook has {:.2})",
+        calc.ace_factor, calc.current
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rates_the_margin() {
+        assert_eq!(from_matches(0, 0), 1.0);
+        assert_eq!(from_matches(3, 0), 2.0);
+        assert_eq!(from_matches(0, 5), 0.0);
+        assert_eq!(from_matches(2, 1), 1.33);
+
+        let mut book = Logbook::default();
+        book.campaign_stats.games_won = 3;
+        book.campaign_stats.game_lost = 1;
+        let calc = calculate(&book);
+        assert_eq!((calc.from, calc.ace_factor), (Record::Campaign, 1.5));
+        book.dogfight_stats.matches_lost = 1;
+        assert_eq!(calculate(&book).ace_factor, 0.0);
+    }
+}
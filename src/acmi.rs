@@ -0,0 +1,472 @@
+//! Kills and deaths from Tacview ACMI recordings (text, or zipped `.zip.acmi`).
+//!
+//! ACMI records objects and when they're destroyed, but not who destroyed them.
+//! We credit each kill to whoever launched the weapon that went away closest to the victim
+//! around the time it died. Kills we can't pin on anyone (guns, collisions, crashes)
+//! aren't counted, though the deaths are.
+
+use std::collections::HashMap;
+use std::io::{prelude::*, Cursor};
+
+use anyhow::{Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use log::*;
+
+use crate::logbook::Logbook;
+
+/// How far apart (in seconds) a weapon's end and its victim's death can be
+const KILL_WINDOW: f64 = 3.0;
+
+/// How far from the victim (in meters) a weapon can end and still get the kill
+const KILL_RANGE: f64 = 2000.0;
+
+#[derive(Debug, Default, Clone)]
+struct Object {
+    /// Tacview's `Type` tags, e.g., `Air+FixedWing`
+    kind: String,
+    pilot: String,
+    coalition: String,
+    parent: Option<String>,
+    /// Longitude and latitude in degrees, altitude in meters
+    position: Option<(f64, f64, f64)>,
+    /// When it entered and left the recording, in seconds
+    first_seen: f64,
+    last_seen: f64,
+}
+
+impl Object {
+    fn is(&self, tag: &str) -> bool {
+        self.kind.split('+').any(|t| t.eq_ignore_ascii_case(tag))
+    }
+}
+
+/// What kind of thing was killed, as the logbook counts them
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Target {
+    Air,
+    Ground,
+    Static,
+    Naval,
+}
+
+/// A kill credited to a pilot
+#[derive(Debug)]
+pub struct Kill {
+    pub killer: String,
+    pub target: Target,
+    pub friendly: bool,
+}
+
+/// Everything we could attribute in a recording
+#[derive(Debug, Default)]
+pub struct Recording {
+    pub kills: Vec<Kill>,
+    /// Pilots whose aircraft were destroyed, once per death
+    pub deaths: Vec<String>,
+    /// Every pilot who appears in it
+    pub pilots: Vec<String>,
+    /// How long each pilot's aircraft was in the recording, in seconds
+    pub flights: Vec<(String, f64)>,
+}
+
+/// Per-pilot totals from a recording
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Tally {
+    pub air: i16,
+    pub ground: i16,
+    pub static_: i16,
+    pub naval: i16,
+    pub friendly: i16,
+    pub deaths: i16,
+}
+
+impl Recording {
+    pub fn read(path: &Utf8Path) -> Result<Self> {
+        let bytes = std::fs::read(path).with_context(|| format!("Couldn't read {path}"))?;
+        // Tacview saves zipped recordings by default.
+        let text = if bytes.starts_with(b"PK") {
+            let mut zip = zip::ZipArchive::new(Cursor::new(bytes))?;
+            let mut text = String::new();
+            zip.by_index(0)?
+                .read_to_string(&mut text)
+                .context("Recording isn't UTF-8")?;
+            text
+        } else {
+            String::from_utf8(bytes).context("Recording isn't UTF-8")?
+        };
+        Self::parse(&text).with_context(|| format!("Couldn't parse {path}"))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut objects: HashMap<String, Object> = HashMap::new();
+        // Objects that have left the recording, since killers can die before their missiles land
+        let mut gone: HashMap<String, Object> = HashMap::new();
+        let mut time = 0.0;
+        // (time, victim)
+        let mut destroyed: Vec<(f64, Object)> = Vec::new();
+        // (time, weapon)
+        let mut spent_weapons: Vec<(f64, Object)> = Vec::new();
+
+        for line in logical_lines(text.trim_start_matches('\u{feff}')) {
+            if line.is_empty() || line.starts_with("//") || line.starts_with("File") {
+                continue;
+            }
+            if let Some(t) = line.strip_prefix('#') {
+                time = t.trim().parse().context("Bad time frame")?;
+                continue;
+            }
+            if let Some(id) = line.strip_prefix('-') {
+                let id = id.trim();
+                if let Some(mut object) = objects.remove(id) {
+                    object.last_seen = time;
+                    if object.is("Weapon") {
+                        spent_weapons.push((time, object.clone()));
+                    }
+                    gone.insert(id.to_owned(), object);
+                }
+                continue;
+            }
+
+            let fields = split_fields(&line);
+            let (id, properties) = match fields.split_first() {
+                Some(f) => f,
+                None => continue,
+            };
+            if id == "0" {
+                // Global properties, including events
+                for p in properties {
+                    if let Some(event) = p.strip_prefix("Event=") {
+                        let mut parts = event.split('|');
+                        if parts.next() == Some("Destroyed") {
+                            let victim = parts
+                                .next()
+                                .and_then(|v| objects.get(v).or_else(|| gone.get(v)));
+                            if let Some(victim) = victim {
+                                destroyed.push((time, victim.clone()));
+                            }
+                        }
+                    }
+                }
+                continue;
+            }
+
+            let object = objects.entry(id.clone()).or_insert_with(|| Object {
+                first_seen: time,
+                ..Object::default()
+            });
+            object.last_seen = time;
+            for p in properties {
+                let (key, value) = match p.split_once('=') {
+                    Some(kv) => kv,
+                    None => continue,
+                };
+                match key {
+                    "Type" => object.kind = value.to_owned(),
+                    "Pilot" => object.pilot = value.to_owned(),
+                    "Coalition" => object.coalition = value.to_owned(),
+                    "Parent" => object.parent = Some(value.to_owned()),
+                    "T" => object.position = update_position(object.position, value),
+                    _ => {}
+                }
+            }
+        }
+
+        let mut recording = Recording::default();
+        for o in objects.values().chain(gone.values()) {
+            if !o.pilot.is_empty() && !recording.pilots.contains(&o.pilot) {
+                recording.pilots.push(o.pilot.clone());
+            }
+        }
+        recording.pilots.sort();
+        // Anything still around flew until the recording stopped.
+        for o in objects.values_mut() {
+            o.last_seen = time;
+        }
+        for o in objects.values().chain(gone.values()) {
+            if o.is("Air") && !o.pilot.is_empty() {
+                recording
+                    .flights
+                    .push((o.pilot.clone(), o.last_seen - o.first_seen));
+            }
+        }
+
+        for (when, victim) in &destroyed {
+            if victim.is("Air") && !victim.pilot.is_empty() {
+                recording.deaths.push(victim.pilot.clone());
+            }
+
+            let target = if victim.is("Air") {
+                Target::Air
+            } else if victim.is("Sea") {
+                Target::Naval
+            } else if victim.is("Static") || victim.is("Building") {
+                Target::Static
+            } else if victim.is("Ground") {
+                Target::Ground
+            } else {
+                continue;
+            };
+
+            let weapon = spent_weapons
+                .iter()
+                .filter(|(t, w)| (t - when).abs() <= KILL_WINDOW && w.parent.is_some())
+                .filter_map(|(_, w)| Some((distance(w.position?, victim.position?), w)))
+                .filter(|(d, _)| *d <= KILL_RANGE)
+                .min_by(|a, b| a.0.total_cmp(&b.0))
+                .map(|(_, w)| w);
+            let killer = weapon
+                .and_then(|w| w.parent.as_deref())
+                .and_then(|parent| objects.get(parent).or_else(|| gone.get(parent)));
+            match killer {
+                Some(killer) if !killer.pilot.is_empty() => {
+                    trace!("{} killed a {} at {when}s", killer.pilot, victim.kind);
+                    recording.kills.push(Kill {
+                        killer: killer.pilot.clone(),
+                        target,
+                        friendly: !victim.coalition.is_empty()
+                            && victim.coalition == killer.coalition,
+                    });
+                }
+                _ => debug!("Couldn't tell who killed a {} at {when}s", victim.kind),
+            }
+        }
+        Ok(recording)
+    }
+
+    /// Hours the pilot spent flying in the recording (matching callsigns ignoring case)
+    pub fn hours(&self, callsign: &str) -> f64 {
+        let seconds: f64 = self
+            .flights
+            .iter()
+            .filter(|(pilot, _)| pilot.eq_ignore_ascii_case(callsign))
+            .map(|(_, seconds)| seconds)
+            .sum();
+        seconds / 3600.0
+    }
+
+    /// Add up a pilot's kills and deaths (matching callsigns ignoring case).
+    pub fn tally(&self, callsign: &str) -> Tally {
+        let mut tally = Tally::default();
+        for kill in self
+            .kills
+            .iter()
+            .filter(|k| k.killer.eq_ignore_ascii_case(callsign))
+        {
+            let count = if kill.friendly {
+                &mut tally.friendly
+            } else {
+                match kill.target {
+                    Target::Air => &mut tally.air,
+                    Target::Ground => &mut tally.ground,
+                    Target::Static => &mut tally.static_,
+                    Target::Naval => &mut tally.naval,
+                }
+            };
+            *count = count.saturating_add(1);
+        }
+        let deaths = self
+            .deaths
+            .iter()
+            .filter(|d| d.eq_ignore_ascii_case(callsign))
+            .count();
+        tally.deaths = deaths.try_into().unwrap_or(i16::MAX);
+        tally
+    }
+}
+
+/// Every recording (`*.acmi`) in the directory, sorted by path
+pub fn recordings_in(dir: &Utf8Path) -> Result<Vec<Utf8PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in dir
+        .read_dir_utf8()
+        .with_context(|| format!("Couldn't read {dir}"))?
+    {
+        let path = entry
+            .with_context(|| format!("Couldn't read {dir}"))?
+            .into_path();
+        if path.as_str().to_ascii_lowercase().ends_with(".acmi") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Which counters a recording updates
+#[derive(clap::ValueEnum, Debug, Copy, Clone)]
+pub enum Stats {
+    Dogfight,
+    Campaign,
+}
+
+/// Add a pilot's tally to their logbook.
+///
+/// Dogfight stats only count air kills and deaths.
+/// Campaign stats also count the recording as a mission.
+/// Counters stop at the most the logbook can hold rather than wrapping around.
+pub fn apply(book: &mut Logbook, tally: &Tally, stats: Stats) {
+    let add = |count: &mut i16, n: i16| *count = count.saturating_add(n);
+    match stats {
+        Stats::Dogfight => {
+            let d = &mut book.dogfight_stats;
+            add(&mut d.kills, tally.air);
+            add(&mut d.killed, tally.deaths);
+        }
+        Stats::Campaign => {
+            let c = &mut book.campaign_stats;
+            add(&mut c.missions, 1);
+            add(&mut c.kills, tally.air);
+            add(&mut c.air_to_ground_kills, tally.ground);
+            add(&mut c.static_kills, tally.static_);
+            add(&mut c.naval_kills, tally.naval);
+            add(&mut c.friendly_kills, tally.friendly);
+            add(&mut c.killed, tally.deaths);
+        }
+    }
+}
+
+/// Join lines ending in `\` with the next.
+fn logical_lines(text: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for line in text.lines() {
+        match line.strip_suffix('\\') {
+            Some(continued) => {
+                current.push_str(continued);
+                current.push('\n');
+            }
+            None => {
+                current.push_str(line);
+                lines.push(std::mem::take(&mut current).trim().to_owned());
+            }
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Split a line on the commas that aren't escaped as `\,`.
+fn split_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => current.extend(chars.next()),
+            ',' => fields.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    fields.push(current);
+    fields
+}
+
+/// Positions are `lon|lat|alt|...`; blank parts keep their last value.
+fn update_position(last: Option<(f64, f64, f64)>, value: &str) -> Option<(f64, f64, f64)> {
+    let mut parts = value.split('|');
+    let (last_lon, last_lat, last_alt) = match last {
+        Some((lon, lat, alt)) => (Some(lon), Some(lat), Some(alt)),
+        None => (None, None, None),
+    };
+    let mut next = |last: Option<f64>| match parts.next() {
+        Some(p) if !p.is_empty() => p.parse().ok().or(last),
+        _ => last,
+    };
+    Some((next(last_lon)?, next(last_lat)?, next(last_alt)?))
+}
+
+/// Rough distance in meters between two `(lon, lat, alt)` positions
+fn distance(a: (f64, f64, f64), b: (f64, f64, f64)) -> f64 {
+    const EARTH_RADIUS: f64 = 6_371_000.0;
+    let mean_lat = ((a.1 + b.1) / 2.0).to_radians();
+    let dx = (b.0 - a.0).to_radians() * mean_lat.cos() * EARTH_RADIUS;
+    let dy = (b.1 - a.1).to_radians() * EARTH_RADIUS;
+    let dz = b.2 - a.2;
+    (dx * dx + dy * dy + dz * dz).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Viper shoots down a bandit with a missile and bombs a tank, then lands;
+    /// a bomb from a second pass lands on a friendly bunker after Viper's gone,
+    /// and a stray missile ends far from anything.
+    const RECORDING: &str = "\u{feff}FileType=text/acmi/tacview
+FileVersion=2.2
+0,ReferenceTime=2024-01-01T00:00:00Z
+#0
+1,T=0|0|1000,Type=Air+FixedWing,Pilot=Viper,Coalition=Allies
+2,T=0.01|0|1000,Type=Air+FixedWing,Pilot=Bandit\\, Jr.,Coalition=Enemies
+3,T=0.5|0|0,Type=Ground+Heavy+Armor+Vehicle+Tank,Coalition=Enemies
+6,T=0.6|0|0,Type=Ground+Static+Bunker,Coalition=Allies
+// A comment
+#10
+4,T=0|0|1000,Type=Weapon+Missile,Parent=1
+5,T=0.5|0|0,Type=Weapon+Bomb,Parent=1
+7,T=0.6|0|0,Type=Weapon+Bomb,Parent=1
+8,T=0|0|1000,Type=Weapon+Missile,Parent=1
+#20
+4,T=0.01||
+-4
+0,Event=Destroyed|2|
+-2
+#30
+-5
+0,Event=Destroyed|3|
+8,T=1|1|
+-8
+-1
+#31
+-7
+0,Event=Destroyed|6|
+#3630
+0,Comment=Still recording\\
+an hour later
+";
+
+    #[test]
+    fn credits_kills_to_whoever_launched_the_weapon() {
+        let recording = Recording::parse(RECORDING).unwrap();
+        assert_eq!(recording.pilots, ["Bandit, Jr.", "Viper"]);
+        assert_eq!(recording.deaths, ["Bandit, Jr."]);
+        assert_eq!(
+            recording.tally("viper"),
+            Tally {
+                air: 1,
+                ground: 1,
+                friendly: 1,
+                ..Tally::default()
+            }
+        );
+        assert_eq!(
+            recording.tally("Bandit, Jr."),
+            Tally {
+                deaths: 1,
+                ..Tally::default()
+            }
+        );
+        assert_eq!(recording.hours("Viper"), 30.0 / 3600.0);
+        assert!(Recording::parse("#soon").is_err());
+    }
+
+    #[test]
+    fn stops_counting_at_the_limit() {
+        let mut book = Logbook::default();
+        book.campaign_stats.kills = i16::MAX - 1;
+        book.campaign_stats.missions = i16::MAX;
+        let tally = Tally {
+            air: 5,
+            deaths: 1,
+            ..Tally::default()
+        };
+        apply(&mut book, &tally, Stats::Campaign);
+        assert_eq!(book.campaign_stats.kills, i16::MAX);
+        assert_eq!(book.campaign_stats.missions, i16::MAX);
+        assert_eq!(book.campaign_stats.killed, 1);
+        apply(&mut book, &tally, Stats::Dogfight);
+        assert_eq!(book.dogfight_stats.kills, 5);
+    }
+}
@@ -0,0 +1,136 @@
+//! Squadron-wide totals and averages.
+
+use std::collections::BTreeMap;
+use std::io::prelude::*;
+
+use anyhow::Result;
+use enum_iterator::IntoEnumIterator;
+use serde::Serialize;
+
+use crate::batch::Entry;
+use crate::humanize;
+use crate::lang;
+use crate::logbook::Medals;
+use crate::stats::DerivedStats;
+use crate::table::{self, Format, Table};
+
+/// Totals and averages over a group of pilots.
+///
+/// Like the leaderboard, these are campaign stats.
+#[derive(Debug, Serialize)]
+pub struct Aggregate {
+    /// The squadron, when broken down by squadron
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub squadron: Option<String>,
+    pub pilots: usize,
+    pub total_hours: f32,
+    pub average_hours: Option<f32>,
+    pub total_missions: i64,
+    pub total_kills: i64,
+    pub total_killed: i64,
+    pub total_score: i64,
+    /// Mean of each pilot's kills per death, leaving out pilots with neither
+    pub average_kill_death_ratio: Option<f32>,
+    /// How many pilots have each medal
+    pub medals: BTreeMap<Medals, usize>,
+}
+
+impl Aggregate {
+    pub fn new(squadron: Option<String>, entries: &[&Entry]) -> Self {
+        let books = || entries.iter().map(|e| &e.logbook);
+        let total_hours: f32 = books().map(|b| b.flight_hours).sum();
+        let ratios: Vec<f32> = books()
+            .filter_map(|b| DerivedStats::new(b).campaign_kill_death_ratio)
+            .collect();
+        let mean = |sum: f32, n: usize| (n > 0).then(|| sum / n as f32);
+
+        let mut medals: BTreeMap<Medals, usize> =
+            Medals::into_enum_iter().map(|m| (m, 0)).collect();
+        for medal in books().flat_map(|b| &b.medals) {
+            *medals.get_mut(medal).unwrap() += 1;
+        }
+
+        Self {
+            squadron,
+            pilots: entries.len(),
+            total_hours,
+            average_hours: mean(total_hours, entries.len()),
+            total_missions: books().map(|b| b.campaign_stats.missions as i64).sum(),
+            total_kills: books().map(|b| b.campaign_stats.kills as i64).sum(),
+            total_killed: books().map(|b| b.campaign_stats.killed as i64).sum(),
+            total_score: books().map(|b| b.campaign_stats.total_score as i64).sum(),
+            average_kill_death_ratio: mean(ratios.iter().sum(), ratios.len()),
+            medals,
+        }
+    }
+}
+
+/// One aggregate of every pilot, or one per squadron (sorted by name).
+pub fn aggregate(entries: &[Entry], by_squadron: bool) -> Vec<Aggregate> {
+    if !by_squadron {
+        let all: Vec<&Entry> = entries.iter().collect();
+        return vec![Aggregate::new(None, &all)];
+    }
+    let mut groups: BTreeMap<&str, Vec<&Entry>> = BTreeMap::new();
+    for e in entries {
+        groups.entry(&e.logbook.squadron).or_default().push(e);
+    }
+    groups
+        .into_iter()
+        .map(|(name, entries)| Aggregate::new(Some(name.to_owned()), &entries))
+        .collect()
+}
+
+pub fn write<W: Write>(
+    w: &mut W,
+    aggregates: &[Aggregate],
+    format: Format,
+    pretty: bool,
+) -> Result<()> {
+    if let Format::Json = format {
+        return match aggregates {
+            [one] if one.squadron.is_none() => crate::write_json(w, one, pretty),
+            all => crate::write_json(w, &all, pretty),
+        };
+    }
+
+    let mut headers: Vec<String> = [
+        "Squadron",
+        "Pilots",
+        "Hours",
+        "Avg Hours",
+        "Missions",
+        "Kills",
+        "Killed",
+        "Score",
+        "Avg K/D",
+    ]
+    .map(String::from)
+    .into();
+    headers.extend(Medals::into_enum_iter().map(lang::medal));
+
+    let mut t = Table::new(headers);
+    for a in aggregates {
+        let squadron = match &a.squadron {
+            None => "(all)".to_owned(),
+            Some(s) if s.is_empty() => "(no squadron)".to_owned(),
+            Some(s) => s.clone(),
+        };
+        let mut row = vec![
+            squadron,
+            humanize::count(a.pilots as i64),
+            humanize::hours(a.total_hours),
+            a.average_hours
+                .map(humanize::hours)
+                .unwrap_or_else(|| table::optional(None, 1)),
+            humanize::count(a.total_missions),
+            humanize::count(a.total_kills),
+            humanize::count(a.total_killed),
+            humanize::count(a.total_score),
+            table::optional(a.average_kill_death_ratio, 2),
+        ];
+        row.extend(a.medals.values().map(|n| n.to_string()));
+        t.push(row);
+    }
+    t.write(w, format, "Totals")
+}
@@ -0,0 +1,101 @@
+//! Logbooks inside zip archives, like the profile backups squadron members share.
+//!
+//! `backup.zip!Viper.lbk` names a member anywhere a logbook path goes,
+//! and a zip given as a directory (or to `read`) stands for every `*.lbk` in it.
+
+use std::fs::File;
+use std::io::prelude::*;
+
+use anyhow::{bail, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use zip::ZipArchive;
+
+/// Split `backup.zip!Viper.lbk` into the archive and the member's name.
+pub fn split(path: &Utf8Path) -> Option<(&Utf8Path, &str)> {
+    let at = path.as_str().to_ascii_lowercase().find(".zip!")?;
+    let (zip, member) = path.as_str().split_at(at + ".zip".len());
+    Some((Utf8Path::new(zip), &member[1..]))
+}
+
+/// Is this a zip archive (rather than a member of one)?
+pub fn is_zip(path: &Utf8Path) -> bool {
+    split(path).is_none()
+        && path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("zip"))
+        && path.is_file()
+}
+
+fn open(zip: &Utf8Path) -> Result<ZipArchive<File>> {
+    let f = crate::lock::open_shared(zip)?;
+    ZipArchive::new(f).with_context(|| format!("Couldn't open {zip} as a zip archive"))
+}
+
+/// Read a member, e.g., `backup.zip!Viper.lbk`.
+///
+/// A member that isn't at that exact path can still be found by its file name
+/// (ignoring case), if only one has it.
+pub fn read(path: &Utf8Path) -> Result<Vec<u8>> {
+    let Some((zip, member)) = split(path) else {
+        bail!("{path} isn't in a zip archive");
+    };
+    let mut archive = open(zip)?;
+    let name = if archive.file_names().any(|n| n == member) {
+        member.to_owned()
+    } else {
+        let matches: Vec<String> = archive
+            .file_names()
+            .filter(|n| {
+                Utf8Path::new(n)
+                    .file_name()
+                    .is_some_and(|f| f.eq_ignore_ascii_case(member))
+            })
+            .map(str::to_owned)
+            .collect();
+        match &matches[..] {
+            [one] => one.clone(),
+            [] => bail!("{zip} has no {member}"),
+            _ => bail!("{member} could be any of {} in {zip}", matches.join(", ")),
+        }
+    };
+
+    let mut bytes = Vec::new();
+    archive
+        .by_name(&name)?
+        .read_to_end(&mut bytes)
+        .with_context(|| format!("Couldn't read {path}"))?;
+    Ok(bytes)
+}
+
+/// Every `*.lbk` in the archive, as `{zip}!{member}` paths, sorted
+pub fn logbooks_in(zip: &Utf8Path) -> Result<Vec<Utf8PathBuf>> {
+    let archive = open(zip)?;
+    let mut paths: Vec<Utf8PathBuf> = archive
+        .file_names()
+        .filter(|n| {
+            Utf8Path::new(n)
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("lbk"))
+        })
+        .map(|n| format!("{zip}!{n}").into())
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_members() {
+        assert_eq!(
+            split("backups/Squadron.ZIP!pilots/Viper.lbk".into()),
+            Some((Utf8Path::new("backups/Squadron.ZIP"), "pilots/Viper.lbk"))
+        );
+        assert_eq!(split("Viper.lbk".into()), None);
+        let member = Utf8Path::new("a.zip!Viper.lbk");
+        assert_eq!(member.extension(), Some("lbk"));
+        assert!(!is_zip(member));
+    }
+}
@@ -0,0 +1,56 @@
+//! Keeping copies of logbooks before we overwrite them.
+
+use anyhow::{Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use log::*;
+use serde::Deserialize;
+use time::{macros::format_description, OffsetDateTime};
+
+/// What to keep when replacing a logbook
+#[derive(clap::ValueEnum, Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Policy {
+    /// Nothing
+    #[default]
+    None,
+    /// The last version, as `<file>.bak`
+    Bak,
+    /// Every version, timestamped, in `.versions/<file stem>/` beside it
+    Versions,
+}
+
+/// Back up the file at `path`, if it exists, before it's replaced.
+pub fn backup(path: &Utf8Path, policy: Policy) -> Result<()> {
+    if !path.is_file() {
+        return Ok(());
+    }
+    match policy {
+        Policy::None => Ok(()),
+        Policy::Bak => {
+            let to = Utf8PathBuf::from(format!("{path}.bak"));
+            std::fs::copy(path, &to).with_context(|| format!("Couldn't back up {path} to {to}"))?;
+            debug!("Backed up {path} to {to}");
+            Ok(())
+        }
+        Policy::Versions => {
+            let dir = path.parent().unwrap_or_else(|| Utf8Path::new("."));
+            versioned(dir, path)
+        }
+    }
+}
+
+/// Keep a copy of the logbook at `path` in `{dir}/.versions/{file stem}/` before it's replaced.
+pub fn versioned(dir: &Utf8Path, path: &Utf8Path) -> Result<()> {
+    let versions = dir
+        .join(".versions")
+        .join(path.file_stem().unwrap_or_default());
+    std::fs::create_dir_all(&versions).with_context(|| format!("Couldn't create {versions}"))?;
+
+    let now = OffsetDateTime::now_utc().format(format_description!(
+        "[year][month][day]T[hour][minute][second]Z"
+    ))?;
+    let to = versions.join(format!("{now}.lbk"));
+    std::fs::copy(path, &to).with_context(|| format!("Couldn't back up {path} to {to}"))?;
+    debug!("Backed up {path} to {to}");
+    Ok(())
+}
@@ -0,0 +1,239 @@
+use std::io::prelude::*;
+
+use anyhow::{anyhow, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use indicatif::ProgressBar;
+use log::*;
+use rayon::prelude::*;
+use time::format_description::well_known::Rfc3339;
+use time::{macros::format_description, Date, OffsetDateTime};
+
+use crate::archive;
+use crate::logbook::{Logbook, ParseOptions, ParseWarning};
+use crate::progress;
+
+/// A logbook and the file it came from.
+#[derive(Debug)]
+pub struct Entry {
+    pub path: Utf8PathBuf,
+    pub logbook: Logbook,
+}
+
+/// Which logbooks directory-wide commands pick up, from `--recursive`, `--include`,
+/// `--exclude`, and `--newer-than`
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    /// Look in subdirectories too (but not hidden ones, like `serve`'s `.versions`)
+    pub recursive: bool,
+    /// Only take logbooks whose path (relative to the directory) matches one of these
+    pub include: Option<GlobSet>,
+    /// Skip logbooks whose path (relative to the directory) matches one of these
+    pub exclude: Option<GlobSet>,
+    /// Skip logbooks last modified before this
+    pub newer_than: Option<OffsetDateTime>,
+}
+
+/// Build a glob set for [`Filter`], or `None` if there aren't any globs.
+pub fn globs(globs: &[String]) -> Result<Option<GlobSet>> {
+    if globs.is_empty() {
+        return Ok(None);
+    }
+    let mut set = GlobSetBuilder::new();
+    for g in globs {
+        set.add(Glob::new(g).with_context(|| format!("Bad glob {g}"))?);
+    }
+    Ok(Some(set.build()?))
+}
+
+/// `2024-05-01` (as midnight UTC) or an RFC 3339 time, for `--newer-than`
+pub fn parse_time(s: &str) -> Result<OffsetDateTime> {
+    if let Ok(date) = Date::parse(s, format_description!("[year]-[month]-[day]")) {
+        return Ok(date.midnight().assume_utc());
+    }
+    OffsetDateTime::parse(s, &Rfc3339)
+        .map_err(|_| anyhow!("{s} isn't a date (YYYY-MM-DD) or RFC 3339 time"))
+}
+
+impl Filter {
+    fn wants(
+        &self,
+        relative: &Utf8Path,
+        modified: impl FnOnce() -> Result<OffsetDateTime>,
+    ) -> Result<bool> {
+        if self.include.as_ref().is_some_and(|i| !i.is_match(relative)) {
+            return Ok(false);
+        }
+        if self.exclude.as_ref().is_some_and(|e| e.is_match(relative)) {
+            return Ok(false);
+        }
+        match self.newer_than {
+            Some(cutoff) => Ok(modified()? >= cutoff),
+            None => Ok(true),
+        }
+    }
+}
+
+/// Find every `*.lbk` in the given directory or zip archive
+/// that `filter` lets through, sorted by path.
+pub fn logbooks_in(dir: &Utf8Path, filter: &Filter) -> Result<Vec<Utf8PathBuf>> {
+    if archive::is_zip(dir) {
+        let mut paths = Vec::new();
+        for path in archive::logbooks_in(dir)? {
+            let (_, member) = archive::split(&path).unwrap();
+            // Members' times aren't reliable, so go by the archive's.
+            let modified = || Ok(OffsetDateTime::from(dir.metadata()?.modified()?));
+            if filter.wants(member.into(), modified)? {
+                paths.push(path);
+            }
+        }
+        return Ok(paths);
+    }
+    let mut paths = Vec::new();
+    let mut dirs = vec![dir.to_owned()];
+    while let Some(next) = dirs.pop() {
+        for entry in next
+            .read_dir_utf8()
+            .with_context(|| format!("Couldn't list {next}"))?
+        {
+            let entry = entry.with_context(|| format!("Couldn't list {next}"))?;
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                if filter.recursive && !entry.file_name().starts_with('.') {
+                    dirs.push(path.to_owned());
+                }
+                continue;
+            }
+            let is_logbook = path
+                .extension()
+                .map(|e| e.eq_ignore_ascii_case("lbk"))
+                .unwrap_or(false);
+            if !is_logbook || !file_type.is_file() {
+                continue;
+            }
+            let relative = path.strip_prefix(dir).unwrap_or(path);
+            let modified = || Ok(OffsetDateTime::from(entry.metadata()?.modified()?));
+            if filter.wants(relative, modified)? {
+                paths.push(path.to_owned());
+            }
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Parse a logbook from disk.
+pub fn parse_file(path: &Utf8Path, parsing: ParseOptions) -> Result<Logbook> {
+    parse_file_with_report(path, parsing).map(|(book, _)| book)
+}
+
+/// Parse a logbook from disk, along with anything odd about it.
+pub fn parse_file_with_report(
+    path: &Utf8Path,
+    parsing: ParseOptions,
+) -> Result<(Logbook, Vec<ParseWarning>)> {
+    let bytes = read_bytes(path)?;
+    Logbook::parse_with_report(&bytes, parsing)
+        .with_context(|| crate::exit::Unparsable(format!("Couldn't parse logbook {path}")))
+}
+
+/// Read a file, or a member of a zip archive (see [`archive`]).
+pub fn read_bytes(path: &Utf8Path) -> Result<Vec<u8>> {
+    if archive::split(path).is_some() {
+        return archive::read(path);
+    }
+    let mut f = crate::lock::open_shared(path)?;
+    let mut bytes = Vec::with_capacity(crate::logbook::LOGBOOK_LEN);
+    f.read_to_end(&mut bytes)
+        .with_context(|| format!("Couldn't read {path}"))?;
+    Ok(bytes)
+}
+/// Parse every logbook in the given directory.
+///
+/// Files that fail to parse are skipped with a warning
+/// so one bad logbook doesn't sink a whole squadron's report.
+pub fn read_dir(dir: &Utf8Path, filter: &Filter, parsing: ParseOptions) -> Result<Vec<Entry>> {
+    read_paths(
+        dir,
+        logbooks_in(dir, filter)?,
+        parsing,
+        ProgressBar::hidden(),
+    )
+}
+
+/// Like [`read_dir`], but with a progress bar, for one-off commands that might
+/// have hundreds of logbooks to get through.
+pub fn read_dir_with_progress(
+    dir: &Utf8Path,
+    filter: &Filter,
+    parsing: ParseOptions,
+) -> Result<Vec<Entry>> {
+    let paths = logbooks_in(dir, filter)?;
+    let bar = progress::bar(paths.len(), "Reading");
+    read_paths(dir, paths, parsing, bar)
+}
+
+fn read_paths(
+    dir: &Utf8Path,
+    paths: Vec<Utf8PathBuf>,
+    parsing: ParseOptions,
+    bar: ProgressBar,
+) -> Result<Vec<Entry>> {
+    let total = paths.len();
+    // Parse in parallel (keeping the order), since network drives make each read slow.
+    let parsed: Vec<_> = paths
+        .into_par_iter()
+        .map(|path| {
+            let parsed = parse_file(&path, parsing.clone());
+            bar.set_message(path.file_name().unwrap_or_default().to_owned());
+            bar.inc(1);
+            (path, parsed)
+        })
+        .collect();
+    bar.finish_and_clear();
+
+    let mut entries = Vec::new();
+    let mut failures = Vec::new();
+    for (path, parsed) in parsed {
+        match parsed {
+            Ok(logbook) => entries.push(Entry { path, logbook }),
+            Err(e) => failures.push((path, e)),
+        }
+    }
+
+    // Warn once the bar's gone so the two don't draw over each other.
+    for (path, e) in &failures {
+        warn!("Skipping {path}: {e:#}");
+    }
+    if !failures.is_empty() {
+        warn!(
+            "Couldn't read {} of {total} logbooks in {dir}",
+            failures.len()
+        );
+    }
+    debug!("Read {} logbooks from {dir}", entries.len());
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters() {
+        let filter = Filter {
+            include: globs(&["*.lbk".to_owned()]).unwrap(),
+            exclude: globs(&["**/backup*/**".to_owned()]).unwrap(),
+            newer_than: Some(parse_time("2024-05-01").unwrap()),
+            ..Filter::default()
+        };
+        let new = || parse_time("2024-05-02T10:00:00Z");
+        let old = || parse_time("2024-04-30T10:00:00Z");
+        assert!(filter.wants("Viper.lbk".into(), new).unwrap());
+        assert!(filter.wants("sub/Viper.lbk".into(), new).unwrap());
+        assert!(!filter.wants("Viper.lbk".into(), old).unwrap());
+        assert!(!filter.wants("x/backup-2023/Viper.lbk".into(), new).unwrap());
+        assert!(parse_time("yesterday").is_err());
+    }
+}
@@ -0,0 +1,222 @@
+//! Build logbooks a field at a time, finding out what BMS won't take
+//! when setting it rather than when writing the logbook.
+
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use camino::Utf8PathBuf;
+
+use crate::exit::Invalid;
+use crate::logbook::*;
+
+/// Builds a [`Logbook`], checking each field as it's set,
+/// so [`LogbookBuilder::build`] can't give one [`Logbook::write`] would refuse.
+///
+/// ```
+/// # fn main() -> anyhow::Result<()> {
+/// use bms_logcat::{LogbookBuilder, Rank};
+///
+/// let book = LogbookBuilder::new("Jane Doe", "Viper")?
+///     .rank(Rank::Captain)
+///     .squadron("80th FS")?
+///     .flight_hours(120.5)?
+///     .build();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct LogbookBuilder {
+    book: Logbook,
+}
+
+impl LogbookBuilder {
+    /// A new pilot, commissioned today, whose options file is named for their callsign
+    pub fn new(name: impl Into<String>, callsign: impl Into<String>) -> Result<Self> {
+        let name = fits("name", name.into(), NAME_LEN)?;
+        let callsign = fits("callsign", callsign.into(), CALLSIGN_LEN)?;
+        Ok(Self {
+            book: Logbook::new(name, callsign, String::new())?,
+        })
+    }
+
+    /// Start from an existing logbook, checking all of it.
+    pub fn from_logbook(book: Logbook) -> Result<Self> {
+        let b = Self {
+            book: Logbook::default(),
+        };
+        b.name(book.name)?
+            .callsign(book.callsign)?
+            .password(book.password)?
+            .commissioned(book.commissioned)?
+            .options_file(book.options_file)?
+            .flight_hours(book.flight_hours)?
+            .ace_factor(book.ace_factor)?
+            .rank(book.rank)
+            .dogfight_stats(book.dogfight_stats)
+            .campaign_stats(book.campaign_stats)
+            .medals(book.medals)
+            .picture_file(book.picture_file)?
+            .patch_file(book.patch_file)?
+            .personal_text(book.personal_text)?
+            .squadron(book.squadron)?
+            .voice(book.voice)
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Result<Self> {
+        self.book.name = fits("name", name.into(), NAME_LEN)?;
+        Ok(self)
+    }
+
+    /// Set the callsign. This doesn't rename the options file.
+    pub fn callsign(mut self, callsign: impl Into<String>) -> Result<Self> {
+        self.book.callsign = fits("callsign", callsign.into(), CALLSIGN_LEN)?;
+        Ok(self)
+    }
+
+    pub fn password(mut self, password: impl Into<String>) -> Result<Self> {
+        self.book.password = fits("password", password.into(), PASSWORD_LEN)?;
+        Ok(self)
+    }
+
+    /// Set the commissioned date, which BMS writes as `MM/DD/YY`.
+    pub fn commissioned(mut self, date: impl Into<String>) -> Result<Self> {
+        self.book.commissioned = fits("commissioned", date.into(), COMM_LEN)?;
+        Ok(self)
+    }
+
+    pub fn options_file(mut self, file: impl Into<Utf8PathBuf>) -> Result<Self> {
+        self.book.options_file = fits_path("options_file", file.into(), CALLSIGN_LEN)?;
+        Ok(self)
+    }
+
+    pub fn flight_hours(mut self, hours: f32) -> Result<Self> {
+        if !hours.is_finite() || hours < 0.0 {
+            return Err(Invalid(format!("flight_hours {hours} isn't a number of hours")).into());
+        }
+        self.book.flight_hours = hours;
+        Ok(self)
+    }
+
+    pub fn ace_factor(mut self, ace_factor: f32) -> Result<Self> {
+        if !ace_factor.is_finite() {
+            return Err(Invalid(format!("ace_factor {ace_factor} isn't a number")).into());
+        }
+        self.book.ace_factor = ace_factor;
+        Ok(self)
+    }
+
+    pub fn rank(mut self, rank: Rank) -> Self {
+        self.book.rank = rank;
+        self
+    }
+
+    /// Set the rank by the number BMS stores it as, 0 (second lieutenant) and up.
+    pub fn rank_index(self, index: i32) -> Result<Self> {
+        let rank = Rank::try_from(index)
+            .map_err(|e| Invalid(format!("{} isn't a valid rank index", e.number)))?;
+        Ok(self.rank(rank))
+    }
+
+    pub fn dogfight_stats(mut self, stats: DogfightStats) -> Self {
+        self.book.dogfight_stats = stats;
+        self
+    }
+
+    pub fn campaign_stats(mut self, stats: CampaignStats) -> Self {
+        self.book.campaign_stats = stats;
+        self
+    }
+
+    /// Award a medal.
+    pub fn medal(mut self, medal: Medals) -> Self {
+        self.book.medals.insert(medal);
+        self
+    }
+
+    /// Replace the pilot's medals.
+    pub fn medals(mut self, medals: impl IntoIterator<Item = Medals>) -> Self {
+        self.book.medals = medals.into_iter().collect::<BTreeSet<_>>();
+        self
+    }
+
+    pub fn picture_file(mut self, file: impl Into<Utf8PathBuf>) -> Result<Self> {
+        self.book.picture_file = fits_path("picture_file", file.into(), FILENAME_LEN)?;
+        Ok(self)
+    }
+
+    pub fn patch_file(mut self, file: impl Into<Utf8PathBuf>) -> Result<Self> {
+        self.book.patch_file = fits_path("patch_file", file.into(), FILENAME_LEN)?;
+        Ok(self)
+    }
+
+    pub fn personal_text(mut self, text: impl Into<String>) -> Result<Self> {
+        self.book.personal_text = fits("personal_text", text.into(), PERSONAL_TEXT_LEN)?;
+        Ok(self)
+    }
+
+    pub fn squadron(mut self, squadron: impl Into<String>) -> Result<Self> {
+        // The squadron's field is a byte shorter than the others, counting its NUL.
+        self.book.squadron = fits("squadron", squadron.into(), NAME_LEN - 1)?;
+        Ok(self)
+    }
+
+    /// Set the pilot's voice, 0 through 11.
+    pub fn voice(mut self, voice: i16) -> Result<Self> {
+        if !(0..VOICES).contains(&voice) {
+            return Err(Invalid(format!("voice index {voice} isn't 0-{}", VOICES - 1)).into());
+        }
+        self.book.voice = voice;
+        Ok(self)
+    }
+
+    pub fn build(self) -> Logbook {
+        self.book
+    }
+}
+
+/// Check `s` fits in a field holding `max` bytes, and has no NUL to cut it short.
+fn fits(field: &str, s: String, max: usize) -> Result<String> {
+    if s.len() > max {
+        return Err(Invalid(format!(
+            "{field} {s} is longer than the allowed length ({max})"
+        ))
+        .into());
+    }
+    if s.contains('\0') {
+        return Err(Invalid(format!("{field} can't contain NUL characters")).into());
+    }
+    Ok(s)
+}
+
+fn fits_path(field: &str, path: Utf8PathBuf, max: usize) -> Result<Utf8PathBuf> {
+    Ok(fits(field, path.into_string(), max)?.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_logbooks_write() {
+        let book = LogbookBuilder::new("Jane Doe", "Viper")
+            .unwrap()
+            .squadron("x".repeat(NAME_LEN - 1))
+            .unwrap()
+            .voice(VOICES - 1)
+            .unwrap()
+            .medal(Medals::AirMedal)
+            .build();
+        book.to_bytes().unwrap();
+    }
+
+    #[test]
+    fn refuses_what_write_would() {
+        let b = || LogbookBuilder::new("Jane Doe", "Viper").unwrap();
+        assert!(LogbookBuilder::new("x".repeat(NAME_LEN + 1), "Viper").is_err());
+        assert!(b().squadron("x".repeat(NAME_LEN)).is_err());
+        assert!(b().password("x".repeat(PASSWORD_LEN + 1)).is_err());
+        assert!(b().voice(VOICES).is_err());
+        assert!(b().rank_index(99).is_err());
+        assert!(b().flight_hours(f32::NAN).is_err());
+    }
+}
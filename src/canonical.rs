@@ -0,0 +1,86 @@
+//! `--canonical` output: the same input always gives the same bytes,
+//! whatever version or platform produced it, for diffing and caching.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde_json::{Map, Value};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Normalize JSON output:
+///
+/// - Object keys are sorted, so new fields or reordered structs don't move anything.
+/// - Paths (fields named `file`, `path`, `dir`, or ending in `_file`) use `/`.
+/// - `commissioned` dates are zero-padded `MM/DD/YY`, as BMS writes them.
+/// - Strings have no trailing whitespace.
+pub fn canonicalize(value: Value) -> Value {
+    normalize(None, value)
+}
+
+fn normalize(key: Option<&str>, value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            let map: Map<String, Value> = entries
+                .into_iter()
+                .map(|(k, v)| {
+                    let v = normalize(Some(&k), v);
+                    (k, v)
+                })
+                .collect();
+            Value::Object(map)
+        }
+        Value::Array(values) => {
+            Value::Array(values.into_iter().map(|v| normalize(key, v)).collect())
+        }
+        Value::String(s) => {
+            let s = s.trim_end();
+            let s = match key {
+                Some("file" | "path" | "dir") => s.replace('\\', "/"),
+                Some(k) if k.ends_with("_file") => s.replace('\\', "/"),
+                Some("commissioned") => date(s),
+                _ => s.to_owned(),
+            };
+            Value::String(s)
+        }
+        v => v,
+    }
+}
+
+/// Sort object keys, leaving values exactly as they are.
+pub fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k, sort_keys(v)))
+                    .collect(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sort_keys).collect()),
+        v => v,
+    }
+}
+
+/// Zero-pad an `M/D/YY` date, leaving anything else alone.
+pub fn date(s: &str) -> String {
+    let parts: Vec<&str> = s.split('/').collect();
+    match parts[..] {
+        [m, d, y] if parts.iter().all(|p| p.parse::<u32>().is_ok()) => {
+            format!("{m:0>2}/{d:0>2}/{y:0>2}")
+        }
+        _ => s.to_owned(),
+    }
+}
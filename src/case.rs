@@ -0,0 +1,94 @@
+//! `--json-case`: the style of the keys in our JSON, for tools that expect
+//! something other than the snake_case our fields are named in.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+#[derive(clap::ValueEnum, Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Case {
+    /// `campaign_stats`
+    Snake,
+    /// `campaignStats`
+    Camel,
+    /// `CampaignStats`
+    Pascal,
+}
+
+static CASE: AtomicU8 = AtomicU8::new(Case::Snake as u8);
+
+pub fn set(case: Case) {
+    CASE.store(case as u8, Ordering::Relaxed);
+}
+
+pub fn get() -> Case {
+    match CASE.load(Ordering::Relaxed) {
+        c if c == Case::Camel as u8 => Case::Camel,
+        c if c == Case::Pascal as u8 => Case::Pascal,
+        _ => Case::Snake,
+    }
+}
+
+/// Rename every object key in `value` (snake_case, as serialized) to `case`.
+pub fn to_case(value: Value, case: Case) -> Value {
+    if case == Case::Snake {
+        return value;
+    }
+    rename_keys(value, &|k| convert(k, case))
+}
+
+/// Rename every object key in `value` back to snake_case so it deserializes.
+///
+/// Keys already in snake_case are left alone,
+/// so this is harmless on JSON written without `--json-case`.
+pub fn to_snake(value: Value) -> Value {
+    rename_keys(value, &snake)
+}
+
+fn rename_keys(value: Value, rename: &dyn Fn(&str) -> String) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| (rename(&k), rename_keys(v, rename)))
+                .collect::<Map<String, Value>>(),
+        ),
+        Value::Array(values) => {
+            Value::Array(values.into_iter().map(|v| rename_keys(v, rename)).collect())
+        }
+        v => v,
+    }
+}
+
+fn convert(key: &str, case: Case) -> String {
+    let mut out = String::with_capacity(key.len());
+    for (i, word) in key.split('_').enumerate() {
+        if i == 0 && case == Case::Camel {
+            out.push_str(word);
+            continue;
+        }
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            out.extend(first.to_uppercase());
+            out.push_str(chars.as_str());
+        }
+    }
+    out
+}
+
+/// `campaignStats` or `CampaignStats` as `campaign_stats`
+pub fn snake(key: &str) -> String {
+    let mut out = String::with_capacity(key.len() + 4);
+    for (i, c) in key.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 && !out.ends_with('_') {
+                out.push('_');
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, ensure, Result};
+use camino::Utf8Path;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use time::format_description::well_known::Rfc3339;
+
+use crate::history::Snapshot;
+
+/// Which stat to plot.
+#[derive(clap::ValueEnum, Debug, Copy, Clone)]
+pub enum Metric {
+    Hours,
+    Kills,
+    Missions,
+    Score,
+}
+
+impl Metric {
+    fn label(self) -> &'static str {
+        match self {
+            Metric::Hours => "Flight hours",
+            Metric::Kills => "Kills",
+            Metric::Missions => "Missions",
+            Metric::Score => "Score",
+        }
+    }
+
+    fn of(self, s: &Snapshot) -> f64 {
+        match self {
+            Metric::Hours => s.flight_hours as f64,
+            Metric::Kills => s.kills as f64,
+            Metric::Missions => s.missions as f64,
+            Metric::Score => s.total_score as f64,
+        }
+    }
+}
+
+/// One pilot's line on the chart, with X in days since the Unix epoch.
+pub struct Series {
+    pub label: String,
+    pub points: Vec<(f64, f64)>,
+}
+
+const SECONDS_PER_DAY: f64 = 86400.0;
+
+/// Plot a pilot's snapshots, either as running totals
+/// or (if `weekly` is set) how much they gained each week.
+pub fn series(label: &str, snapshots: &[Snapshot], metric: Metric, weekly: bool) -> Result<Series> {
+    let mut points = Vec::with_capacity(snapshots.len());
+    for s in snapshots {
+        let taken = time::OffsetDateTime::parse(&s.taken_at, &Rfc3339)?;
+        let day = taken.unix_timestamp() as f64 / SECONDS_PER_DAY;
+        points.push((day, metric.of(s)));
+    }
+
+    if weekly {
+        points = per_week(&points);
+    }
+
+    Ok(Series {
+        label: label.to_owned(),
+        points,
+    })
+}
+
+/// Turn running totals into per-week gains, plotted at the start of each week.
+fn per_week(points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    // The Unix epoch was a Thursday; shift so weeks start on Monday.
+    let week_of = |day: f64| ((day + 3.0) / 7.0).floor() as i64;
+
+    // Last total seen each week
+    let mut totals: BTreeMap<i64, f64> = BTreeMap::new();
+    for (day, value) in points {
+        totals.insert(week_of(*day), *value);
+    }
+
+    let mut previous = points.first().map(|(_, v)| *v).unwrap_or_default();
+    totals
+        .into_iter()
+        .map(|(week, total)| {
+            let gained = total - previous;
+            previous = total;
+            ((week * 7 - 3) as f64, gained)
+        })
+        .collect()
+}
+
+/// Render the chart as SVG.
+pub fn render_svg(series: &[Series], metric: Metric, weekly: bool) -> Result<String> {
+    let mut svg = String::new();
+    {
+        let root = SVGBackend::with_string(&mut svg, SIZE).into_drawing_area();
+        draw(&root, series, metric, weekly)?;
+        root.present()
+            .map_err(|e| anyhow!("Couldn't render chart: {e}"))?;
+    }
+    Ok(svg)
+}
+
+/// Render the chart as a PNG at the given path.
+pub fn render_png(path: &Utf8Path, series: &[Series], metric: Metric, weekly: bool) -> Result<()> {
+    let root = BitMapBackend::new(path.as_std_path(), SIZE).into_drawing_area();
+    draw(&root, series, metric, weekly)?;
+    root.present()
+        .map_err(|e| anyhow!("Couldn't write chart to {path}: {e}"))?;
+    Ok(())
+}
+
+const SIZE: (u32, u32) = (1024, 600);
+
+fn draw<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    series: &[Series],
+    metric: Metric,
+    weekly: bool,
+) -> Result<()> {
+    let points = || series.iter().flat_map(|s| s.points.iter());
+    ensure!(points().next().is_some(), "No snapshots to chart");
+
+    let (mut x_min, mut x_max, mut y_min, mut y_max) = points().fold(
+        (f64::MAX, f64::MIN, f64::MAX, f64::MIN),
+        |(x0, x1, y0, y1), (x, y)| (x0.min(*x), x1.max(*x), y0.min(*y), y1.max(*y)),
+    );
+    // Give single points and flat lines some room.
+    if x_max - x_min < 7.0 {
+        x_min -= 3.5;
+        x_max += 3.5;
+    }
+    y_min = y_min.min(0.0);
+    if y_max - y_min < 1.0 {
+        y_max = y_min + 1.0;
+    }
+
+    let err = |e: DrawingAreaErrorKind<DB::ErrorType>| anyhow!("Couldn't render chart: {e}");
+
+    root.fill(&WHITE).map_err(err)?;
+
+    let caption = if weekly {
+        format!("{} per week", metric.label())
+    } else {
+        metric.label().to_owned()
+    };
+    let mut chart = ChartBuilder::on(root)
+        .caption(caption, ("sans-serif", 24))
+        .margin(16)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(x_min..x_max, y_min..y_max * 1.05)
+        .map_err(err)?;
+
+    chart
+        .configure_mesh()
+        .x_labels(8)
+        .x_label_formatter(&|day| format_day(*day))
+        .y_desc(metric.label())
+        .draw()
+        .map_err(err)?;
+
+    for (i, s) in series.iter().enumerate() {
+        let color = Palette99::pick(i).to_rgba();
+        chart
+            .draw_series(LineSeries::new(
+                s.points.iter().copied(),
+                color.stroke_width(2),
+            ))
+            .map_err(err)?
+            .label(s.label.clone())
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+        chart
+            .draw_series(s.points.iter().map(|p| Circle::new(*p, 3, color.filled())))
+            .map_err(err)?;
+    }
+
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()
+        .map_err(err)?;
+
+    Ok(())
+}
+
+fn format_day(day: f64) -> String {
+    time::OffsetDateTime::from_unix_timestamp((day * SECONDS_PER_DAY) as i64)
+        .ok()
+        .and_then(|t| {
+            t.format(time::macros::format_description!("[year]-[month]-[day]"))
+                .ok()
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,27 @@
+//! `--output clipboard`: text output goes on the system clipboard instead of a file,
+//! e.g., to paste a pilot's stats straight into Discord.
+
+use std::io::prelude::*;
+
+/// What to give as `--output` for the clipboard
+pub const NAME: &str = "clipboard";
+
+/// Collects output, putting it all on the clipboard when flushed.
+#[derive(Default)]
+pub struct Clipboard {
+    text: Vec<u8>,
+}
+
+impl Write for Clipboard {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.text.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        let text = String::from_utf8_lossy(&self.text).into_owned();
+        arboard::Clipboard::new()
+            .and_then(|mut c| c.set_text(text))
+            .map_err(|e| std::io::Error::other(format!("Couldn't copy to the clipboard: {e}")))
+    }
+}
@@ -0,0 +1,248 @@
+//! `compare`: pilots side by side, one column each and a row per stat,
+//! with the best in each row marked.
+
+use std::io::prelude::*;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::humanize;
+use crate::logbook::Logbook;
+use crate::stats::DerivedStats;
+use crate::table::{Format, Table};
+
+/// Which way is better for a stat
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Better {
+    Higher,
+    Lower,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Shown {
+    Count,
+    Hours,
+    Ratio,
+    Percent,
+}
+
+/// A stat pilots can be compared by
+pub struct Stat {
+    pub name: &'static str,
+    pub better: Better,
+    shown: Shown,
+    value: fn(&Logbook, &DerivedStats) -> Option<f64>,
+}
+
+impl Stat {
+    /// The pilot's value, if it means anything for them (e.g., no K/D without kills or deaths)
+    pub fn of(&self, book: &Logbook) -> Option<f64> {
+        (self.value)(book, &DerivedStats::new(book))
+    }
+
+    pub fn show(&self, value: Option<f64>) -> String {
+        let Some(v) = value else {
+            return "-".to_owned();
+        };
+        match self.shown {
+            Shown::Count => humanize::count(v as i64),
+            Shown::Hours => humanize::hours(v as f32),
+            Shown::Ratio => format!("{v:.2}"),
+            Shown::Percent => format!("{v:.1}%"),
+        }
+    }
+}
+
+const fn stat(
+    name: &'static str,
+    better: Better,
+    shown: Shown,
+    value: fn(&Logbook, &DerivedStats) -> Option<f64>,
+) -> Stat {
+    Stat {
+        name,
+        better,
+        shown,
+        value,
+    }
+}
+
+/// An `f32` as the `f64` nearest what it prints as,
+/// so 162.9 hours is 162.9 in JSON rather than 162.89999389648438
+fn widen(v: f32) -> f64 {
+    v.to_string().parse().unwrap_or(v as f64)
+}
+
+/// The stats `compare` shows, in order
+pub const STATS: &[Stat] = &[
+    stat("Flight hours", Better::Higher, Shown::Hours, |b, _| {
+        Some(widen(b.flight_hours))
+    }),
+    stat("Ace factor", Better::Higher, Shown::Ratio, |b, _| {
+        Some(widen(b.ace_factor))
+    }),
+    stat("Medals", Better::Higher, Shown::Count, |b, _| {
+        Some(b.medals.len() as f64)
+    }),
+    stat("Missions", Better::Higher, Shown::Count, |b, _| {
+        Some(b.campaign_stats.missions as f64)
+    }),
+    stat("Score", Better::Higher, Shown::Count, |b, _| {
+        Some(b.campaign_stats.total_score as f64)
+    }),
+    stat("Avg mission score", Better::Higher, Shown::Ratio, |_, d| {
+        d.average_mission_score.map(widen)
+    }),
+    stat("Campaign win %", Better::Higher, Shown::Percent, |_, d| {
+        d.campaign_win_percent.map(widen)
+    }),
+    stat("Kills", Better::Higher, Shown::Count, |b, _| {
+        Some(b.campaign_stats.kills as f64)
+    }),
+    stat("Human kills", Better::Higher, Shown::Count, |b, _| {
+        Some(b.campaign_stats.human_kills as f64)
+    }),
+    stat("Killed", Better::Lower, Shown::Count, |b, _| {
+        Some(b.campaign_stats.killed as f64)
+    }),
+    stat("K/D", Better::Higher, Shown::Ratio, |_, d| {
+        d.campaign_kill_death_ratio.map(widen)
+    }),
+    stat("Kills per mission", Better::Higher, Shown::Ratio, |_, d| {
+        d.kills_per_mission.map(widen)
+    }),
+    stat("Ground kills", Better::Higher, Shown::Count, |b, _| {
+        Some(b.campaign_stats.air_to_ground_kills as f64)
+    }),
+    stat("Static kills", Better::Higher, Shown::Count, |b, _| {
+        Some(b.campaign_stats.static_kills as f64)
+    }),
+    stat("Naval kills", Better::Higher, Shown::Count, |b, _| {
+        Some(b.campaign_stats.naval_kills as f64)
+    }),
+    stat("Friendly kills", Better::Lower, Shown::Count, |b, _| {
+        Some(b.campaign_stats.friendly_kills as f64)
+    }),
+    stat("Self kills", Better::Lower, Shown::Count, |b, _| {
+        Some(b.campaign_stats.self_kills as f64)
+    }),
+    stat("Dogfight wins", Better::Higher, Shown::Count, |b, _| {
+        Some(b.dogfight_stats.matches_won as f64)
+    }),
+    stat("Dogfight win %", Better::Higher, Shown::Percent, |_, d| {
+        d.dogfight_win_percent.map(widen)
+    }),
+    stat("Dogfight kills", Better::Higher, Shown::Count, |b, _| {
+        Some(b.dogfight_stats.kills as f64)
+    }),
+    stat("Dogfight K/D", Better::Higher, Shown::Ratio, |_, d| {
+        d.dogfight_kill_death_ratio.map(widen)
+    }),
+];
+
+/// One stat for every pilot compared
+#[derive(Debug, Serialize)]
+pub struct Row {
+    pub stat: &'static str,
+    /// In the same order as the pilots
+    pub values: Vec<Option<f64>>,
+    /// Callsigns of whoever has the best value, none if everyone's tied
+    pub best: Vec<String>,
+    #[serde(skip)]
+    shown: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Comparison {
+    pub pilots: Vec<String>,
+    pub rows: Vec<Row>,
+}
+
+pub fn compare(books: &[Logbook]) -> Comparison {
+    let rows = STATS
+        .iter()
+        .map(|stat| {
+            let values: Vec<Option<f64>> = books.iter().map(|b| stat.of(b)).collect();
+            let best = best(&values, stat.better);
+            Row {
+                stat: stat.name,
+                best: best.iter().map(|&i| books[i].callsign.clone()).collect(),
+                shown: values
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| {
+                        let marker = if best.contains(&i) { " *" } else { "" };
+                        stat.show(*v) + marker
+                    })
+                    .collect(),
+                values,
+            }
+        })
+        .collect();
+    Comparison {
+        pilots: books.iter().map(|b| b.callsign.clone()).collect(),
+        rows,
+    }
+}
+
+/// Indexes of the best values, or none if they're all the same
+fn best(values: &[Option<f64>], better: Better) -> Vec<usize> {
+    let known = values.iter().flatten().copied();
+    let top = match better {
+        Better::Higher => known.reduce(f64::max),
+        Better::Lower => known.reduce(f64::min),
+    };
+    let Some(top) = top else {
+        return Vec::new();
+    };
+    let winners: Vec<usize> = (0..values.len())
+        .filter(|&i| values[i] == Some(top))
+        .collect();
+    if winners.len() == values.len() {
+        Vec::new()
+    } else {
+        winners
+    }
+}
+
+pub fn write<W: Write>(
+    w: &mut W,
+    comparison: &Comparison,
+    format: Format,
+    pretty: bool,
+) -> Result<()> {
+    if let Format::Json = format {
+        return crate::write_json(w, comparison, pretty);
+    }
+
+    let mut t =
+        Table::new(std::iter::once("Stat").chain(comparison.pilots.iter().map(|p| p.as_str())));
+    for row in &comparison.rows {
+        t.push(
+            std::iter::once(row.stat.to_owned())
+                .chain(row.shown.iter().cloned())
+                .collect(),
+        );
+    }
+    t.write(w, format, "Comparison")?;
+    if let Format::Table = format {
+        writeln!(w, "\n* best")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marks_the_best() {
+        assert_eq!(
+            best(&[Some(1.0), Some(3.0), Some(3.0)], Better::Higher),
+            [1, 2]
+        );
+        assert_eq!(best(&[Some(1.0), None, Some(3.0)], Better::Lower), [0]);
+        assert!(best(&[Some(2.0), Some(2.0)], Better::Higher).is_empty());
+        assert!(best(&[None, None], Better::Higher).is_empty());
+    }
+}
@@ -0,0 +1,76 @@
+//! Defaults from a config file, so frequent users stop repeating flags.
+//!
+//! Flags given on the command line win over the file. For example:
+//!
+//! ```toml
+//! install = "4.37"         # or the install's directory, like --install
+//! output-dir = "D:/pilots" # where relative --output paths go
+//! backup = "bak"           # none, bak, or versions, like --backup
+//! validate = false         # like always passing --no-validate
+//! json-case = "camel"      # snake, camel, or pascal, like --json-case
+//! lang = "de"              # en, de, fr, or es, like --lang
+//!
+//! [policy]                 # what lint flags, like --policy (see lint.rs)
+//! max-hours = 3000
+//!
+//! [redact-profiles.public] # what --redact-profile blanks (see redact.rs)
+//! fields = ["password", "name", "personal_text"]
+//!
+//! [keys]                   # obfuscation for Falcon-derived sims (see keys.rs)
+//! master-key = "Falcon is your Master"
+//! ```
+
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use log::*;
+use serde::Deserialize;
+
+use crate::{backup, case, keys, lang, lint, redact};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub install: Option<String>,
+    pub output_dir: Option<Utf8PathBuf>,
+    pub backup: Option<backup::Policy>,
+    pub validate: Option<bool>,
+    pub json_case: Option<case::Case>,
+    pub lang: Option<lang::Lang>,
+    pub policy: Option<lint::Policy>,
+    pub signing_key: Option<String>,
+    pub redact_profiles: BTreeMap<String, redact::Profile>,
+    pub keys: Option<keys::Config>,
+}
+
+/// `config.toml` in the user's config directory, e.g., `~/.config/bms-logcat/`
+pub fn default_path() -> Result<Utf8PathBuf> {
+    let dir = dirs::config_dir().ok_or_else(|| anyhow!("Couldn't find a config directory"))?;
+    let dir = Utf8PathBuf::try_from(dir)?;
+    Ok(dir.join("bms-logcat").join("config.toml"))
+}
+
+impl Config {
+    /// Load the given config file, or the default one if it exists.
+    pub fn load(path: Option<&Utf8Path>) -> Result<Self> {
+        let path = match path {
+            Some(p) => p.to_owned(),
+            None => match default_path() {
+                Ok(p) if p.exists() => p,
+                _ => return Ok(Self::default()),
+            },
+        };
+        let text =
+            std::fs::read_to_string(&path).with_context(|| format!("Couldn't read {path}"))?;
+        let config: Self = toml::from_str(&text)
+            .with_context(|| crate::exit::Unparsable(format!("Couldn't parse {path}")))?;
+        if let Some(policy) = &config.policy {
+            policy
+                .check()
+                .with_context(|| crate::exit::Invalid(format!("Bad policy in {path}")))?;
+        }
+        debug!("Loaded {path}: {config:?}");
+        Ok(config)
+    }
+}
@@ -0,0 +1,248 @@
+//! Mission results from debriefing text, like BMS shows after a mission.
+//!
+//! BMS doesn't document a format for these, so we read `Label: value` lines,
+//! matching labels loosely (ignoring case, spaces, and punctuation)
+//! and skipping any we don't know. Each `Pilot:` line starts a new pilot's results,
+//! so one file can cover a whole flight.
+
+use anyhow::{anyhow, bail, Context, Result};
+use log::*;
+
+use crate::logbook::Logbook;
+
+/// One pilot's results from a mission
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Debrief {
+    pub pilot: Option<String>,
+    pub score: i32,
+    pub air_kills: i16,
+    pub ground_kills: i16,
+    pub static_kills: i16,
+    pub naval_kills: i16,
+    pub friendly_kills: i16,
+    pub deaths: i16,
+    pub hours: f32,
+}
+
+/// Parse every pilot's results in the text.
+pub fn parse(text: &str) -> Result<Vec<Debrief>> {
+    let mut debriefs: Vec<Debrief> = Vec::new();
+    let mut current = Debrief::default();
+    let mut recognized = false;
+
+    for (i, line) in text.lines().enumerate() {
+        let (label, value) = match line.split_once(':') {
+            Some((l, v)) => (normalize(l), v.trim()),
+            None => continue,
+        };
+        let context = || format!("Line {}: bad value for {}", i + 1, line.trim());
+        match label.as_str() {
+            "pilot" | "callsign" => {
+                if current.pilot.is_some() || recognized {
+                    debriefs.push(std::mem::take(&mut current));
+                }
+                current.pilot = Some(value.to_owned());
+            }
+            "score" | "missionscore" | "points" => {
+                current.score = number(value).with_context(context)?
+            }
+            "airkills" | "aakills" | "airtoairkills" => {
+                current.air_kills = number(value).with_context(context)?
+            }
+            "groundkills" | "agkills" | "airtogroundkills" => {
+                current.ground_kills = number(value).with_context(context)?
+            }
+            "statickills" => current.static_kills = number(value).with_context(context)?,
+            "navalkills" | "shipkills" => {
+                current.naval_kills = number(value).with_context(context)?
+            }
+            "friendlykills" | "fratricides" => {
+                current.friendly_kills = number(value).with_context(context)?
+            }
+            "deaths" | "killed" => current.deaths = number(value).with_context(context)?,
+            "status" => {
+                let status = normalize(value);
+                if matches!(
+                    status.as_str(),
+                    "kia" | "killed" | "killedinaction" | "dead"
+                ) {
+                    current.deaths = 1;
+                }
+            }
+            "flighttime" | "timeinair" | "flighthours" | "hours" => {
+                current.hours = hours(value).with_context(context)?
+            }
+            other => {
+                trace!("Ignoring {other}");
+                continue;
+            }
+        }
+        recognized = true;
+    }
+
+    if !recognized {
+        bail!("Didn't find any mission results");
+    }
+    debriefs.push(current);
+    Ok(debriefs)
+}
+
+/// Find the given pilot's results, or the only results if they don't name a pilot.
+pub fn for_pilot(debriefs: Vec<Debrief>, callsign: &str) -> Result<Debrief> {
+    if let [only] = &debriefs[..] {
+        if only.pilot.is_none() {
+            return Ok(only.clone());
+        }
+    }
+    let names: Vec<String> = debriefs.iter().filter_map(|d| d.pilot.clone()).collect();
+    debriefs
+        .into_iter()
+        .find(|d| {
+            d.pilot
+                .as_deref()
+                .map(|p| p.eq_ignore_ascii_case(callsign))
+                .unwrap_or(false)
+        })
+        .ok_or_else(|| anyhow!("No results for {callsign} (found {})", names.join(", ")))
+}
+
+/// Add a mission's results to the campaign stats and flight hours.
+///
+/// Counters stop at the most the logbook can hold rather than wrapping around.
+pub fn apply(book: &mut Logbook, debrief: &Debrief) {
+    book.flight_hours += debrief.hours;
+
+    let c = &mut book.campaign_stats;
+    c.missions = c.missions.saturating_add(1);
+    c.total_mission_score = c.total_mission_score.saturating_add(debrief.score);
+    c.total_score = c.total_score.saturating_add(debrief.score);
+    c.kills = c.kills.saturating_add(debrief.air_kills);
+    c.air_to_ground_kills = c.air_to_ground_kills.saturating_add(debrief.ground_kills);
+    c.static_kills = c.static_kills.saturating_add(debrief.static_kills);
+    c.naval_kills = c.naval_kills.saturating_add(debrief.naval_kills);
+    c.friendly_kills = c.friendly_kills.saturating_add(debrief.friendly_kills);
+    c.killed = c.killed.saturating_add(debrief.deaths);
+    if debrief.deaths > 0 {
+        c.consecutive_missions = 0;
+    } else {
+        c.consecutive_missions = c.consecutive_missions.saturating_add(1);
+    }
+    if debrief.friendly_kills > 0 {
+        c.missions_since_last_friendly_kill = 0;
+    } else {
+        c.missions_since_last_friendly_kill = c.missions_since_last_friendly_kill.saturating_add(1);
+    }
+}
+
+/// Lowercase, keeping only letters and digits
+fn normalize(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn number<T: std::str::FromStr>(s: &str) -> Result<T> {
+    // Allow things like "+45" or "2 (1 shared)"
+    let digits = s.trim_start_matches('+');
+    let end = digits
+        .find(|c: char| !(c.is_ascii_digit() || c == '-'))
+        .unwrap_or(digits.len());
+    digits[..end]
+        .parse()
+        .map_err(|_| anyhow!("Expected a number, not {s}"))
+}
+
+/// Hours as a decimal (`1.5`) or `H:MM[:SS]`
+fn hours(s: &str) -> Result<f32> {
+    let parts: Vec<&str> = s.split(':').map(str::trim).collect();
+    let bad = || anyhow!("Expected hours like 1.5 or 1:30, not {s}");
+    match parts[..] {
+        [h] => h.parse().map_err(|_| bad()),
+        [h, m] | [h, m, _] => {
+            let h: f32 = h.parse().map_err(|_| bad())?;
+            let m: f32 = m.parse().map_err(|_| bad())?;
+            let s: f32 = parts
+                .get(2)
+                .map_or(Ok(0.0), |s| s.parse())
+                .map_err(|_| bad())?;
+            Ok(h + m / 60.0 + s / 3600.0)
+        }
+        _ => Err(bad()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_each_pilot() {
+        let text = "Mission: Strike on Kunsan\n\
+                    Pilot: Viper\n\
+                    Mission Score: +45\n\
+                    A/A Kills: 2 (1 shared)\n\
+                    Weather: clear\n\
+                    Flight time: 1:30\n\
+                    \n\
+                    Callsign: Ghost\n\
+                    Ground kills: 3\n\
+                    Status: KIA\n";
+        let debriefs = parse(text).unwrap();
+        assert_eq!(
+            debriefs,
+            [
+                Debrief {
+                    pilot: Some("Viper".into()),
+                    score: 45,
+                    air_kills: 2,
+                    hours: 1.5,
+                    ..Debrief::default()
+                },
+                Debrief {
+                    pilot: Some("Ghost".into()),
+                    ground_kills: 3,
+                    deaths: 1,
+                    ..Debrief::default()
+                },
+            ]
+        );
+
+        assert_eq!(
+            for_pilot(debriefs.clone(), "GHOST").unwrap().ground_kills,
+            3
+        );
+        assert!(for_pilot(debriefs, "Rattler").is_err());
+        let anonymous = parse("Score: 10").unwrap();
+        assert_eq!(for_pilot(anonymous, "Viper").unwrap().score, 10);
+
+        assert!(parse("Weather: clear").is_err());
+        assert!(parse("Score: lots").is_err());
+    }
+
+    #[test]
+    fn reads_hours() {
+        assert_eq!(hours("1.5").unwrap(), 1.5);
+        assert_eq!(hours("1:30").unwrap(), 1.5);
+        assert_eq!(hours("1:30:36").unwrap(), 1.51);
+        assert!(hours("1:2:3:4").is_err());
+        assert!(hours("an hour").is_err());
+    }
+
+    #[test]
+    fn stops_counting_at_the_limit() {
+        let mut book = Logbook::default();
+        book.campaign_stats.total_score = i32::MAX - 1;
+        book.campaign_stats.kills = i16::MAX;
+        let debrief = Debrief {
+            score: 10,
+            air_kills: 1,
+            ..Debrief::default()
+        };
+        apply(&mut book, &debrief);
+        assert_eq!(book.campaign_stats.total_score, i32::MAX);
+        assert_eq!(book.campaign_stats.total_mission_score, 10);
+        assert_eq!(book.campaign_stats.kills, i16::MAX);
+        assert_eq!(book.campaign_stats.missions, 1);
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::io::prelude::*;
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+
+/// A field whose value differs between two versions of a logbook.
+#[derive(Debug, Serialize)]
+pub struct Change {
+    /// Dotted path to the field, e.g., `campaign_stats.kills`
+    pub field: String,
+    /// The old value, or `None` if the field is new
+    pub old: Option<Value>,
+    /// The new value, or `None` if the field was removed
+    pub new: Option<Value>,
+}
+
+/// Every field that differs between `old` and `new`, in the order they're serialized.
+pub fn changes<T: Serialize + ?Sized, U: Serialize + ?Sized>(
+    old: &T,
+    new: &U,
+) -> Result<Vec<Change>> {
+    let old = flatten(&serde_json::to_value(old)?);
+    let new = flatten(&serde_json::to_value(new)?);
+
+    let old_by_field: HashMap<&str, &Value> = old.iter().map(|(f, v)| (f.as_str(), v)).collect();
+    let new_by_field: HashMap<&str, &Value> = new.iter().map(|(f, v)| (f.as_str(), v)).collect();
+
+    let mut changes = Vec::new();
+    for (field, value) in &new {
+        match old_by_field.get(field.as_str()) {
+            Some(old_value) if *old_value == value => {}
+            old_value => changes.push(Change {
+                field: field.clone(),
+                old: old_value.map(|v| (*v).clone()),
+                new: Some(value.clone()),
+            }),
+        }
+    }
+    for (field, value) in &old {
+        if !new_by_field.contains_key(field.as_str()) {
+            changes.push(Change {
+                field: field.clone(),
+                old: Some(value.clone()),
+                new: None,
+            });
+        }
+    }
+    Ok(changes)
+}
+
+/// Flatten nested objects into `(dotted.path, value)` pairs.
+///
+/// Arrays (like medals) are kept whole - they're compared as a single value.
+pub fn flatten(value: &Value) -> Vec<(String, Value)> {
+    let mut fields = Vec::new();
+    flatten_into("", value, &mut fields);
+    fields
+}
+
+fn flatten_into(prefix: &str, value: &Value, fields: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, v) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                flatten_into(&path, v, fields);
+            }
+        }
+        v => fields.push((prefix.to_owned(), v.clone())),
+    }
+}
+
+/// Print changes one per line, as `field: old -> new`.
+pub fn write_text<W: Write>(w: &mut W, changes: &[Change]) -> Result<()> {
+    if changes.is_empty() {
+        writeln!(w, "No changes")?;
+        return Ok(());
+    }
+
+    let show = |v: &Option<Value>| match v {
+        Some(v) => v.to_string(),
+        None => "(none)".to_owned(),
+    };
+    for c in changes {
+        writeln!(w, "{}: {} -> {}", c.field, show(&c.old), show(&c.new))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,307 @@
+use std::collections::BTreeMap;
+use std::io::prelude::*;
+
+use anyhow::Result;
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::Serialize;
+
+use crate::batch::{self, Entry};
+use crate::installs::Install;
+use crate::logbook::{Logbook, ParseOptions};
+use crate::progress;
+use crate::resources::{self, ArtDirs, Lookup};
+
+/// How urgently a finding needs fixing, most urgent first.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum Severity {
+    /// BMS won't load the pilot, or will lose data
+    Error,
+    /// Something's off and will probably bite later
+    Warning,
+    /// Worth knowing, but harmless
+    Note,
+}
+
+/// A problem with the setup and how to fix it.
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    pub severity: Severity,
+    /// The file or directory with the problem
+    pub subject: String,
+    pub problem: String,
+    pub fix: String,
+}
+
+impl Finding {
+    pub fn new(
+        severity: Severity,
+        subject: impl ToString,
+        problem: impl Into<String>,
+        fix: impl Into<String>,
+    ) -> Self {
+        Self {
+            severity,
+            subject: subject.to_string(),
+            problem: problem.into(),
+            fix: fix.into(),
+        }
+    }
+}
+
+/// Check the BMS installs (or directories) given, most urgent findings first.
+pub fn examine(
+    installs: &[Install],
+    dirs: &[Utf8PathBuf],
+    filter: &batch::Filter,
+    parsing: ParseOptions,
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    if installs.is_empty() && dirs.is_empty() {
+        findings.push(Finding::new(
+            Severity::Error,
+            "BMS",
+            "Couldn't find any BMS installs",
+            "Install BMS, or give the directory holding your logbooks",
+        ));
+    }
+
+    for install in installs {
+        if !install.config_dir.is_dir() {
+            findings.push(Finding::new(
+                Severity::Error,
+                &install.base_dir,
+                format!("BMS {} has no User/Config directory", install.version),
+                "Run BMS once to create it, or repair the install",
+            ));
+            continue;
+        }
+        check_dir(&install.config_dir, filter, parsing.clone(), &mut findings);
+    }
+    for dir in dirs {
+        check_dir(dir, filter, parsing.clone(), &mut findings);
+    }
+
+    findings.sort_by_key(|f| f.severity);
+    findings
+}
+
+fn check_dir(
+    dir: &Utf8Path,
+    filter: &batch::Filter,
+    parsing: ParseOptions,
+    findings: &mut Vec<Finding>,
+) {
+    let paths = match batch::logbooks_in(dir, filter) {
+        Ok(p) => p,
+        Err(e) => {
+            findings.push(Finding::new(
+                Severity::Error,
+                dir,
+                format!("{e:#}"),
+                "Check the directory exists and you can read it",
+            ));
+            return;
+        }
+    };
+
+    if paths.is_empty() {
+        findings.push(Finding::new(
+            Severity::Note,
+            dir,
+            "No logbooks here",
+            "Create a pilot in BMS or with write-default",
+        ));
+    }
+
+    let art = ArtDirs::for_config_dir(dir);
+    let mut entries = Vec::new();
+
+    let bar = progress::bar(paths.len(), "Checking");
+    for path in &paths {
+        bar.set_message(path.file_name().unwrap_or_default().to_owned());
+        bar.inc(1);
+        match std::fs::metadata(path) {
+            Ok(m) if m.len() == 0 => {
+                findings.push(Finding::new(
+                    Severity::Error,
+                    path,
+                    "Logbook is empty",
+                    "Restore it from a backup or recreate the pilot",
+                ));
+                continue;
+            }
+            _ => {}
+        }
+
+        let book = match batch::parse_file(path, parsing.clone()) {
+            Ok(b) => b,
+            Err(e) => {
+                findings.push(Finding::new(
+                    Severity::Error,
+                    path,
+                    format!("{e:#}"),
+                    "Restore it from a backup or recreate the pilot",
+                ));
+                continue;
+            }
+        };
+
+        check_logbook(path, &book, &art, findings);
+        entries.push(Entry {
+            path: path.clone(),
+            logbook: book,
+        });
+    }
+    bar.finish_and_clear();
+
+    findings.extend(conflicts(dir, &entries));
+}
+
+/// Find logbooks that claim the same callsign or options file,
+/// which BMS can't tell apart in multiplayer.
+pub fn conflicts(dir: &Utf8Path, entries: &[Entry]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut by_callsign: BTreeMap<String, Vec<&Entry>> = BTreeMap::new();
+    let mut by_options: BTreeMap<String, Vec<&Entry>> = BTreeMap::new();
+    for e in entries {
+        by_callsign
+            .entry(e.logbook.callsign.to_lowercase())
+            .or_default()
+            .push(e);
+        by_options
+            .entry(e.logbook.options_file.as_str().to_lowercase())
+            .or_default()
+            .push(e);
+    }
+    let taken: Vec<&String> = by_callsign.keys().collect();
+
+    for (callsign, claimants) in &by_callsign {
+        if claimants.len() < 2 {
+            continue;
+        }
+        let names: Vec<&str> = claimants.iter().map(|e| e.path.as_str()).collect();
+        // Keep the one whose file is named for the callsign, if any.
+        let keep = claimants
+            .iter()
+            .position(|e| {
+                e.path
+                    .file_stem()
+                    .map(|s| s.eq_ignore_ascii_case(callsign))
+                    .unwrap_or(false)
+            })
+            .unwrap_or(0);
+        let mut renames = Vec::new();
+        let mut suffix = 2;
+        for (i, e) in claimants.iter().enumerate() {
+            if i == keep {
+                continue;
+            }
+            let new = loop {
+                let candidate = format!("{}{suffix}", e.logbook.callsign);
+                suffix += 1;
+                if !taken.contains(&&candidate.to_lowercase()) {
+                    break candidate;
+                }
+            };
+            renames.push(format!("rename {} to {new}", e.path));
+        }
+        findings.push(Finding::new(
+            Severity::Warning,
+            dir,
+            format!("{callsign} is claimed by {}", names.join(", ")),
+            format!(
+                "Keep {} and {} (callsign and file name)",
+                claimants[keep].path,
+                renames.join(", ")
+            ),
+        ));
+    }
+
+    for (options_file, claimants) in &by_options {
+        if claimants.len() < 2 || options_file.is_empty() {
+            continue;
+        }
+        let names: Vec<&str> = claimants.iter().map(|e| e.path.as_str()).collect();
+        findings.push(Finding::new(
+            Severity::Warning,
+            dir,
+            format!(
+                "options_file {options_file} is shared by {}",
+                names.join(", ")
+            ),
+            "Set each one's options_file to its own callsign",
+        ));
+    }
+    findings
+}
+
+fn check_logbook(path: &Utf8Path, book: &Logbook, art: &ArtDirs, findings: &mut Vec<Finding>) {
+    let stem = path.file_stem().unwrap_or_default();
+    if !stem.eq_ignore_ascii_case(&book.callsign) {
+        findings.push(Finding::new(
+            Severity::Warning,
+            path,
+            format!("File name doesn't match callsign {}", book.callsign),
+            format!("Rename it to {}.lbk", book.callsign),
+        ));
+    }
+
+    if !book
+        .options_file
+        .as_str()
+        .eq_ignore_ascii_case(&book.callsign)
+    {
+        findings.push(Finding::new(
+            Severity::Warning,
+            path,
+            format!(
+                "options_file {} doesn't match callsign {}",
+                book.options_file, book.callsign
+            ),
+            format!("Set options_file to {}", book.callsign),
+        ));
+    }
+
+    for resource in resources::check(book, art) {
+        let (kind, file, dir) = (resource.kind, resource.file, resource.dir);
+        match resource.lookup {
+            Lookup::Found(_) => {}
+            Lookup::CaseMismatch(actual) => findings.push(Finding::new(
+                Severity::Note,
+                path,
+                format!("{kind} {file} only matches {actual} if you ignore case"),
+                format!(
+                    "Set {kind}_file to {}",
+                    actual.file_name().unwrap_or_default()
+                ),
+            )),
+            Lookup::Missing => findings.push(Finding::new(
+                Severity::Warning,
+                path,
+                format!("{kind} {file} isn't in {dir}"),
+                format!("Copy the {kind} there or pick another one"),
+            )),
+        }
+    }
+}
+
+pub fn write_text<W: Write>(w: &mut W, findings: &[Finding]) -> Result<()> {
+    if findings.is_empty() {
+        writeln!(w, "Everything looks good.")?;
+        return Ok(());
+    }
+
+    for (i, f) in findings.iter().enumerate() {
+        writeln!(
+            w,
+            "{}. [{:?}] {}: {}",
+            i + 1,
+            f.severity,
+            f.subject,
+            f.problem
+        )?;
+        writeln!(w, "   Fix: {}", f.fix)?;
+    }
+    Ok(())
+}
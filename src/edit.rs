@@ -0,0 +1,156 @@
+//! `edit`: change fields by name, either given as `--set field=value`
+//! or prompted for one by one with `--interactive`.
+//!
+//! Fields are named as `diff` shows them, like `squadron` or `campaign_stats.kills`.
+//! Text is taken as-is, medals as a comma-separated list, and anything else as JSON.
+
+use std::io::prelude::*;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+
+use crate::diff;
+use crate::logbook::{Logbook, Validation};
+
+/// `field=value`, from `--set`
+#[derive(Debug, Clone)]
+pub struct Assignment {
+    field: String,
+    value: String,
+}
+
+impl FromStr for Assignment {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (field, value) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Give FIELD=VALUE, not {s}"))?;
+        Ok(Self {
+            field: field.trim().to_owned(),
+            value: value.to_owned(),
+        })
+    }
+}
+
+/// The logbook with each assignment made, in order.
+pub fn set(book: &Logbook, assignments: &[Assignment], validation: Validation) -> Result<Logbook> {
+    let mut value = serde_json::to_value(book)?;
+    for a in assignments {
+        let slot = field(&mut value, &a.field)?;
+        *slot = parse(slot, &a.value);
+    }
+    checked(value, validation)
+}
+
+/// Prompt (on `prompts`) for each field in turn, reading new values from `input`.
+/// An empty line keeps the field as it is, and the end of input keeps the rest.
+pub fn interactive<R: BufRead, W: Write>(
+    book: &Logbook,
+    mut input: R,
+    mut prompts: W,
+    validation: Validation,
+) -> Result<Logbook> {
+    let mut value = serde_json::to_value(book)?;
+    'fields: for (name, current) in diff::flatten(&value.clone()) {
+        loop {
+            write!(prompts, "{name} [{}]: ", shown(&current))?;
+            prompts.flush()?;
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                writeln!(prompts)?;
+                break 'fields;
+            }
+            let line = line.trim_end_matches(['\r', '\n']);
+            if line.is_empty() {
+                break;
+            }
+
+            let mut candidate = value.clone();
+            *field(&mut candidate, &name)? = parse(&current, line);
+            // Check it right away, so the pilot can try again.
+            match checked(candidate.clone(), validation) {
+                Ok(_) => {
+                    value = candidate;
+                    break;
+                }
+                Err(e) => writeln!(prompts, "  {e:#}")?,
+            }
+        }
+    }
+    checked(value, validation)
+}
+
+fn field<'a>(value: &'a mut Value, name: &str) -> Result<&'a mut Value> {
+    value
+        .pointer_mut(&format!("/{}", name.replace('.', "/")))
+        .ok_or_else(|| anyhow!("No field called {name}"))
+}
+
+/// Take `input` as the same kind of thing as `current`.
+fn parse(current: &Value, input: &str) -> Value {
+    match current {
+        Value::String(_) => Value::String(input.to_owned()),
+        Value::Array(_) if !input.trim_start().starts_with('[') => input
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(Value::from)
+            .collect(),
+        // Let deserializing say what's wrong with it.
+        _ => serde_json::from_str(input).unwrap_or_else(|_| Value::String(input.to_owned())),
+    }
+}
+
+fn shown(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items
+            .iter()
+            .map(|i| i.as_str().map_or_else(|| i.to_string(), str::to_owned))
+            .collect::<Vec<_>>()
+            .join(", "),
+        v => v.to_string(),
+    }
+}
+
+/// The logbook, if it is one and BMS would take it
+fn checked(value: Value, validation: Validation) -> Result<Logbook> {
+    let book: Logbook = serde_json::from_value(value).context("Not a valid logbook")?;
+    book.write(std::io::sink(), validation)?;
+    Ok(book)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompts_for_each_field() {
+        let book = bms_logcat::LogbookBuilder::new("Jane Doe", "Viper")
+            .unwrap()
+            .build();
+        // Keep the name, try a callsign that's too long, then take a short one.
+        let input = "\nViperViperViper\nHawk\n\n\n\n\n\nMajor\n";
+        let mut prompts = Vec::new();
+        let edited =
+            interactive(&book, input.as_bytes(), &mut prompts, Validation::Strict).unwrap();
+        assert_eq!(edited.name, "Jane Doe");
+        assert_eq!(edited.callsign, "Hawk");
+        assert_eq!(edited.rank, crate::logbook::Rank::Major);
+
+        let prompts = String::from_utf8(prompts).unwrap();
+        assert!(prompts.starts_with("name [Jane Doe]: callsign [Viper]: "));
+        assert!(prompts.contains("longer than the allowed length"));
+
+        let set: Vec<Assignment> = ["campaign_stats.kills=12", "medals=AirMedal, SilverStar"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let edited = super::set(&book, &set, Validation::Strict).unwrap();
+        assert_eq!(edited.campaign_stats.kills, 12);
+        assert_eq!(edited.medals.len(), 2);
+        assert!(super::set(&book, &["nope=1".parse().unwrap()], Validation::Strict).is_err());
+    }
+}
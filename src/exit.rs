@@ -0,0 +1,104 @@
+//! Exit codes, so scripts can tell failures apart without scraping error messages.
+//!
+//! | Code | Meaning |
+//! |------|---------|
+//! | 0 | Success |
+//! | 1 | Any other error |
+//! | 2 | Bad command line |
+//! | 3 | A file wasn't found |
+//! | 4 | A logbook or JSON file couldn't be parsed |
+//! | 5 | A logbook failed validation (see `--no-validate`) |
+//! | 6 | Problems or differences were found (`diff`, `doctor`, `dupes`, `lint`, `list-patches --check`, `verify-signature`) |
+//! | 7 | BMS is running and would overwrite what we wrote |
+
+use std::fmt;
+
+pub const FAILURE: i32 = 1;
+pub const NOT_FOUND: i32 = 3;
+pub const UNPARSABLE: i32 = 4;
+pub const INVALID: i32 = 5;
+pub const FOUND: i32 = 6;
+pub const RUNNING: i32 = 7;
+
+/// Shown in `--help`, since script writers won't read this file
+pub const HELP: &str = "EXIT CODES:
+    0    Success
+    1    Any other error
+    2    Bad command line
+    3    A file wasn't found
+    4    A logbook or JSON file couldn't be parsed
+    5    A logbook failed validation (see --no-validate)
+    6    Problems or differences were found (diff, doctor, dupes, lint,
+         list-patches --check, verify-signature)
+    7    BMS is running and would overwrite what we wrote";
+
+/// A file couldn't be parsed.
+#[derive(Debug)]
+pub struct Unparsable(pub String);
+
+/// A value is out of range or doesn't fit its field.
+#[derive(Debug)]
+pub struct Invalid(pub String);
+
+/// A check found problems (not that it failed to run).
+#[derive(Debug)]
+pub struct Found(pub String);
+
+/// BMS is running, so we refused to write.
+#[derive(Debug)]
+pub struct Running(pub String);
+
+impl fmt::Display for Unparsable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Display for Invalid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Display for Found {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Display for Running {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Unparsable {}
+impl std::error::Error for Invalid {}
+impl std::error::Error for Found {}
+impl std::error::Error for Running {}
+
+/// The exit code for an error.
+///
+/// More specific causes win: a logbook that couldn't be parsed
+/// because it failed validation exits with [`INVALID`].
+pub fn code(e: &anyhow::Error) -> i32 {
+    if e.downcast_ref::<Found>().is_some() {
+        FOUND
+    } else if e.downcast_ref::<Running>().is_some() {
+        RUNNING
+    } else if e.downcast_ref::<Invalid>().is_some() {
+        INVALID
+    } else if e.downcast_ref::<Unparsable>().is_some()
+        || e.downcast_ref::<serde_json::Error>().is_some()
+    {
+        UNPARSABLE
+    } else if e
+        .chain()
+        .filter_map(|c| c.downcast_ref::<std::io::Error>())
+        .any(|io| io.kind() == std::io::ErrorKind::NotFound)
+    {
+        NOT_FOUND
+    } else {
+        FAILURE
+    }
+}
@@ -0,0 +1,200 @@
+//! A C ABI for reading and writing logbooks from other languages (the `ffi` feature).
+//!
+//! `include/bms_logcat.h` declares these; regenerate it with
+//! `cbindgen --config cbindgen.toml --output include/bms_logcat.h src/ffi.rs`.
+//!
+//! Functions that can fail return `NULL` or a negative number,
+//! and [`bms_last_error`] says why.
+//! Logbooks and strings this library hands out are freed with
+//! [`bms_logbook_free`] and [`bms_string_free`].
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+use anyhow::{anyhow, Result};
+
+use crate::logbook::{Logbook, Validation, LOGBOOK_LEN};
+
+/// The size of a logbook on disk, and of the buffer [`bms_logbook_write`] needs
+pub const BMS_LOGBOOK_LEN: usize = 372;
+const _: () = assert!(BMS_LOGBOOK_LEN == LOGBOOK_LEN);
+
+/// A parsed logbook. Callers only ever see pointers to it.
+pub struct BmsLogbook(Logbook);
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(e: anyhow::Error) {
+    let message = format!("{e:#}").replace('\0', "");
+    LAST_ERROR.with(|last| *last.borrow_mut() = CString::new(message).ok());
+}
+
+/// Run `f`, stashing its error (or panic) for [`bms_last_error`] instead of
+/// unwinding into C.
+fn catch<T>(f: impl FnOnce() -> Result<T>) -> Option<T> {
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+        Ok(Ok(t)) => Some(t),
+        Ok(Err(e)) => {
+            set_error(e);
+            None
+        }
+        Err(_) => {
+            set_error(anyhow!("bms-logcat panicked"));
+            None
+        }
+    }
+}
+
+/// The last error on this thread, or `NULL` if there hasn't been one.
+///
+/// The string is valid until the next call into this library on the same thread.
+#[no_mangle]
+pub extern "C" fn bms_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Parse a logbook from `len` bytes (a whole `.lbk` file).
+///
+/// # Safety
+///
+/// `bytes` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bms_logbook_parse(bytes: *const u8, len: usize) -> *mut BmsLogbook {
+    catch(|| {
+        if bytes.is_null() {
+            return Err(anyhow!("bytes is NULL"));
+        }
+        let bytes = std::slice::from_raw_parts(bytes, len);
+        let book = Logbook::parse_bytes(bytes)?;
+        Ok(Box::into_raw(Box::new(BmsLogbook(book))))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Encrypt the logbook into `out`, which must hold at least [`BMS_LOGBOOK_LEN`] bytes.
+///
+/// Returns how many bytes were written, or -1 on error.
+///
+/// # Safety
+///
+/// `book` must come from this library, and `out` must point to `out_len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bms_logbook_write(
+    book: *const BmsLogbook,
+    out: *mut u8,
+    out_len: usize,
+) -> isize {
+    catch(|| {
+        let book = book.as_ref().ok_or_else(|| anyhow!("book is NULL"))?;
+        if out.is_null() || out_len < LOGBOOK_LEN {
+            return Err(anyhow!("out needs room for {LOGBOOK_LEN} bytes"));
+        }
+        let mut bytes = Vec::with_capacity(LOGBOOK_LEN);
+        book.0.write(&mut bytes, Validation::Strict)?;
+        ptr::copy_nonoverlapping(bytes.as_ptr(), out, bytes.len());
+        Ok(bytes.len() as isize)
+    })
+    .unwrap_or(-1)
+}
+
+/// The logbook as JSON: the fields `bms-logcat read` gives, in snake case,
+/// but without its `format_version`.
+/// Free it with [`bms_string_free`].
+///
+/// # Safety
+///
+/// `book` must come from this library.
+#[no_mangle]
+pub unsafe extern "C" fn bms_logbook_to_json(book: *const BmsLogbook) -> *mut c_char {
+    catch(|| {
+        let book = book.as_ref().ok_or_else(|| anyhow!("book is NULL"))?;
+        let json = serde_json::to_string(&book.0)?;
+        Ok(CString::new(json)?.into_raw())
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Make a logbook from JSON, as [`bms_logbook_to_json`] gives it.
+///
+/// Unlike `bms-logcat write`, this takes neither JSON5 nor JSON from older
+/// `format_version`s, and every field must be given.
+///
+/// # Safety
+///
+/// `json` must be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn bms_logbook_from_json(json: *const c_char) -> *mut BmsLogbook {
+    catch(|| {
+        if json.is_null() {
+            return Err(anyhow!("json is NULL"));
+        }
+        let json = CStr::from_ptr(json).to_str()?;
+        let book: Logbook = serde_json::from_str(json)?;
+        Ok(Box::into_raw(Box::new(BmsLogbook(book))))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Free a logbook from [`bms_logbook_parse`] or [`bms_logbook_from_json`].
+///
+/// # Safety
+///
+/// `book` must come from this library (or be `NULL`), and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn bms_logbook_free(book: *mut BmsLogbook) {
+    if !book.is_null() {
+        drop(Box::from_raw(book));
+    }
+}
+
+/// Free a string from this library.
+///
+/// # Safety
+///
+/// `s` must come from this library (or be `NULL`), and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn bms_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let bytes = crate::LogbookBuilder::new("Jane Doe", "Viper")
+            .unwrap()
+            .build()
+            .to_bytes()
+            .unwrap();
+        unsafe {
+            let book = bms_logbook_parse(bytes.as_ptr(), bytes.len());
+            assert!(!book.is_null());
+
+            let mut out = [0; BMS_LOGBOOK_LEN];
+            assert_eq!(
+                bms_logbook_write(book, out.as_mut_ptr(), out.len()),
+                BMS_LOGBOOK_LEN as isize
+            );
+            assert_eq!(&out[..], &bytes[..]);
+
+            let json = bms_logbook_to_json(book);
+            assert!(CStr::from_ptr(json).to_str().unwrap().contains("\"Viper\""));
+            bms_string_free(json);
+            bms_logbook_free(book);
+
+            assert!(bms_logbook_parse(bytes.as_ptr(), 10).is_null());
+            assert!(!bms_last_error().is_null());
+        }
+    }
+}
@@ -0,0 +1,438 @@
+//! Logbooks as YAML, TOML, or CSV as well as JSON, picked by `--format`,
+//! or failing that, by the file's extension or what's in it.
+
+use std::io::prelude::*;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::ValueEnum;
+use serde_json::{Map, Value};
+
+use crate::table::{self, Table};
+use crate::{case, humanize, lang, logbook::Logbook, pop, version};
+
+#[derive(clap::ValueEnum, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    /// JSON (or, reading, JSON5)
+    Json,
+    Yaml,
+    Toml,
+    /// One row, with a `campaign_stats.kills`-style column per field
+    Csv,
+    /// A table of fields and values, as a web page (output only)
+    Html,
+}
+
+impl Format {
+    /// The format a file's extension says it's in, if it says
+    pub fn from_path(path: &Utf8Path) -> Option<Self> {
+        match path.extension()?.to_ascii_lowercase().as_str() {
+            "json" | "json5" | "jsonc" => Some(Format::Json),
+            "yaml" | "yml" => Some(Format::Yaml),
+            "toml" => Some(Format::Toml),
+            "csv" => Some(Format::Csv),
+            "html" | "htm" => Some(Format::Html),
+            _ => None,
+        }
+    }
+
+    /// Guess the format of `text` from how it starts.
+    pub fn sniff(text: &str) -> Self {
+        let first = text
+            .lines()
+            .map(str::trim)
+            .find(|l| !l.is_empty() && !l.starts_with('#'))
+            .unwrap_or_default();
+        if first.starts_with('{')
+            || first.starts_with("//")
+            || first.starts_with("/*")
+            || (first.starts_with('[') && !is_toml_table(first))
+        {
+            Format::Json
+        } else if is_toml_table(first) || looks_like_toml(first) {
+            Format::Toml
+        } else if first.contains(',') && !first.contains(':') {
+            Format::Csv
+        } else {
+            Format::Yaml
+        }
+    }
+
+    /// The format to read `text` from `path` as, unless the user said
+    pub fn detect(given: Option<Self>, path: &Utf8Path, text: &str) -> Self {
+        given
+            .or_else(|| Self::from_path(path))
+            .unwrap_or_else(|| Self::sniff(text))
+    }
+}
+
+/// A file to write, and what to write it as: `FORMAT=PATH`,
+/// or just a path whose extension says
+#[derive(Debug, Clone)]
+pub struct Out {
+    pub format: Format,
+    pub path: Utf8PathBuf,
+}
+
+impl FromStr for Out {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((format, path)) = s.split_once('=') {
+            if let Ok(format) = Format::from_str(format, true) {
+                return Ok(Out {
+                    format,
+                    path: path.into(),
+                });
+            }
+        }
+        let path = Utf8PathBuf::from(s);
+        match Format::from_path(&path) {
+            Some(format) => Ok(Out { format, path }),
+            None => Err(format!(
+                "can't tell what format {s} should be; give it as FORMAT={s}"
+            )),
+        }
+    }
+}
+
+/// `key = value`, with a bare or quoted key
+fn looks_like_toml(line: &str) -> bool {
+    match line.split_once('=') {
+        Some((key, _)) => is_toml_key(key.trim().trim_matches('"')),
+        None => false,
+    }
+}
+
+/// `[table]` or `[[table]]`, unlike a JSON array like `[1, 2]` or `["a"]`
+fn is_toml_table(line: &str) -> bool {
+    let header = line.split('#').next().unwrap_or_default().trim();
+    let name = header
+        .strip_prefix("[[")
+        .and_then(|h| h.strip_suffix("]]"))
+        .or_else(|| header.strip_prefix('[')?.strip_suffix(']'));
+    name.is_some_and(|name| is_toml_key(name.trim()))
+}
+
+fn is_toml_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// Parse `text` as `format` into the JSON we'd read a logbook from.
+pub fn parse(text: &str, format: Format) -> Result<Value> {
+    match format {
+        Format::Json => crate::parse_json5(text),
+        Format::Yaml => Ok(serde_yaml::from_str(text)?),
+        Format::Toml => Ok(serde_json::to_value(toml::from_str::<toml::Value>(text)?)?),
+        Format::Csv => from_csv(text),
+        Format::Html => bail!("Can't read logbooks from HTML"),
+    }
+}
+
+/// Write `value` as `format`.
+///
+/// JSON is written as [`crate::write_json`] would. Other formats take the key case
+/// and canonical ordering from the same options, and write several values
+/// (e.g., from a `--query`) as a list.
+pub fn write<W: Write>(w: &mut W, values: &[Value], format: Format, pretty: bool) -> Result<()> {
+    let value = || -> Result<Value> {
+        let value = match values {
+            [one] => one.clone(),
+            many => Value::Array(many.to_vec()),
+        };
+        crate::output_value(&value, case::get())
+    };
+    match format {
+        Format::Json => {
+            for value in values {
+                crate::write_json(w, value, pretty)?;
+            }
+        }
+        Format::Yaml => serde_yaml::to_writer(&mut *w, &value()?)?,
+        Format::Toml => {
+            let table = toml::Value::try_from(without_nulls(value()?))?;
+            if !table.is_table() {
+                bail!("TOML can only hold an object; try another --format");
+            }
+            write!(w, "{}", toml::to_string_pretty(&table)?)?;
+        }
+        Format::Csv => to_table(&value()?)?.write_csv(w)?,
+        Format::Html => {
+            let table = match value()? {
+                // One logbook reads better down the page than across it.
+                Value::Array(rows) => to_table(&Value::Array(rows))?,
+                one => {
+                    let mut cells = Vec::new();
+                    flatten(None, &one, &mut cells)?;
+                    let mut table = Table::new(["Field", "Value"]);
+                    for (name, value) in cells {
+                        let value = localized(&name, humanized(&name, value));
+                        table.push(vec![name, value]);
+                    }
+                    table
+                }
+            };
+            table.write(w, table::Format::Html, "Logbook")?;
+        }
+    }
+    Ok(())
+}
+
+/// Rank and medal names in a cell of the logbook table in the `--lang`
+fn localized(name: &str, value: String) -> String {
+    fn parse<T: serde::de::DeserializeOwned>(s: &str) -> Option<T> {
+        serde_json::from_value(Value::String(s.to_owned())).ok()
+    }
+    match case::snake(name.rsplit('.').next().unwrap_or(name)).as_str() {
+        "rank" => parse(&value).map(lang::rank).unwrap_or(value),
+        "medals" => value
+            .split(LIST_SEPARATOR)
+            .map(|m| parse(m).map(lang::medal).unwrap_or_else(|| m.to_owned()))
+            .collect::<Vec<_>>()
+            .join(&format!("{LIST_SEPARATOR} ")),
+        _ => value,
+    }
+}
+
+/// A cell of the logbook table as `--humanize` would have it
+fn humanized(name: &str, value: String) -> String {
+    if !humanize::enabled() {
+        return value;
+    }
+    let field = case::snake(name.rsplit('.').next().unwrap_or(name));
+    if field == "commissioned" {
+        return humanize::commissioned(&value);
+    }
+    if field == "flight_hours" {
+        if let Ok(hours) = value.parse() {
+            return humanize::hours(hours);
+        }
+    }
+    match value.parse::<i64>() {
+        Ok(n) => humanize::count(n),
+        Err(_) => value,
+    }
+}
+
+/// TOML has no null, so leave those fields out.
+fn without_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k, without_nulls(v)))
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(values.into_iter().map(without_nulls).collect()),
+        v => v,
+    }
+}
+
+/// Lists inside CSV cells, like medals, are separated with this.
+const LIST_SEPARATOR: char = ';';
+
+/// A row per value, with a column per (flattened) field
+fn to_table(value: &Value) -> Result<Table> {
+    let rows = match value {
+        Value::Array(rows) => rows.iter().collect(),
+        row => vec![row],
+    };
+    let mut table: Option<Table> = None;
+    for row in rows {
+        let mut cells = Vec::new();
+        flatten(None, row, &mut cells)?;
+        let (names, values): (Vec<String>, Vec<String>) = cells.into_iter().unzip();
+        let table = table.get_or_insert_with(|| Table::new(names.clone()));
+        if table.headers != names {
+            bail!("Rows must all have the same fields");
+        }
+        table.push(values);
+    }
+    Ok(table.unwrap_or_default())
+}
+
+fn flatten(prefix: Option<&str>, value: &Value, cells: &mut Vec<(String, String)>) -> Result<()> {
+    let name = || prefix.unwrap_or("value").to_owned();
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map {
+                let key = match prefix {
+                    Some(p) => format!("{p}.{k}"),
+                    None => k.clone(),
+                };
+                flatten(Some(&key), v, cells)?;
+            }
+        }
+        Value::Array(values) => {
+            let cells_in = values
+                .iter()
+                .map(|v| match v {
+                    Value::Object(_) | Value::Array(_) => {
+                        Err(anyhow!("CSV can't hold {}'s nested lists", name()))
+                    }
+                    v => Ok(cell(v)),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            cells.push((name(), cells_in.join(&LIST_SEPARATOR.to_string())));
+        }
+        v => cells.push((name(), cell(v))),
+    }
+    Ok(())
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+/// Read the first row of CSV back into nested JSON,
+/// giving each cell the type the logbook's field has.
+fn from_csv(text: &str) -> Result<Value> {
+    let mut csv = csv::Reader::from_reader(text.as_bytes());
+    let headers = csv
+        .headers()
+        .context("Couldn't read the CSV header")?
+        .clone();
+    let row = csv
+        .records()
+        .next()
+        .ok_or_else(|| anyhow!("The CSV has no rows"))??;
+
+    let template = template()?;
+    let mut root = Map::new();
+    for (header, cell) in headers.iter().zip(row.iter()) {
+        let path: Vec<String> = header.split('.').map(case::snake).collect();
+        let like = path.iter().try_fold(&template, |t, p| t.get(p));
+
+        let (last, parents) = path.split_last().unwrap();
+        let mut map = &mut root;
+        for parent in parents {
+            map = map
+                .entry(parent.clone())
+                .or_insert_with(|| Value::Object(Map::new()))
+                .as_object_mut()
+                .ok_or_else(|| anyhow!("CSV column {header} is inside a non-object"))?;
+        }
+        map.insert(last.clone(), typed(cell, like));
+    }
+    Ok(Value::Object(root))
+}
+
+/// A logbook as we'd write it, in and out of a `read --with-options` wrapper,
+/// for the types of its fields
+fn template() -> Result<Value> {
+    let book = serde_json::to_value(Logbook::default())?;
+    let options = pop::PilotOptions {
+        file: Default::default(),
+        data: Vec::new(),
+    };
+    let mut template = version::stamp(book.clone());
+    template["logbook"] = book;
+    template["options"] = serde_json::to_value(options)?;
+    Ok(template)
+}
+
+fn typed(cell: &str, like: Option<&Value>) -> Value {
+    match like {
+        Some(Value::String(_)) => Value::String(cell.to_owned()),
+        Some(Value::Array(_)) => Value::Array(
+            cell.split(LIST_SEPARATOR)
+                .filter(|s| !s.is_empty())
+                .map(|s| Value::String(s.to_owned()))
+                .collect(),
+        ),
+        // Numbers, or fields we don't know: take what it looks like.
+        _ => {
+            if cell.is_empty() {
+                Value::Null
+            } else {
+                serde_json::from_str(cell).unwrap_or_else(|_| Value::String(cell.to_owned()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniffs_the_format() {
+        assert_eq!(Format::sniff("\n  {\"name\": \"Jane\"}"), Format::Json);
+        assert_eq!(Format::sniff("// exported\n{}"), Format::Json);
+        assert_eq!(Format::sniff("[\n  {\"name\": \"Jane\"}\n]"), Format::Json);
+        assert_eq!(Format::sniff("[1, 2]"), Format::Json);
+        assert_eq!(Format::sniff("[\"Viper\"]"), Format::Json);
+        assert_eq!(Format::sniff("# pilot\nname = \"Jane\""), Format::Toml);
+        assert_eq!(Format::sniff("[campaign_stats]\nkills = 3"), Format::Toml);
+        assert_eq!(
+            Format::sniff("[[logbooks]] # one each\nname = \"Jane\""),
+            Format::Toml
+        );
+        assert_eq!(Format::sniff("name,callsign\nJane,Viper"), Format::Csv);
+        assert_eq!(Format::sniff("name: Jane\ncallsign: Viper"), Format::Yaml);
+    }
+
+    #[test]
+    fn parses_outs() {
+        let out: Out = "yaml=pilot.txt".parse().unwrap();
+        assert_eq!((out.format, out.path.as_str()), (Format::Yaml, "pilot.txt"));
+        let out: Out = "Pilot.TOML".parse().unwrap();
+        assert_eq!(
+            (out.format, out.path.as_str()),
+            (Format::Toml, "Pilot.TOML")
+        );
+        // Not a format, so the `=` is part of the path.
+        let out: Out = "a=b.csv".parse().unwrap();
+        assert_eq!((out.format, out.path.as_str()), (Format::Csv, "a=b.csv"));
+        assert!("pilot".parse::<Out>().is_err());
+    }
+
+    #[test]
+    fn types_csv_cells_like_the_logbook() {
+        let csv = "name,callsign,flight_hours,campaign_stats.kills,medals,unknown\n\
+                   007,42,1.5,3,silver_star;air_medal,\n";
+        let value = from_csv(csv).unwrap();
+        let template = template().unwrap();
+        assert!(template["name"].is_string());
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "name": "007",
+                "callsign": "42",
+                "flight_hours": 1.5,
+                "campaign_stats": {"kills": 3},
+                "medals": ["silver_star", "air_medal"],
+                "unknown": null,
+            })
+        );
+        assert!(from_csv("name,callsign\n").is_err());
+    }
+
+    #[test]
+    fn reads_back_what_it_writes() {
+        let book = Logbook {
+            name: "Jane Doe".into(),
+            callsign: "Viper".into(),
+            flight_hours: 12.5,
+            ..Logbook::default()
+        };
+        let value = serde_json::to_value(&book).unwrap();
+        for format in [Format::Csv, Format::Toml, Format::Yaml] {
+            let mut text = Vec::new();
+            write(&mut text, std::slice::from_ref(&value), format, true).unwrap();
+            let text = String::from_utf8(text).unwrap();
+            assert_eq!(Format::sniff(&text), format, "{text}");
+            let parsed = parse(&text, format).unwrap();
+            let parsed: Logbook = serde_json::from_value(parsed).unwrap();
+            assert_eq!(serde_json::to_value(parsed).unwrap(), value, "{format:?}");
+        }
+    }
+}
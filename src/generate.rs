@@ -0,0 +1,215 @@
+//! `generate`: random but believable logbooks, for demos, screenshots, and test fixtures.
+//!
+//! A seeded RNG picks a rank and hours to match, then counters that hang together
+//! the way a real career's would, so [`lint`](crate::lint) has nothing to say about them.
+
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use camino::Utf8PathBuf;
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+
+use crate::logbook::*;
+
+const NAMES: &[&str] = &[
+    "Alex Mercer",
+    "Dana Kowalski",
+    "Chris Haldane",
+    "Sam Okafor",
+    "Robin Lindqvist",
+    "Jordan Reyes",
+    "Casey Brandt",
+    "Morgan Tanaka",
+];
+
+const CALLSIGNS: &[&str] = &[
+    "Viper", "Mongoose", "Rattler", "Hawg", "Stinger", "Razor", "Ghost", "Banshee", "Jester",
+    "Maverick", "Nomad", "Sabre",
+];
+
+const SQUADRONS: &[&str] = &["36th FS", "80th FS", "35th FS", "13th FS", "555th FS"];
+
+/// Plausible flight hours for each rank, indexed by rank.
+const HOURS_BY_RANK: [(f32, f32); 7] = [
+    (5.0, 60.0),
+    (60.0, 180.0),
+    (180.0, 350.0),
+    (350.0, 600.0),
+    (600.0, 900.0),
+    (900.0, 1400.0),
+    (1400.0, 2500.0),
+];
+
+/// Generate a random but internally consistent logbook.
+///
+/// The same seed always produces the same logbook,
+/// so the output can be checked into test fixtures.
+pub fn generate(seed: u64, name: Option<String>, callsign: Option<String>) -> Result<Logbook> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    let name = name.unwrap_or_else(|| NAMES.choose(&mut rng).unwrap().to_string());
+    let callsign = callsign.unwrap_or_else(|| CALLSIGNS.choose(&mut rng).unwrap().to_string());
+    let squadron = SQUADRONS.choose(&mut rng).unwrap().to_string();
+
+    let rank_index = rng.gen_range(0..HOURS_BY_RANK.len());
+    let rank = Rank::try_from(rank_index as i32).unwrap();
+    let (min_hours, max_hours) = HOURS_BY_RANK[rank_index];
+    // Round to tenths of an hour like the BMS UI shows.
+    let flight_hours = (rng.gen_range(min_hours..max_hours) * 10.0).round() / 10.0;
+
+    let campaign_stats = campaign_stats(&mut rng, flight_hours);
+    let dogfight_stats = dogfight_stats(&mut rng, flight_hours);
+    let ace_factor = crate::ace::from_matches(
+        dogfight_stats.matches_won as i32,
+        dogfight_stats.matches_lost as i32,
+    );
+    let medals = earned_medals(flight_hours, &campaign_stats);
+
+    // Commissioned sometime between 2000 and 2020 so it doesn't depend on today's date.
+    let epoch = time::macros::date!(2000 - 01 - 01);
+    let commissioned = (epoch + time::Duration::days(rng.gen_range(0..7300))).format(
+        time::macros::format_description!("[month]/[day]/[year repr:last_two]"),
+    )?;
+
+    let options_file = Utf8PathBuf::from(&callsign);
+    let voice = rng.gen_range(0..12);
+
+    Ok(Logbook {
+        name,
+        callsign,
+        commissioned,
+        options_file,
+        flight_hours,
+        ace_factor,
+        rank,
+        dogfight_stats,
+        campaign_stats,
+        medals,
+        squadron,
+        voice,
+        ..Default::default()
+    })
+}
+
+fn campaign_stats(rng: &mut ChaCha8Rng, flight_hours: f32) -> CampaignStats {
+    // Campaign sorties run somewhere between one and two hours.
+    let missions = (flight_hours / rng.gen_range(1.0..2.0)) as i16;
+    let campaigns = missions / rng.gen_range(15..40);
+    let games_won = rng.gen_range(0..=campaigns);
+    let game_lost = rng.gen_range(0..=campaigns - games_won);
+    let games_tied = campaigns - games_won - game_lost;
+
+    let kills = (missions as f32 * rng.gen_range(0.0..0.8)) as i16;
+    let killed = (missions as f32 * rng.gen_range(0.0..0.1)) as i16;
+    let human_kills = (kills as f32 * rng.gen_range(0.0..0.2)) as i16;
+    let killed_versus_humans = (killed as f32 * rng.gen_range(0.0..0.3)) as i16;
+    let self_kills = rng.gen_range(0..=killed / 4);
+    let air_to_ground_kills = (missions as f32 * rng.gen_range(0.0..2.0)) as i16;
+    let static_kills = (missions as f32 * rng.gen_range(0.0..1.0)) as i16;
+    let naval_kills = (missions as f32 * rng.gen_range(0.0..0.05)) as i16;
+    let friendly_kills = (missions as f32 * rng.gen_range(0.0..0.02)) as i16;
+
+    let missions_since_last_friendly_kill = if friendly_kills > 0 {
+        rng.gen_range(0..=missions)
+    } else {
+        missions
+    };
+    let consecutive_missions = rng.gen_range(0..=missions);
+
+    let total_mission_score = missions as i32 * rng.gen_range(20..120);
+    let total_score = total_mission_score + games_won as i32 * rng.gen_range(100..500);
+
+    CampaignStats {
+        games_won,
+        game_lost,
+        games_tied,
+        missions,
+        total_score,
+        total_mission_score,
+        consecutive_missions,
+        kills,
+        killed,
+        human_kills,
+        killed_versus_humans,
+        self_kills,
+        air_to_ground_kills,
+        static_kills,
+        naval_kills,
+        friendly_kills,
+        missions_since_last_friendly_kill,
+    }
+}
+
+fn dogfight_stats(rng: &mut ChaCha8Rng, flight_hours: f32) -> DogfightStats {
+    // Dogfights are short; assume a slice of the total hours went to them.
+    let matches = (flight_hours * rng.gen_range(0.0..1.5)) as i16;
+    let matches_won = rng.gen_range(0..=matches);
+    let matches_lost = matches - matches_won;
+    let matches_won_versus_humans = rng.gen_range(0..=matches_won);
+    let matches_lost_versus_humans = rng.gen_range(0..=matches_lost);
+
+    let kills = matches_won + rng.gen_range(0..=matches_won);
+    let killed = matches_lost + rng.gen_range(0..=matches_lost);
+    let human_kills = rng.gen_range(0..=kills);
+    let killed_versus_humans = rng.gen_range(0..=killed);
+
+    DogfightStats {
+        matches_won,
+        matches_lost,
+        matches_won_versus_humans,
+        matches_lost_versus_humans,
+        kills,
+        killed,
+        human_kills,
+        killed_versus_humans,
+    }
+}
+
+/// Hand out the medals a career like this one would plausibly have earned.
+pub(crate) fn earned_medals(flight_hours: f32, stats: &CampaignStats) -> BTreeSet<Medals> {
+    let mut medals = BTreeSet::new();
+
+    if stats.kills >= 40 {
+        medals.insert(Medals::AirForceCross);
+    }
+    if stats.kills >= 15 {
+        medals.insert(Medals::SilverStar);
+    }
+    if stats.missions >= 50 && stats.kills >= 5 {
+        medals.insert(Medals::DistinguishedFlyingCross);
+    }
+    if stats.missions >= 20 {
+        medals.insert(Medals::AirMedal);
+    }
+    if stats.games_won > 0 {
+        medals.insert(Medals::KoreaCampaign);
+    }
+    if flight_hours >= 500.0 {
+        medals.insert(Medals::Longevity);
+    }
+
+    medals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lint;
+
+    #[test]
+    fn makes_the_same_believable_logbook_for_a_seed() {
+        for seed in 0..500 {
+            let book = generate(seed, None, None).unwrap();
+            let again = generate(seed, None, None).unwrap();
+            assert_eq!(
+                serde_json::to_value(&book).unwrap(),
+                serde_json::to_value(&again).unwrap()
+            );
+
+            let findings = lint::check("generated.lbk".into(), &book, &lint::Policy::default());
+            let problems: Vec<_> = findings.into_iter().map(|f| f.problem).collect();
+            assert_eq!(problems, [] as [String; 0], "seed {seed}");
+        }
+    }
+}
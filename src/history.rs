@@ -0,0 +1,198 @@
+use std::io::prelude::*;
+
+use anyhow::{anyhow, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use time::format_description::well_known::Rfc3339;
+
+use crate::humanize;
+use crate::lang;
+use crate::logbook::{Logbook, Rank};
+use crate::redact;
+use crate::table::{Format, Table};
+
+/// A logbook's stats at a point in time.
+#[derive(Debug, Serialize)]
+pub struct Snapshot {
+    /// When the snapshot was taken, as RFC 3339 UTC
+    pub taken_at: String,
+    pub callsign: String,
+    pub file: Utf8PathBuf,
+    pub rank: Rank,
+    pub flight_hours: f32,
+    pub missions: i16,
+    pub kills: i16,
+    pub dogfight_kills: i16,
+    pub total_score: i32,
+}
+
+/// SQLite database of snapshots.
+pub struct History {
+    conn: Connection,
+}
+
+/// `history.sqlite` in the user's data directory
+pub fn default_path() -> Result<Utf8PathBuf> {
+    let dir = dirs::data_dir().ok_or_else(|| anyhow!("Couldn't find a data directory"))?;
+    let dir = Utf8PathBuf::try_from(dir)?;
+    Ok(dir.join("bms-logcat").join("history.sqlite"))
+}
+
+impl History {
+    pub fn open(path: &Utf8Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_str().is_empty() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Couldn't create {parent}"))?;
+            }
+        }
+
+        let conn = Connection::open(path).with_context(|| format!("Couldn't open {path}"))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS snapshots (
+                id INTEGER PRIMARY KEY,
+                taken_at TEXT NOT NULL,
+                callsign TEXT NOT NULL,
+                file TEXT NOT NULL,
+                rank INTEGER NOT NULL,
+                flight_hours REAL NOT NULL,
+                missions INTEGER NOT NULL,
+                kills INTEGER NOT NULL,
+                dogfight_kills INTEGER NOT NULL,
+                total_score INTEGER NOT NULL,
+                logbook TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS snapshots_by_callsign
+                ON snapshots (callsign COLLATE NOCASE, taken_at);",
+        )
+        .with_context(|| format!("Couldn't set up {path}"))?;
+
+        Ok(Self { conn })
+    }
+
+    /// Record the logbook's current stats, along with a full JSON copy
+    /// (without the password, which has no business in a stats database).
+    pub fn record(&self, file: &Utf8Path, book: &Logbook) -> Result<Snapshot> {
+        let snapshot = Snapshot {
+            // Whole seconds keep the timestamps the same length so they sort as text.
+            taken_at: time::OffsetDateTime::now_utc()
+                .replace_nanosecond(0)?
+                .format(&Rfc3339)?,
+            callsign: book.callsign.clone(),
+            file: file.to_owned(),
+            rank: book.rank,
+            flight_hours: book.flight_hours,
+            missions: book.campaign_stats.missions,
+            kills: book.campaign_stats.kills,
+            dogfight_kills: book.dogfight_stats.kills,
+            total_score: book.campaign_stats.total_score,
+        };
+
+        let no_password = redact::Profile::default().with(redact::Field::Password);
+        self.conn.execute(
+            "INSERT INTO snapshots (taken_at, callsign, file, rank, flight_hours,
+                missions, kills, dogfight_kills, total_score, logbook)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                snapshot.taken_at,
+                snapshot.callsign,
+                snapshot.file.as_str(),
+                i32::from(snapshot.rank),
+                snapshot.flight_hours,
+                snapshot.missions,
+                snapshot.kills,
+                snapshot.dogfight_kills,
+                snapshot.total_score,
+                serde_json::to_string(&no_password.apply(book.clone()))?,
+            ],
+        )?;
+
+        Ok(snapshot)
+    }
+
+    /// Callsigns of every pilot that's been snapshotted while flying for the given squadron.
+    pub fn squadron_callsigns(&self, squadron: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT callsign FROM snapshots
+            WHERE json_extract(logbook, '$.squadron') = ?1
+            ORDER BY callsign",
+        )?;
+        let callsigns = stmt
+            .query_map([squadron], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(callsigns)
+    }
+
+    /// Every snapshot of the given callsign, oldest first.
+    pub fn snapshots(&self, callsign: &str) -> Result<Vec<Snapshot>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT taken_at, callsign, file, rank, flight_hours,
+                missions, kills, dogfight_kills, total_score
+            FROM snapshots
+            WHERE callsign = ?1 COLLATE NOCASE
+            ORDER BY taken_at, id",
+        )?;
+
+        let rows = stmt.query_map([callsign], |row| {
+            Ok((
+                Snapshot {
+                    taken_at: row.get(0)?,
+                    callsign: row.get(1)?,
+                    file: Utf8PathBuf::from(row.get::<_, String>(2)?),
+                    rank: Rank::SecondLt,
+                    flight_hours: row.get(4)?,
+                    missions: row.get(5)?,
+                    kills: row.get(6)?,
+                    dogfight_kills: row.get(7)?,
+                    total_score: row.get(8)?,
+                },
+                row.get::<_, i32>(3)?,
+            ))
+        })?;
+
+        let mut snapshots = Vec::new();
+        for row in rows {
+            let (mut snapshot, rank) = row?;
+            snapshot.rank = Rank::try_from(rank)
+                .map_err(|e| anyhow!("{} isn't a valid rank index", e.number))?;
+            snapshots.push(snapshot);
+        }
+        Ok(snapshots)
+    }
+}
+
+/// Show each snapshot along with how much the pilot progressed since the last one.
+pub fn write<W: Write>(
+    w: &mut W,
+    snapshots: &[Snapshot],
+    format: Format,
+    pretty: bool,
+) -> Result<()> {
+    if let Format::Json = format {
+        return crate::write_json(w, &snapshots, pretty);
+    }
+
+    let mut t = Table::new([
+        "Taken", "Rank", "Hours", "+Hours", "Missions", "Kills", "+Kills", "Score",
+    ]);
+    let mut previous: Option<&Snapshot> = None;
+    for s in snapshots {
+        let (hours_delta, kills_delta) = match previous {
+            Some(p) => (s.flight_hours - p.flight_hours, s.kills - p.kills),
+            None => (0.0, 0),
+        };
+        t.push(vec![
+            s.taken_at.clone(),
+            lang::rank(s.rank),
+            humanize::hours(s.flight_hours),
+            humanize::hours(hours_delta),
+            humanize::count(s.missions),
+            humanize::count(s.kills),
+            humanize::count(kills_delta),
+            humanize::count(s.total_score),
+        ]);
+        previous = Some(s);
+    }
+    t.write(w, format, "History")
+}
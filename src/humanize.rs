@@ -0,0 +1,97 @@
+//! `--humanize`: numbers and dates in tables written for people rather than spreadsheets,
+//! like `1,234 h 30 m` of flight hours.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use time::{Date, Month, OffsetDateTime};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Flight hours as `1,234 h 30 m`, or to a tenth of an hour without `--humanize`
+pub fn hours(hours: f32) -> String {
+    if !enabled() {
+        return format!("{hours:.1}");
+    }
+    let minutes = (hours as f64 * 60.0).round() as i64;
+    let sign = if minutes < 0 { "-" } else { "" };
+    let minutes = minutes.abs();
+    format!("{sign}{} h {} m", thousands(minutes / 60), minutes % 60)
+}
+
+/// A counter, with thousands separators if humanizing
+pub fn count(n: impl Into<i64>) -> String {
+    let n = n.into();
+    if enabled() {
+        thousands(n)
+    } else {
+        n.to_string()
+    }
+}
+
+/// `1234567` as `1,234,567`
+pub fn thousands(n: i64) -> String {
+    let digits = n.unsigned_abs().to_string();
+    let sign = if n < 0 { "-" } else { "" };
+    // The first group is whatever's left over from groups of three.
+    let first = match digits.len() % 3 {
+        0 => 3,
+        r => r,
+    };
+    let (first, rest) = digits.split_at(first.min(digits.len()));
+    let mut out = format!("{sign}{first}");
+    for group in rest.as_bytes().chunks(3) {
+        out.push(',');
+        out.push_str(std::str::from_utf8(group).unwrap());
+    }
+    out
+}
+
+/// A commissioned date (`MM/DD/YY`, as BMS writes them) as `commissioned 3 years ago`.
+///
+/// Anything that isn't a date, or without `--humanize`, is left alone.
+pub fn commissioned(date: &str) -> String {
+    match (enabled(), parse_date(date)) {
+        (true, Some(d)) => format!("commissioned {}", ago(d, OffsetDateTime::now_utc().date())),
+        _ => date.to_owned(),
+    }
+}
+
+fn parse_date(date: &str) -> Option<Date> {
+    let parts: Vec<u32> = date
+        .trim()
+        .split('/')
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .ok()?;
+    let [month, day, year] = parts[..] else {
+        return None;
+    };
+    // Two-digit years are this century, since BMS is.
+    let year = if year < 100 { 2000 + year } else { year };
+    let month = Month::try_from(u8::try_from(month).ok()?).ok()?;
+    Date::from_calendar_date(year as i32, month, u8::try_from(day).ok()?).ok()
+}
+
+fn ago(then: Date, today: Date) -> String {
+    let days = (today - then).whole_days();
+    let (n, unit) = match days.abs() {
+        0 => return "today".to_owned(),
+        d if d < 31 => (d, "day"),
+        d if d < 365 => (d / 30, "month"),
+        d => (d / 365, "year"),
+    };
+    let plural = if n == 1 { "" } else { "s" };
+    if days < 0 {
+        format!("in {n} {unit}{plural}")
+    } else {
+        format!("{n} {unit}{plural} ago")
+    }
+}
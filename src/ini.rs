@@ -0,0 +1,219 @@
+//! Pilots' `{callsign}.ini` files, which BMS keeps in `User/Config` next to their logbooks
+//! to hold per-pilot UI and gameplay settings.
+//!
+//! They're plain INI files. We edit them in place,
+//! keeping comments, ordering, and anything else we don't touch as it was.
+
+use std::fmt;
+
+use anyhow::{Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use serde_json::{Map, Value};
+
+use crate::lock;
+use crate::logbook::{Logbook, Validation};
+
+/// One line of an INI file, as it was written
+#[derive(Debug, Clone)]
+struct Line {
+    raw: String,
+    kind: Kind,
+}
+
+#[derive(Debug, Clone)]
+enum Kind {
+    Section(String),
+    Entry {
+        key: String,
+        value: String,
+    },
+    /// Blank lines, comments, and anything else we leave alone
+    Other,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Ini {
+    lines: Vec<Line>,
+}
+
+/// A setting that changed: `[section] key`, its old value (if any), and its new one
+#[derive(Debug)]
+pub struct Change {
+    pub section: String,
+    pub key: String,
+    pub old: Option<String>,
+    pub new: String,
+}
+
+impl Ini {
+    /// Parse an INI file's text, checking that every line makes sense.
+    pub fn parse(text: &str, validation: Validation) -> Result<Self> {
+        let mut lines = Vec::new();
+        let mut section = String::new();
+        let mut seen: Vec<(String, String)> = Vec::new();
+
+        for (i, raw) in text.lines().enumerate() {
+            let trimmed = raw.trim();
+            let kind = if trimmed.is_empty() || trimmed.starts_with([';', '#']) {
+                Kind::Other
+            } else if let Some(name) = trimmed.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+                section = name.trim().to_owned();
+                Kind::Section(section.clone())
+            } else if let Some((key, value)) = trimmed.split_once('=') {
+                let key = key.trim().to_owned();
+                validation.check(!key.is_empty(), || format!("Line {}: empty key", i + 1))?;
+                let id = (section.clone(), key.to_ascii_lowercase());
+                validation.check(!seen.contains(&id), || {
+                    format!("Line {}: [{section}] {key} is set more than once", i + 1)
+                })?;
+                seen.push(id);
+                Kind::Entry {
+                    key,
+                    value: value.trim().to_owned(),
+                }
+            } else {
+                validation.check(false, || {
+                    format!("Line {}: expected `[section]` or `key=value`", i + 1)
+                })?;
+                Kind::Other
+            };
+            lines.push(Line {
+                raw: raw.to_owned(),
+                kind,
+            });
+        }
+        Ok(Self { lines })
+    }
+
+    pub fn read(path: &Utf8Path, validation: Validation) -> Result<Self> {
+        let text = std::io::read_to_string(lock::open_shared(path)?)
+            .with_context(|| format!("Couldn't read {path}"))?;
+        Self::parse(&text, validation).with_context(|| format!("Couldn't parse {path}"))
+    }
+
+    pub fn write(&self, path: &Utf8Path) -> Result<()> {
+        use std::io::Write;
+        let mut f = lock::create_exclusive(path)?;
+        write!(f, "{self}").with_context(|| format!("Couldn't write {path}"))
+    }
+
+    /// Every `(section, key, value)`, in order.
+    /// Keys before the first section have an empty section name.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str, &str)> {
+        let mut section = "";
+        self.lines.iter().filter_map(move |line| match &line.kind {
+            Kind::Section(s) => {
+                section = s;
+                None
+            }
+            Kind::Entry { key, value } => Some((section, key.as_str(), value.as_str())),
+            Kind::Other => None,
+        })
+    }
+
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.entries()
+            .find(|(s, k, _)| s.eq_ignore_ascii_case(section) && k.eq_ignore_ascii_case(key))
+            .map(|(_, _, v)| v)
+    }
+
+    /// Set a key, adding it (and its section) if needed.
+    pub fn set(&mut self, section: &str, key: &str, value: &str) {
+        let entry = Line {
+            raw: format!("{key}={value}"),
+            kind: Kind::Entry {
+                key: key.to_owned(),
+                value: value.to_owned(),
+            },
+        };
+
+        // Find the key, or failing that, where to add it:
+        // after the last key in its section, or right after the section's header.
+        let mut in_section = section.is_empty();
+        let mut insert_at = in_section.then_some(0);
+        let mut found = None;
+        for (i, line) in self.lines.iter().enumerate() {
+            match &line.kind {
+                Kind::Section(s) => {
+                    in_section = s.eq_ignore_ascii_case(section);
+                    if in_section {
+                        insert_at = Some(i + 1);
+                    }
+                }
+                Kind::Entry { key: k, .. } if in_section => {
+                    if k.eq_ignore_ascii_case(key) {
+                        found = Some(i);
+                        break;
+                    }
+                    insert_at = Some(i + 1);
+                }
+                _ => {}
+            }
+        }
+
+        match (found, insert_at) {
+            (Some(i), _) => self.lines[i] = entry,
+            (None, Some(i)) => self.lines.insert(i, entry),
+            (None, None) => {
+                self.lines.push(Line {
+                    raw: format!("[{section}]"),
+                    kind: Kind::Section(section.to_owned()),
+                });
+                self.lines.push(entry);
+            }
+        }
+    }
+
+    /// Apply every setting in the template, filling in `{callsign}`, `{name}`,
+    /// and `{squadron}` from the pilot's logbook, and return what changed.
+    pub fn apply(&mut self, template: &Ini, book: &Logbook) -> Vec<Change> {
+        let mut changes = Vec::new();
+        for (section, key, value) in template.entries() {
+            let value = value
+                .replace("{callsign}", &book.callsign)
+                .replace("{name}", &book.name)
+                .replace("{squadron}", &book.squadron);
+            let old = self.get(section, key).map(str::to_owned);
+            if old.as_deref() == Some(value.as_str()) {
+                continue;
+            }
+            self.set(section, key, &value);
+            changes.push(Change {
+                section: section.to_owned(),
+                key: key.to_owned(),
+                old,
+                new: value,
+            });
+        }
+        changes
+    }
+
+    /// Settings as `{"section": {"key": "value"}}`
+    pub fn to_json(&self) -> Value {
+        let mut sections = Map::new();
+        for (section, key, value) in self.entries() {
+            sections
+                .entry(section)
+                .or_insert_with(|| Value::Object(Map::new()))
+                .as_object_mut()
+                .unwrap()
+                .insert(key.to_owned(), value.into());
+        }
+        Value::Object(sections)
+    }
+}
+
+impl fmt::Display for Ini {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            // BMS is a Windows program; keep its line endings.
+            write!(f, "{}\r\n", line.raw)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where a pilot's settings live, given the directory their logbook is in
+pub fn path_for(dir: &Utf8Path, book: &Logbook) -> Utf8PathBuf {
+    dir.join(format!("{}.ini", book.callsign))
+}
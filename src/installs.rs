@@ -0,0 +1,252 @@
+use std::collections::BTreeMap;
+use std::io::prelude::*;
+
+use anyhow::{anyhow, bail, ensure, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use log::*;
+use serde::Serialize;
+
+use crate::table::{Format, Table};
+
+/// A Falcon BMS installation.
+#[derive(Debug, Clone, Serialize)]
+pub struct Install {
+    /// Version from the install's name, e.g., `4.37`
+    pub version: String,
+    pub base_dir: Utf8PathBuf,
+    /// Where BMS keeps pilots' logbooks and options
+    pub config_dir: Utf8PathBuf,
+}
+
+impl Install {
+    fn new(version: String, base_dir: Utf8PathBuf) -> Self {
+        let config_dir = base_dir.join("User").join("Config");
+        Self {
+            version,
+            base_dir,
+            config_dir,
+        }
+    }
+}
+
+const INSTALL_PREFIX: &str = "Falcon BMS ";
+
+/// Find BMS installs from the registry (on Windows) and a handful of usual places,
+/// sorted by version.
+pub fn discover() -> Vec<Install> {
+    let mut found: BTreeMap<Utf8PathBuf, Install> = BTreeMap::new();
+
+    for install in from_registry().into_iter().chain(from_known_paths()) {
+        if !install.base_dir.is_dir() {
+            debug!("Ignoring {} - it's not a directory", install.base_dir);
+            continue;
+        }
+        found.entry(install.base_dir.clone()).or_insert(install);
+    }
+
+    let mut installs: Vec<Install> = found.into_values().collect();
+    installs.sort_by(|a, b| compare_versions(&a.version, &b.version));
+    installs
+}
+
+/// Find the install with the given version, e.g., `4.37`, or in the given directory
+pub fn find(version: &str) -> Result<Install> {
+    let dir = Utf8Path::new(version);
+    if dir.join("User").is_dir() {
+        let name = dir.file_name().unwrap_or_default();
+        let version = name.strip_prefix(INSTALL_PREFIX).unwrap_or(name);
+        return Ok(Install::new(version.to_owned(), dir.to_owned()));
+    }
+
+    let installs = discover();
+    match installs.iter().find(|i| i.version == version) {
+        Some(i) => Ok(i.clone()),
+        None if installs.is_empty() => bail!("Couldn't find any BMS installs"),
+        None => {
+            let versions: Vec<&str> = installs.iter().map(|i| i.version.as_str()).collect();
+            Err(anyhow!(
+                "Couldn't find BMS {version} (found {})",
+                versions.join(", ")
+            ))
+        }
+    }
+}
+
+fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    let parts = |v: &str| -> Vec<u32> { v.split('.').filter_map(|p| p.parse().ok()).collect() };
+    parts(a).cmp(&parts(b)).then_with(|| a.cmp(b))
+}
+
+#[cfg(windows)]
+fn from_registry() -> Vec<Install> {
+    use winreg::enums::*;
+    use winreg::RegKey;
+
+    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    let mut installs = Vec::new();
+
+    for root in [
+        "SOFTWARE\\WOW6432Node\\Benchmark Sims",
+        "SOFTWARE\\Benchmark Sims",
+    ] {
+        let sims = match hklm.open_subkey(root) {
+            Ok(k) => k,
+            Err(_) => continue,
+        };
+        for name in sims.enum_keys().flatten() {
+            let version = match name.strip_prefix(INSTALL_PREFIX) {
+                Some(v) => v.to_owned(),
+                None => continue,
+            };
+            let base_dir: String =
+                match sims.open_subkey(&name).and_then(|k| k.get_value("baseDir")) {
+                    Ok(d) => d,
+                    Err(e) => {
+                        debug!("{root}\\{name} has no baseDir: {e}");
+                        continue;
+                    }
+                };
+            trace!("Registry lists BMS {version} at {base_dir}");
+            installs.push(Install::new(version, base_dir.into()));
+        }
+    }
+    installs
+}
+
+#[cfg(not(windows))]
+fn from_registry() -> Vec<Install> {
+    Vec::new()
+}
+
+/// Look for `Falcon BMS *` directories wherever people usually put them.
+fn from_known_paths() -> Vec<Install> {
+    let mut parents: Vec<Utf8PathBuf> = Vec::new();
+
+    if cfg!(windows) {
+        for drive in 'C'..='H' {
+            let root = Utf8PathBuf::from(format!("{drive}:\\"));
+            parents.push(root.join("Program Files"));
+            parents.push(root.join("Program Files (x86)"));
+            parents.push(root.join("Games"));
+            parents.push(root);
+        }
+    } else if let Some(home) = dirs::home_dir().and_then(|h| Utf8PathBuf::try_from(h).ok()) {
+        // Wine and Proton prefixes
+        let drive_c = home.join(".wine").join("drive_c");
+        parents.push(drive_c.join("Program Files"));
+        parents.push(drive_c.join("Games"));
+        parents.push(drive_c);
+    }
+
+    let mut installs = Vec::new();
+    for parent in parents {
+        let entries = match parent.read_dir_utf8() {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            if let Some(version) = entry.file_name().strip_prefix(INSTALL_PREFIX) {
+                trace!("Found BMS {version} at {}", entry.path());
+                installs.push(Install::new(version.to_owned(), entry.path().to_owned()));
+            }
+        }
+    }
+    installs
+}
+
+/// Resolve a relative logbook path against the install's config directory.
+pub fn resolve(install: Option<&Install>, path: Utf8PathBuf) -> Utf8PathBuf {
+    match install {
+        Some(i) if path.is_relative() && path != "-" => i.config_dir.join(path),
+        _ => path,
+    }
+}
+
+/// Find `<callsign>.lbk` in the install's config directory,
+/// or in every install we can find if none was picked.
+pub fn find_callsign(install: Option<&Install>, callsign: &str) -> Result<Utf8PathBuf> {
+    let installs = match install {
+        Some(i) => vec![i.clone()],
+        None => discover(),
+    };
+    ensure!(
+        !installs.is_empty(),
+        "Couldn't find any BMS installs to look for {callsign} in"
+    );
+
+    let mut found = Vec::new();
+    for i in &installs {
+        let entries = match i.config_dir.read_dir_utf8() {
+            Ok(e) => e,
+            Err(e) => {
+                debug!("Couldn't list {}: {e}", i.config_dir);
+                continue;
+            }
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let matches = path
+                .extension()
+                .map(|e| e.eq_ignore_ascii_case("lbk"))
+                .unwrap_or(false)
+                && path
+                    .file_stem()
+                    .map(|s| s.eq_ignore_ascii_case(callsign))
+                    .unwrap_or(false);
+            if matches {
+                found.push((i.version.as_str(), path.to_owned()));
+            }
+        }
+    }
+
+    match found.len() {
+        0 => {
+            let searched: Vec<&str> = installs.iter().map(|i| i.config_dir.as_str()).collect();
+            bail!(
+                "Couldn't find a logbook for {callsign} in {}",
+                searched.join(", ")
+            )
+        }
+        1 => Ok(found.pop().unwrap().1),
+        _ => {
+            let candidates: Vec<String> = found
+                .iter()
+                .map(|(version, path)| format!("{path} (BMS {version})"))
+                .collect();
+            bail!(
+                "{callsign} could be any of {} - pick one with --install or give a path",
+                candidates.join(", ")
+            )
+        }
+    }
+}
+
+/// Use the given directory, or the install's config directory if there isn't one.
+pub fn dir_or_config(install: Option<&Install>, dir: Option<Utf8PathBuf>) -> Result<Utf8PathBuf> {
+    match (dir, install) {
+        (Some(d), i) => Ok(resolve(i, d)),
+        (None, Some(i)) => Ok(i.config_dir.clone()),
+        (None, None) => bail!("Give a directory or pick a BMS install with --install"),
+    }
+}
+
+pub fn write<W: Write>(
+    w: &mut W,
+    installs: &[Install],
+    format: Format,
+    pretty: bool,
+) -> Result<()> {
+    if let Format::Json = format {
+        return crate::write_json(w, &installs, pretty);
+    }
+
+    let mut t = Table::new(["Version", "Directory", "Config"]);
+    for i in installs {
+        t.push(vec![
+            i.version.clone(),
+            i.base_dir.to_string(),
+            i.config_dir.to_string(),
+        ]);
+    }
+    t.write(w, format, "BMS installs")
+}
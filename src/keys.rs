@@ -0,0 +1,80 @@
+//! `[keys]` in the config file: the strings logbooks are obfuscated with,
+//! for Falcon-derived sims that changed the ones BMS uses.
+//!
+//! Each is text or `{ hex = "..." }`, and either can be left out to keep BMS's:
+//!
+//! ```toml
+//! [keys]
+//! master-key = "Falcon is your Master"
+//! password-masks = ["Who needs a password!", { hex = "526570656e64..." }]
+//! ```
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use bms_logcat::Keys;
+
+/// A key as text, or as hex for bytes that aren't
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Key {
+    Text(String),
+    Hex { hex: String },
+}
+
+impl Key {
+    fn bytes(&self) -> Result<Vec<u8>> {
+        match self {
+            Key::Text(text) => Ok(text.as_bytes().to_vec()),
+            Key::Hex { hex } => crate::signature::from_hex(hex.trim())
+                .with_context(|| crate::exit::Invalid(format!("{hex} isn't hex"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub master_key: Option<Key>,
+    pub password_masks: Option<[Key; 2]>,
+}
+
+impl Config {
+    /// The keys to use, BMS's where none are given
+    pub fn keys(&self) -> Result<Keys> {
+        let mut keys = Keys::default();
+        if let Some(key) = &self.master_key {
+            keys.master_key = key.bytes().context("Bad master-key")?;
+        }
+        if let Some([first, second]) = &self.password_masks {
+            keys.password_masks = [
+                first.bytes().context("Bad password-masks")?,
+                second.bytes().context("Bad password-masks")?,
+            ];
+        }
+        keys.check()?;
+        Ok(keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn takes_text_or_hex() {
+        let config: Config = toml::from_str(
+            "master-key = { hex = \"466131\" }\npassword-masks = [\"abc\", { hex = \"00ff\" }]",
+        )
+        .unwrap();
+        let keys = config.keys().unwrap();
+        assert_eq!(keys.master_key, b"Fa1");
+        assert_eq!(keys.password_masks, [b"abc".to_vec(), vec![0, 0xff]]);
+
+        assert_eq!(Config::default().keys().unwrap(), Keys::default());
+        let odd: Config = toml::from_str("master-key = { hex = \"466\" }").unwrap();
+        assert!(odd.keys().is_err());
+        let empty: Config = toml::from_str("master-key = \"\"").unwrap();
+        assert!(empty.keys().is_err());
+    }
+}
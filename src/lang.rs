@@ -0,0 +1,155 @@
+//! `--lang`: rank, medal, and column names in tables for people,
+//! since plenty of BMS squadrons don't fly in English.
+//!
+//! JSON, and logbooks `read` writes in other formats, stay in English
+//! so programs can read them back.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use serde::Deserialize;
+
+use crate::logbook::{Medals, Rank};
+
+#[derive(clap::ValueEnum, Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Lang {
+    /// English
+    En,
+    /// Deutsch
+    De,
+    /// Français
+    Fr,
+    /// Español
+    Es,
+}
+
+static LANG: AtomicU8 = AtomicU8::new(Lang::En as u8);
+
+pub fn set(lang: Lang) {
+    LANG.store(lang as u8, Ordering::Relaxed);
+}
+
+pub fn get() -> Lang {
+    match LANG.load(Ordering::Relaxed) {
+        l if l == Lang::De as u8 => Lang::De,
+        l if l == Lang::Fr as u8 => Lang::Fr,
+        l if l == Lang::Es as u8 => Lang::Es,
+        _ => Lang::En,
+    }
+}
+
+impl Lang {
+    /// The language's tag, for HTML's `lang` attribute
+    pub fn tag(self) -> &'static str {
+        match self {
+            Lang::En => "en",
+            Lang::De => "de",
+            Lang::Fr => "fr",
+            Lang::Es => "es",
+        }
+    }
+}
+
+/// The rank's name in the `--lang`
+pub fn rank(rank: Rank) -> String {
+    use Rank::*;
+    let name = match (get(), rank) {
+        (Lang::En, r) => return format!("{r:?}"),
+
+        (Lang::De, SecondLt) => "Leutnant",
+        (Lang::De, Leiutenant) => "Oberleutnant",
+        (Lang::De, Captain) => "Hauptmann",
+        (Lang::De, Major) => "Major",
+        (Lang::De, LtColonel) => "Oberstleutnant",
+        (Lang::De, Colonel) => "Oberst",
+        (Lang::De, BrigadierGeneral) => "Brigadegeneral",
+
+        (Lang::Fr, SecondLt) => "Sous-lieutenant",
+        (Lang::Fr, Leiutenant) => "Lieutenant",
+        (Lang::Fr, Captain) => "Capitaine",
+        (Lang::Fr, Major) => "Commandant",
+        (Lang::Fr, LtColonel) => "Lieutenant-colonel",
+        (Lang::Fr, Colonel) => "Colonel",
+        (Lang::Fr, BrigadierGeneral) => "Général de brigade",
+
+        (Lang::Es, SecondLt) => "Alférez",
+        (Lang::Es, Leiutenant) => "Teniente",
+        (Lang::Es, Captain) => "Capitán",
+        (Lang::Es, Major) => "Comandante",
+        (Lang::Es, LtColonel) => "Teniente coronel",
+        (Lang::Es, Colonel) => "Coronel",
+        (Lang::Es, BrigadierGeneral) => "General de brigada",
+    };
+    name.to_owned()
+}
+
+/// The medal's name in the `--lang`
+pub fn medal(medal: Medals) -> String {
+    use Medals::*;
+    let name = match (get(), medal) {
+        (Lang::En, m) => return format!("{m:?}"),
+
+        (Lang::De, AirForceCross) => "Air-Force-Kreuz",
+        (Lang::De, SilverStar) => "Silberstern",
+        (Lang::De, DistinguishedFlyingCross) => "Fliegerkreuz",
+        (Lang::De, AirMedal) => "Luftmedaille",
+        (Lang::De, KoreaCampaign) => "Korea-Feldzug",
+        (Lang::De, Longevity) => "Dienstalter",
+
+        (Lang::Fr, AirForceCross) => "Croix de l'Air Force",
+        (Lang::Fr, SilverStar) => "Étoile d'argent",
+        (Lang::Fr, DistinguishedFlyingCross) => "Croix du mérite aéronautique",
+        (Lang::Fr, AirMedal) => "Médaille de l'air",
+        (Lang::Fr, KoreaCampaign) => "Campagne de Corée",
+        (Lang::Fr, Longevity) => "Ancienneté",
+
+        (Lang::Es, AirForceCross) => "Cruz de la Fuerza Aérea",
+        (Lang::Es, SilverStar) => "Estrella de Plata",
+        (Lang::Es, DistinguishedFlyingCross) => "Cruz de Vuelo Distinguido",
+        (Lang::Es, AirMedal) => "Medalla Aérea",
+        (Lang::Es, KoreaCampaign) => "Campaña de Corea",
+        (Lang::Es, Longevity) => "Antigüedad",
+    };
+    name.to_owned()
+}
+
+/// A table's column name in the `--lang`, or as given if we don't have one
+pub fn label(label: &str) -> &str {
+    let lang = get();
+    let Some((_, de, fr, es)) = LABELS.iter().find(|(en, ..)| *en == label) else {
+        return label;
+    };
+    match lang {
+        Lang::En => label,
+        Lang::De => de,
+        Lang::Fr => fr,
+        Lang::Es => es,
+    }
+}
+
+/// Column names as `(English, German, French, Spanish)`
+const LABELS: &[(&str, &str, &str, &str)] = &[
+    ("Callsign", "Rufzeichen", "Indicatif", "Indicativo"),
+    ("Name", "Name", "Nom", "Nombre"),
+    ("Squadron", "Staffel", "Escadron", "Escuadrón"),
+    ("Rank", "Dienstgrad", "Grade", "Rango"),
+    ("Hours", "Stunden", "Heures", "Horas"),
+    ("+Hours", "+Stunden", "+Heures", "+Horas"),
+    ("Avg Hours", "Ø Stunden", "Heures moy.", "Horas prom."),
+    ("Missions", "Einsätze", "Missions", "Misiones"),
+    ("Kills", "Abschüsse", "Victoires", "Derribos"),
+    ("+Kills", "+Abschüsse", "+Victoires", "+Derribos"),
+    ("Killed", "Abgeschossen", "Abattu", "Derribado"),
+    ("Score", "Punkte", "Score", "Puntuación"),
+    ("Avg K/D", "Ø K/D", "K/D moy.", "K/D prom."),
+    ("Pilots", "Piloten", "Pilotes", "Pilotos"),
+    ("Taken", "Aufgenommen", "Pris le", "Tomado"),
+    ("Field", "Feld", "Champ", "Campo"),
+    ("Value", "Wert", "Valeur", "Valor"),
+    ("Stat", "Statistik", "Statistique", "Estadística"),
+    ("File", "Datei", "Fichier", "Archivo"),
+    ("Size", "Größe", "Taille", "Tamaño"),
+    ("Version", "Version", "Version", "Versión"),
+    ("Directory", "Verzeichnis", "Répertoire", "Directorio"),
+    ("Config", "Konfiguration", "Configuration", "Configuración"),
+];
@@ -0,0 +1,335 @@
+//! A logbook field by field, as it's laid out on disk,
+//! for tools that want the raw bytes or their own view of them
+//! rather than a [`Logbook`](crate::Logbook).
+//!
+//! [`patch`] changes one field's bytes and nothing else,
+//! for logbooks from BMS versions that [`Logbook`](crate::Logbook) doesn't fully model.
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use byteorder::{ByteOrder, WriteBytesExt, LE};
+use enum_iterator::IntoEnumIterator;
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+
+use crate::logbook::*;
+
+/// One field of a logbook
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldEvent {
+    /// Dotted name, like `campaign_stats.kills`, or `padding` between fields
+    pub field: String,
+    /// Where the field starts in the file
+    pub offset: usize,
+    /// The field's bytes, decrypted
+    #[serde(serialize_with = "hex")]
+    pub raw: Vec<u8>,
+    /// What the bytes mean, or `null` if they don't mean anything
+    /// (e.g., text that isn't UTF-8)
+    pub value: Value,
+}
+
+/// How to decode a field's bytes
+#[derive(Debug, Copy, Clone)]
+enum Kind {
+    Text,
+    Password,
+    F32,
+    I16,
+    I32,
+    U32,
+    Rank,
+    Medal,
+    Padding,
+}
+
+/// Walks a logbook's fields in order, yielding a [`FieldEvent`] for each.
+pub struct FieldEvents {
+    buf: Vec<u8>,
+    password_masks: [Vec<u8>; 2],
+    layout: std::vec::IntoIter<(String, usize, Kind)>,
+    position: usize,
+}
+
+/// Walk the fields of a logbook (a whole `.lbk` file, say) obfuscated with `keys`.
+/// Bytes past the end of the logbook are left alone.
+pub fn events(bytes: &[u8], keys: &Keys) -> Result<FieldEvents> {
+    ensure!(
+        bytes.len() >= LOGBOOK_LEN,
+        "Logbook is {} bytes, but should be {LOGBOOK_LEN}",
+        bytes.len()
+    );
+    keys.check()?;
+    let mut buf = bytes[..LOGBOOK_LEN].to_vec();
+    decrypt(&mut buf, 0x58, &keys.master_key);
+    Ok(FieldEvents {
+        buf,
+        password_masks: keys.password_masks.clone(),
+        layout: layout().into_iter(),
+        position: 0,
+    })
+}
+
+impl Iterator for FieldEvents {
+    type Item = FieldEvent;
+
+    fn next(&mut self) -> Option<FieldEvent> {
+        let (field, len, kind) = self.layout.next()?;
+        let offset = self.position;
+        let raw = self.buf[offset..offset + len].to_vec();
+        self.position += len;
+        let value = decode(&raw, kind, &self.password_masks);
+        Some(FieldEvent {
+            field,
+            offset,
+            raw,
+            value,
+        })
+    }
+}
+
+/// Set one field (named as in [`FieldEvent::field`]) to `value`, given as text,
+/// returning what it was before.
+///
+/// Since each encrypted byte depends on the one before it, everything from the field
+/// to the end of the file is re-encrypted, but only the field's decrypted bytes change.
+/// Bytes past the end of the logbook keep their decrypted values too.
+pub fn patch(
+    bytes: &mut [u8],
+    field: &str,
+    value: &str,
+    options: impl Into<WriteOptions>,
+) -> Result<FieldEvent> {
+    let WriteOptions { validation, keys } = options.into();
+    let old = events(bytes, &keys)?
+        .find(|e| e.field == field)
+        .ok_or_else(|| anyhow!("No field called {field}"))?;
+    let (_, len, kind) = layout()
+        .into_iter()
+        .find(|(name, _, _)| name == field)
+        .unwrap();
+    let raw = encode(value, kind, len, validation, &keys.password_masks)
+        .with_context(|| format!("Couldn't use {value:?} for {field}"))?;
+    debug_assert_eq!(raw.len(), len);
+
+    let mut plain = bytes.to_vec();
+    decrypt(&mut plain, 0x58, &keys.master_key);
+    plain[old.offset..old.offset + len].copy_from_slice(&raw);
+    bytes[old.offset..].copy_from_slice(&plain[old.offset..]);
+    encrypt_from(bytes, old.offset, 0x58, &keys.master_key);
+    Ok(old)
+}
+
+fn encode(
+    value: &str,
+    kind: Kind,
+    len: usize,
+    validation: Validation,
+    password_masks: &[Vec<u8>; 2],
+) -> Result<Vec<u8>> {
+    let mut raw = Vec::with_capacity(len);
+    match kind {
+        Kind::Text => write_padded(&mut raw, value, len, validation)?,
+        Kind::Password => write_password(&mut raw, value, validation, password_masks)?,
+        Kind::F32 => raw.write_f32::<LE>(value.parse()?)?,
+        Kind::I16 => raw.write_i16::<LE>(value.parse()?)?,
+        Kind::I32 => raw.write_i32::<LE>(value.parse()?)?,
+        Kind::U32 => raw.write_u32::<LE>(value.parse()?)?,
+        // By name, or by index for ranks we don't know.
+        Kind::Rank => match value.parse() {
+            Ok(index) => raw.write_i32::<LE>(index)?,
+            Err(_) => {
+                let rank: Rank = serde_json::from_value(value.into())
+                    .map_err(|_| anyhow!("{value} isn't a rank"))?;
+                raw.write_i32::<LE>(rank as i32)?
+            }
+        },
+        Kind::Medal => raw.push(value.parse::<bool>()? as u8),
+        Kind::Padding => bail!("Padding isn't a field"),
+    }
+    Ok(raw)
+}
+
+fn decode(raw: &[u8], kind: Kind, password_masks: &[Vec<u8>; 2]) -> Value {
+    let text = |raw: &[u8]| match std::str::from_utf8(raw) {
+        Ok(s) => Value::String(s.split('\0').next().unwrap().to_owned()),
+        Err(_) => Value::Null,
+    };
+    match kind {
+        Kind::Text => text(raw),
+        // It's only ever NUL-terminated if it's valid.
+        Kind::Password if raw.last() == Some(&0) => {
+            let mut pw = raw.to_vec();
+            xor_password(&mut pw, password_masks);
+            text(&pw)
+        }
+        Kind::Password => Value::Null,
+        Kind::F32 => serde_json::json!(LE::read_f32(raw)),
+        Kind::I16 => LE::read_i16(raw).into(),
+        Kind::I32 => LE::read_i32(raw).into(),
+        Kind::U32 | Kind::Padding if raw.len() == 4 => LE::read_u32(raw).into(),
+        Kind::U32 => Value::Null,
+        Kind::Rank => {
+            let index = LE::read_i32(raw);
+            Rank::try_from(index)
+                .ok()
+                .and_then(|r| serde_json::to_value(r).ok())
+                .unwrap_or_else(|| index.into())
+        }
+        Kind::Medal => Value::Bool(raw[0] > 0),
+        Kind::Padding => Value::Null,
+    }
+}
+
+/// Every field, in order, with its length
+fn layout() -> Vec<(String, usize, Kind)> {
+    use Kind::*;
+    let mut fields: Vec<(String, usize, Kind)> = Vec::new();
+    let mut add = |name: &str, len, kind| fields.push((name.to_owned(), len, kind));
+
+    add("name", NAME_LEN + 1, Text);
+    add("callsign", CALLSIGN_LEN + 1, Text);
+    add("password", PASSWORD_LEN + 1, Password);
+    add("commissioned", COMM_LEN + 1, Text);
+    add("options_file", CALLSIGN_LEN + 1, Text);
+    add("padding", 1, Padding);
+    add("flight_hours", 4, F32);
+    add("ace_factor", 4, F32);
+    add("rank", 4, Rank);
+    for name in [
+        "matches_won",
+        "matches_lost",
+        "matches_won_versus_humans",
+        "matches_lost_versus_humans",
+        "kills",
+        "killed",
+        "human_kills",
+        "killed_versus_humans",
+    ] {
+        add(&format!("dogfight_stats.{name}"), 2, I16);
+    }
+    for (name, kind) in [
+        ("games_won", I16),
+        ("game_lost", I16),
+        ("games_tied", I16),
+        ("missions", I16),
+        ("total_score", I32),
+        ("total_mission_score", I32),
+        ("consecutive_missions", I16),
+        ("kills", I16),
+        ("killed", I16),
+        ("human_kills", I16),
+        ("killed_versus_humans", I16),
+        ("self_kills", I16),
+        ("air_to_ground_kills", I16),
+        ("static_kills", I16),
+        ("naval_kills", I16),
+        ("friendly_kills", I16),
+        ("missions_since_last_friendly_kill", I16),
+    ] {
+        let len = if let I32 = kind { 4 } else { 2 };
+        add(&format!("campaign_stats.{name}"), len, kind);
+    }
+    add("padding", 2, Padding);
+    for medal in Medals::into_enum_iter() {
+        add(&format!("medals.{medal:?}"), 1, Medal);
+    }
+    add("padding", 2, Padding);
+    add("picture_resource_id", 4, U32);
+    add("picture_file", FILENAME_LEN + 1, Text);
+    add("padding", 3, Padding);
+    add("patch_resource_id", 4, U32);
+    add("patch_file", FILENAME_LEN + 1, Text);
+    add("personal_text", PERSONAL_TEXT_LEN + 1, Text);
+    add("squadron", NAME_LEN, Text);
+    add("voice", 2, I16);
+    add("checksum", 4, U32);
+    fields
+}
+
+fn hex<S: Serializer>(bytes: &[u8], s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(&to_hex(bytes))
+}
+
+/// Bytes as lowercase hex, two digits each, like `0a1b`
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn agrees_with_parse() {
+        let book = crate::LogbookBuilder::new("Jane Doe", "Viper")
+            .unwrap()
+            .password("hunter2")
+            .unwrap()
+            .rank(Rank::Major)
+            .medal(Medals::AirMedal)
+            .squadron("80th FS")
+            .unwrap()
+            .voice(3)
+            .unwrap()
+            .build();
+        let bytes = book.to_bytes().unwrap();
+        let expected = serde_json::to_value(&book).unwrap();
+
+        let events: Vec<FieldEvent> = events(&bytes, &Keys::default()).unwrap().collect();
+        let last = events.last().unwrap();
+        assert_eq!(last.offset + last.raw.len(), LOGBOOK_LEN);
+
+        for e in events {
+            if e.field == "padding" || e.field.ends_with("resource_id") || e.field == "checksum" {
+                continue;
+            }
+            if let Some(medal) = e.field.strip_prefix("medals.") {
+                let has = expected["medals"]
+                    .as_array()
+                    .unwrap()
+                    .contains(&medal.into());
+                assert_eq!(e.value, Value::Bool(has), "{}", e.field);
+                continue;
+            }
+            let pointer = format!("/{}", e.field.replace('.', "/"));
+            assert_eq!(Some(&e.value), expected.pointer(&pointer), "{}", e.field);
+        }
+    }
+
+    #[test]
+    fn patches_one_field() {
+        let book = crate::LogbookBuilder::new("Jane Doe", "Viper")
+            .unwrap()
+            .build();
+        let mut bytes = book.to_bytes().unwrap();
+        // As if from a newer BMS, with more after the logbook.
+        bytes.extend_from_slice(&[1, 2, 3]);
+        let before: Vec<FieldEvent> = events(&bytes, &Keys::default()).unwrap().collect();
+        let mut trailing = bytes.clone();
+        decrypt(&mut trailing, 0x58, &Keys::default().master_key);
+
+        let old = patch(&mut bytes, "campaign_stats.kills", "12", Validation::Strict).unwrap();
+        assert_eq!(old.value, Value::from(0));
+        patch(&mut bytes, "rank", "Major", Validation::Strict).unwrap();
+        patch(&mut bytes, "password", "hunter2", Validation::Strict).unwrap();
+
+        let after: Vec<FieldEvent> = events(&bytes, &Keys::default()).unwrap().collect();
+        for (b, a) in before.iter().zip(&after) {
+            match a.field.as_str() {
+                "campaign_stats.kills" => assert_eq!(a.value, Value::from(12)),
+                "rank" => assert_eq!(a.value, Value::from("Major")),
+                "password" => assert_eq!(a.value, Value::from("hunter2")),
+                _ => assert_eq!(a.raw, b.raw, "{}", a.field),
+            }
+        }
+        let mut patched = bytes.clone();
+        decrypt(&mut patched, 0x58, &Keys::default().master_key);
+        assert_eq!(patched[LOGBOOK_LEN..], trailing[LOGBOOK_LEN..]);
+
+        let book = Logbook::parse_bytes(&bytes[..LOGBOOK_LEN]).unwrap();
+        assert_eq!(book.campaign_stats.kills, 12);
+        assert!(patch(&mut bytes, "voice", "40000", Validation::Strict).is_err());
+        assert!(patch(&mut bytes, "name", &"x".repeat(30), Validation::Strict).is_err());
+        assert!(patch(&mut bytes, "padding", "0", Validation::Strict).is_err());
+    }
+}
@@ -0,0 +1,115 @@
+use std::cmp::Ordering;
+use std::io::prelude::*;
+
+use anyhow::Result;
+use camino::Utf8PathBuf;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::batch::Entry;
+use crate::humanize;
+use crate::lang;
+use crate::logbook::Rank;
+use crate::stats::DerivedStats;
+use crate::table::{self, Format, Table};
+
+/// What to rank pilots by.
+#[derive(clap::ValueEnum, Debug, Copy, Clone)]
+pub enum SortKey {
+    Kills,
+    Hours,
+    Score,
+    Kd,
+}
+
+/// One pilot's line on the leaderboard.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Standing {
+    pub place: usize,
+    pub callsign: String,
+    pub name: String,
+    pub squadron: String,
+    pub rank: Rank,
+    pub flight_hours: f32,
+    pub missions: i16,
+    pub kills: i16,
+    pub score: i32,
+    pub kill_death_ratio: Option<f32>,
+    #[schemars(with = "String")]
+    pub file: Utf8PathBuf,
+}
+
+/// Rank the given pilots, best first.
+///
+/// Campaign stats are used throughout since that's what
+/// multiplayer groups fly together.
+pub fn standings(entries: &[Entry], sort: SortKey) -> Vec<Standing> {
+    let mut standings: Vec<Standing> = entries
+        .iter()
+        .map(|e| {
+            let book = &e.logbook;
+            Standing {
+                place: 0,
+                callsign: book.callsign.clone(),
+                name: book.name.clone(),
+                squadron: book.squadron.clone(),
+                rank: book.rank,
+                flight_hours: book.flight_hours,
+                missions: book.campaign_stats.missions,
+                kills: book.campaign_stats.kills,
+                score: book.campaign_stats.total_score,
+                kill_death_ratio: DerivedStats::new(book).campaign_kill_death_ratio,
+                file: e.path.clone(),
+            }
+        })
+        .collect();
+
+    standings.sort_by(|a, b| compare(b, a, sort).then_with(|| a.callsign.cmp(&b.callsign)));
+    for (i, s) in standings.iter_mut().enumerate() {
+        s.place = i + 1;
+    }
+    standings
+}
+
+fn compare(a: &Standing, b: &Standing, sort: SortKey) -> Ordering {
+    match sort {
+        SortKey::Kills => a.kills.cmp(&b.kills),
+        SortKey::Hours => a.flight_hours.total_cmp(&b.flight_hours),
+        SortKey::Score => a.score.cmp(&b.score),
+        SortKey::Kd => {
+            let kd = |s: &Standing| s.kill_death_ratio.unwrap_or(f32::NEG_INFINITY);
+            kd(a).total_cmp(&kd(b))
+        }
+    }
+}
+
+pub fn write<W: Write>(
+    w: &mut W,
+    standings: &[Standing],
+    format: Format,
+    pretty: bool,
+) -> Result<()> {
+    if let Format::Json = format {
+        return crate::write_json(w, &standings, pretty);
+    }
+
+    let mut t = Table::new([
+        "#", "Callsign", "Name", "Squadron", "Rank", "Hours", "Missions", "Kills", "Score", "K/D",
+    ]);
+    for s in standings {
+        t.push(vec![
+            s.place.to_string(),
+            s.callsign.clone(),
+            s.name.clone(),
+            s.squadron.clone(),
+            lang::rank(s.rank),
+            humanize::hours(s.flight_hours),
+            humanize::count(s.missions),
+            humanize::count(s.kills),
+            humanize::count(s.score),
+            table::optional(s.kill_death_ratio, 2),
+        ]);
+    }
+
+    t.write(w, format, "Leaderboard")
+}
@@ -0,0 +1,40 @@
+//! Read and write Falcon BMS logbooks (`*.lbk`).
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use bms_logcat::{Logbook, Validation};
+//!
+//! let book = Logbook::parse(std::fs::File::open("Viper.lbk")?)?;
+//! println!("{} has flown {} hours", book.callsign, book.flight_hours);
+//! book.write(std::fs::File::create("Viper2.lbk")?, Validation::Strict)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Errors carry the [`exit`] type saying what went wrong,
+//! e.g., [`exit::Invalid`] for values BMS won't take.
+//!
+//! The `async` feature adds [`nonblocking`], for reading and writing them from tokio,
+//! the `ffi` feature a C ABI in [`ffi`],
+//! the `python` feature a Python module built with maturin,
+//! and the `wasm` feature [`wasm`] bindings for browsers.
+
+pub mod builder;
+pub mod exit;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod layout;
+pub mod logbook;
+#[cfg(feature = "async")]
+pub mod nonblocking;
+#[cfg(feature = "python")]
+mod python;
+pub mod show;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use builder::LogbookBuilder;
+pub use logbook::{
+    CampaignStats, DogfightStats, Keys, Logbook, Medals, ParseOptions, ParseWarning, Rank,
+    Validation, WriteOptions,
+};
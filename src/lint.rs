@@ -0,0 +1,663 @@
+//! Impossible or suspicious numbers in logbooks:
+//! the sort of thing a hex editor or a bad import leaves behind.
+//! `lint` reports them; `sanitize` fixes what it can.
+//!
+//! What counts as suspicious comes from a policy, given with `--policy`
+//! or as a `[policy]` table in the config file, so leagues can set their own rules:
+//!
+//! ```toml
+//! max-hours = 3000
+//! min-ace-factor = 0.5
+//! max-ace-factor = 1.5
+//! max-kills = 500
+//! max-kills-per-mission = 4
+//! squadrons = ["VF-103", "VF-143"]
+//! ```
+
+use anyhow::{ensure, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use log::*;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::doctor::{Finding, Severity};
+use crate::logbook::{self, Logbook, ParseOptions};
+use crate::{batch, canonical};
+
+/// The ace factor BMS accepts, from rookie to ace
+const ACE_FACTOR_RANGE: std::ops::RangeInclusive<f32> = 0.0..=2.0;
+
+/// Counters that count some of what another does, as `(part, whole, why)`
+const SUBSETS: &[(&str, &str, &str)] = &[
+    (
+        "campaign_stats.human_kills",
+        "campaign_stats.kills",
+        "kills of human pilots are counted among all kills",
+    ),
+    (
+        "campaign_stats.killed_versus_humans",
+        "campaign_stats.killed",
+        "deaths to human pilots are counted among all deaths",
+    ),
+    (
+        "campaign_stats.consecutive_missions",
+        "campaign_stats.missions",
+        "a streak of missions is made of missions flown",
+    ),
+    (
+        "campaign_stats.killed",
+        "campaign_stats.missions",
+        "a pilot can only be killed once per mission",
+    ),
+    (
+        "dogfight_stats.human_kills",
+        "dogfight_stats.kills",
+        "kills of human pilots are counted among all kills",
+    ),
+    (
+        "dogfight_stats.killed_versus_humans",
+        "dogfight_stats.killed",
+        "deaths to human pilots are counted among all deaths",
+    ),
+    (
+        "dogfight_stats.matches_won_versus_humans",
+        "dogfight_stats.matches_won",
+        "matches won against humans are counted among all matches won",
+    ),
+    (
+        "dogfight_stats.matches_lost_versus_humans",
+        "dogfight_stats.matches_lost",
+        "matches lost to humans are counted among all matches lost",
+    ),
+];
+
+/// Counters that together can't be more than another, as `(parts, whole, why)`
+const SUMS: &[(&[&str], &str, &str)] = &[(
+    &[
+        "campaign_stats.games_won",
+        "campaign_stats.game_lost",
+        "campaign_stats.games_tied",
+    ],
+    "campaign_stats.missions",
+    "every campaign ends in one of them, and takes at least one mission",
+)];
+
+/// Counters that are unlikely to be above zero unless another is,
+/// as `(counter, needs, why)`
+const NEEDS: &[(&str, &str, &str)] = &[
+    (
+        "dogfight_stats.matches_won",
+        "dogfight_stats.kills",
+        "matches are won by shooting the other side down",
+    ),
+    (
+        "dogfight_stats.matches_lost",
+        "dogfight_stats.killed",
+        "matches are lost by being shot down",
+    ),
+];
+
+/// Where suspicious becomes worth mentioning
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Policy {
+    pub max_hours: f32,
+    /// The ace factor range to allow, within the one BMS does
+    pub min_ace_factor: f32,
+    pub max_ace_factor: f32,
+    /// Most campaign kills (of any kind) a pilot can have
+    pub max_kills: Option<i64>,
+    pub max_kills_per_mission: f32,
+    /// Squadrons pilots can fly for, if only some (ignoring case)
+    pub squadrons: Option<Vec<String>>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            max_hours: 10_000.0,
+            min_ace_factor: *ACE_FACTOR_RANGE.start(),
+            max_ace_factor: *ACE_FACTOR_RANGE.end(),
+            max_kills: None,
+            max_kills_per_mission: 10.0,
+            squadrons: None,
+        }
+    }
+}
+
+impl Policy {
+    pub fn load(path: &Utf8Path) -> Result<Self> {
+        let text =
+            std::fs::read_to_string(path).with_context(|| format!("Couldn't read {path}"))?;
+        let policy: Self = toml::from_str(&text)
+            .with_context(|| crate::exit::Unparsable(format!("Couldn't parse {path}")))?;
+        policy
+            .check()
+            .with_context(|| crate::exit::Invalid(format!("Bad policy in {path}")))?;
+        debug!("Loaded {path}: {policy:?}");
+        Ok(policy)
+    }
+
+    /// Make sure the limits make sense.
+    pub fn check(&self) -> Result<()> {
+        ensure!(
+            self.min_ace_factor <= self.max_ace_factor,
+            "min-ace-factor is more than max-ace-factor"
+        );
+        Ok(())
+    }
+}
+
+/// Lint the given logbooks, and every logbook in the given directories,
+/// most urgent findings first.
+pub fn lint(
+    paths: &[Utf8PathBuf],
+    policy: &Policy,
+    filter: &batch::Filter,
+    parsing: ParseOptions,
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for path in paths {
+        if !path.is_dir() {
+            lint_file(path, policy, parsing.clone(), &mut findings);
+            continue;
+        }
+        match batch::logbooks_in(path, filter) {
+            Ok(books) => {
+                for book in &books {
+                    lint_file(book, policy, parsing.clone(), &mut findings);
+                }
+            }
+            Err(e) => findings.push(Finding::new(
+                Severity::Error,
+                path,
+                format!("{e:#}"),
+                "Check the directory exists and you can read it",
+            )),
+        }
+    }
+    findings.sort_by_key(|f| f.severity);
+    findings
+}
+
+fn lint_file(path: &Utf8Path, policy: &Policy, parsing: ParseOptions, findings: &mut Vec<Finding>) {
+    match batch::parse_file_with_report(path, parsing) {
+        Ok((book, warnings)) => {
+            for warning in warnings {
+                findings.push(Finding::new(
+                    Severity::Note,
+                    path,
+                    warning.to_string(),
+                    "Rewrite it with sanitize if BMS has trouble with it",
+                ));
+            }
+            findings.extend(check(path, &book, policy));
+        }
+        Err(e) => findings.push(Finding::new(
+            Severity::Error,
+            path,
+            format!("{e:#}"),
+            "Try sanitize, or restore it from a backup",
+        )),
+    }
+}
+
+/// Check one logbook's numbers.
+pub fn check(path: &Utf8Path, book: &Logbook, policy: &Policy) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut found = |severity, problem: String, fix: &str| {
+        findings.push(Finding::new(severity, path, problem, fix))
+    };
+    let fix = "Fix it with sanitize, or restore it from a backup";
+
+    // Counters only go up, so any below zero wrapped or were hand-edited.
+    for (name, n) in negative_counters(book) {
+        found(Severity::Error, format!("{name} is negative ({n})"), fix);
+    }
+
+    let hours = book.flight_hours;
+    if !hours.is_finite() || hours < 0.0 {
+        found(Severity::Error, format!("flight_hours is {hours}"), fix);
+    } else if hours > policy.max_hours {
+        found(
+            Severity::Warning,
+            format!(
+                "flight_hours is {hours:.1}, more than the policy's {}",
+                policy.max_hours
+            ),
+            "Check the hours, or raise the policy's max-hours if they're real",
+        );
+    }
+
+    let ace = book.ace_factor;
+    if !ACE_FACTOR_RANGE.contains(&ace) {
+        found(
+            Severity::Error,
+            format!(
+                "ace_factor is {ace}, outside the {}-{} BMS allows",
+                ACE_FACTOR_RANGE.start(),
+                ACE_FACTOR_RANGE.end()
+            ),
+            fix,
+        );
+    } else if !(policy.min_ace_factor..=policy.max_ace_factor).contains(&ace) {
+        found(
+            Severity::Warning,
+            format!(
+                "ace_factor is {ace}, outside the policy's {}-{}",
+                policy.min_ace_factor, policy.max_ace_factor
+            ),
+            "Check the ace factor, or widen the policy's range if it's real",
+        );
+    }
+
+    if let Some(squadrons) = &policy.squadrons {
+        if !squadrons
+            .iter()
+            .any(|s| s.eq_ignore_ascii_case(&book.squadron))
+        {
+            found(
+                Severity::Warning,
+                format!(
+                    "Squadron \"{}\" isn't one of the policy's ({})",
+                    book.squadron,
+                    squadrons.join(", ")
+                ),
+                "Move the pilot to one with write, or add it to the policy's squadrons",
+            );
+        }
+    }
+
+    let c = &book.campaign_stats;
+    let kills = c.kills as i32
+        + c.air_to_ground_kills as i32
+        + c.static_kills as i32
+        + c.naval_kills as i32;
+    if let Some(max) = policy.max_kills.filter(|max| kills as i64 > *max) {
+        found(
+            Severity::Warning,
+            format!("{kills} campaign kills, more than the policy's {max}"),
+            "Check the kills, or raise the policy's max-kills if they're real",
+        );
+    }
+    if kills > 0 && c.missions <= 0 {
+        found(
+            Severity::Warning,
+            format!("{kills} campaign kills but no missions"),
+            "Check the kills and missions, or restore it from a backup",
+        );
+    } else if c.missions > 0 && kills as f32 / c.missions as f32 > policy.max_kills_per_mission {
+        found(
+            Severity::Warning,
+            format!(
+                "{kills} campaign kills in {} missions, over {} per mission",
+                c.missions, policy.max_kills_per_mission
+            ),
+            "Check the kills, or raise the policy's max-kills-per-mission if they're real",
+        );
+    }
+
+    let counters = serde_json::to_value(book).expect("logbooks serialize");
+    for (part, whole, why) in SUBSETS {
+        let (p, w) = (counter(&counters, part), counter(&counters, whole));
+        if p > w {
+            found(
+                Severity::Warning,
+                format!("{part} ({p}) is more than {whole} ({w}), but {why}"),
+                fix,
+            );
+        }
+    }
+    for (parts, whole, why) in SUMS {
+        let sum: i64 = parts.iter().map(|p| counter(&counters, p)).sum();
+        let w = counter(&counters, whole);
+        if sum > w {
+            found(
+                Severity::Warning,
+                format!(
+                    "{} ({sum}) is more than {whole} ({w}), but {why}",
+                    parts.join(" + ")
+                ),
+                "Check the counters, or restore it from a backup",
+            );
+        }
+    }
+    for (name, needs, why) in NEEDS {
+        let (n, other) = (counter(&counters, name), counter(&counters, needs));
+        if n > 0 && other <= 0 {
+            found(
+                Severity::Note,
+                format!("{name} is {n} with no {needs}, but {why}"),
+                "Check the dogfight stats, or restore it from a backup",
+            );
+        }
+    }
+
+    let since = c.missions_since_last_friendly_kill;
+    if since > c.missions {
+        found(
+            Severity::Warning,
+            format!(
+                "campaign_stats.missions_since_last_friendly_kill ({since}) is more than campaign_stats.missions ({})",
+                c.missions
+            ),
+            fix,
+        );
+    } else if c.friendly_kills == 0 && since < c.missions {
+        found(
+            Severity::Warning,
+            format!(
+                "No friendly kills, but only {since} of {} missions since the last one",
+                c.missions
+            ),
+            fix,
+        );
+    } else if c.friendly_kills > 0 && since == c.missions && c.missions > 0 {
+        found(
+            Severity::Warning,
+            format!(
+                "{} friendly kill(s), but none in any of {} missions",
+                c.friendly_kills, c.missions
+            ),
+            "Check the friendly kills, or restore it from a backup",
+        );
+    }
+
+    findings
+}
+
+/// Dotted counter names (e.g., `campaign_stats.kills`) and values, for those below zero
+fn negative_counters(book: &Logbook) -> Vec<(String, i64)> {
+    let mut negative = Vec::new();
+    for (group, stats) in [
+        ("dogfight_stats", serde_json::to_value(&book.dogfight_stats)),
+        ("campaign_stats", serde_json::to_value(&book.campaign_stats)),
+    ] {
+        let stats = stats.expect("stats serialize");
+        for (name, value) in stats.as_object().into_iter().flatten() {
+            // Scores can go negative honestly, by bombing your own side.
+            if name.ends_with("score") {
+                continue;
+            }
+            if let Some(n) = value.as_i64().filter(|n| *n < 0) {
+                negative.push((format!("{group}.{name}"), n));
+            }
+        }
+    }
+    negative
+}
+
+fn pointer(name: &str) -> String {
+    format!("/{}", name.replace('.', "/"))
+}
+
+fn counter(book: &Value, name: &str) -> i64 {
+    book.pointer(&pointer(name))
+        .and_then(Value::as_i64)
+        .unwrap_or_default()
+}
+
+/// Fix what [`check`] calls impossible: clamp counters and the ace factor into range,
+/// reset unknown voices, cut strings down to what their fields hold,
+/// and zero-pad the commissioned date.
+///
+/// Suspicious but possible numbers, like thousands of flight hours, are left alone.
+pub fn sanitize(book: &mut Logbook) -> Result<()> {
+    // Fix these first; JSON can't hold NaN.
+    if !book.flight_hours.is_finite() || book.flight_hours < 0.0 {
+        book.flight_hours = 0.0;
+    }
+    book.ace_factor = if book.ace_factor.is_nan() {
+        // Halfway between rookie and ace
+        (ACE_FACTOR_RANGE.start() + ACE_FACTOR_RANGE.end()) / 2.0
+    } else {
+        book.ace_factor
+            .clamp(*ACE_FACTOR_RANGE.start(), *ACE_FACTOR_RANGE.end())
+    };
+
+    let mut value = serde_json::to_value(&*book)?;
+    for (name, _) in negative_counters(book) {
+        set_counter(&mut value, &name, 0);
+    }
+    for (part, whole, _) in SUBSETS {
+        let whole = counter(&value, whole);
+        if counter(&value, part) > whole {
+            set_counter(&mut value, part, whole);
+        }
+    }
+    *book = serde_json::from_value(value)?;
+
+    let c = &mut book.campaign_stats;
+    if c.friendly_kills == 0 {
+        c.missions_since_last_friendly_kill = c.missions;
+    } else {
+        c.missions_since_last_friendly_kill = c.missions_since_last_friendly_kill.min(c.missions);
+    }
+
+    if !(0..logbook::VOICES).contains(&book.voice) {
+        book.voice = 0;
+    }
+
+    book.commissioned = commissioned(&book.commissioned);
+    book.truncate_strings();
+    Ok(())
+}
+
+fn set_counter(book: &mut Value, name: &str, n: i64) {
+    if let Some(v) = book.pointer_mut(&pointer(name)) {
+        *v = n.into();
+    }
+}
+
+/// A commissioned date as BMS writes it, `MM/DD/YY`, from that or `YYYY-MM-DD`
+fn commissioned(date: &str) -> String {
+    let date = date.trim();
+    let parts: Vec<&str> = date.split('-').collect();
+    match parts[..] {
+        [y, m, d] if y.len() == 4 && parts.iter().all(|p| p.parse::<u32>().is_ok()) => {
+            format!("{m:0>2}/{d:0>2}/{}", &y[2..])
+        }
+        _ => canonical::date(date),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logbook::CampaignStats;
+
+    fn problems(book: &Logbook, policy: &Policy) -> Vec<String> {
+        check(Utf8Path::new("Viper.lbk"), book, policy)
+            .into_iter()
+            .map(|f| f.problem)
+            .collect()
+    }
+
+    #[test]
+    fn applies_the_policy() {
+        let mut book = Logbook {
+            flight_hours: 120.0,
+            ace_factor: 1.0,
+            squadron: "VF-103".into(),
+            campaign_stats: CampaignStats {
+                missions: 10,
+                missions_since_last_friendly_kill: 10,
+                kills: 20,
+                ..CampaignStats::default()
+            },
+            ..Logbook::default()
+        };
+        assert_eq!(problems(&book, &Policy::default()), [] as [String; 0]);
+
+        let strict = Policy {
+            max_hours: 100.0,
+            min_ace_factor: 1.5,
+            max_ace_factor: 2.0,
+            max_kills: Some(10),
+            max_kills_per_mission: 1.0,
+            squadrons: Some(vec!["vf-143".into()]),
+        };
+        assert_eq!(
+            problems(&book, &strict),
+            [
+                "flight_hours is 120.0, more than the policy's 100",
+                "ace_factor is 1, outside the policy's 1.5-2",
+                "Squadron \"VF-103\" isn't one of the policy's (vf-143)",
+                "20 campaign kills, more than the policy's 10",
+                "20 campaign kills in 10 missions, over 1 per mission",
+            ]
+        );
+        strict.check().unwrap();
+        let backwards = Policy {
+            min_ace_factor: 1.5,
+            max_ace_factor: 0.5,
+            ..Policy::default()
+        };
+        assert!(backwards.check().is_err());
+
+        // What BMS itself can't take is an error whatever the policy says.
+        book.flight_hours = -1.0;
+        book.ace_factor = 2.5;
+        let errors: Vec<_> = check(Utf8Path::new("Viper.lbk"), &book, &Policy::default())
+            .into_iter()
+            .filter(|f| f.severity == Severity::Error)
+            .map(|f| f.problem)
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                "flight_hours is -1",
+                "ace_factor is 2.5, outside the 0-2 BMS allows"
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_the_friendly_kill_streak() {
+        let mut book = Logbook::default();
+        let c = &mut book.campaign_stats;
+        c.missions = 5;
+        c.missions_since_last_friendly_kill = 6;
+        assert_eq!(
+            problems(&book, &Policy::default()),
+            ["campaign_stats.missions_since_last_friendly_kill (6) is more than campaign_stats.missions (5)"]
+        );
+
+        book.campaign_stats.missions_since_last_friendly_kill = 3;
+        assert_eq!(
+            problems(&book, &Policy::default()),
+            ["No friendly kills, but only 3 of 5 missions since the last one"]
+        );
+
+        book.campaign_stats.friendly_kills = 2;
+        assert_eq!(problems(&book, &Policy::default()), [] as [String; 0]);
+
+        book.campaign_stats.missions_since_last_friendly_kill = 5;
+        assert_eq!(
+            problems(&book, &Policy::default()),
+            ["2 friendly kill(s), but none in any of 5 missions"]
+        );
+    }
+
+    #[test]
+    fn sanitizes_what_check_calls_impossible() {
+        let mut book = Logbook {
+            flight_hours: f32::NAN,
+            ace_factor: 3.0,
+            voice: logbook::VOICES,
+            campaign_stats: CampaignStats {
+                missions: 4,
+                kills: -2,
+                friendly_kills: 1,
+                missions_since_last_friendly_kill: 9,
+                total_score: -7,
+                ..CampaignStats::default()
+            },
+            commissioned: "2024-01-02".into(),
+            ..Logbook::default()
+        };
+        sanitize(&mut book).unwrap();
+
+        assert_eq!(book.flight_hours, 0.0);
+        assert_eq!(book.ace_factor, 2.0);
+        assert_eq!(book.voice, 0);
+        assert_eq!(book.campaign_stats.kills, 0);
+        assert_eq!(book.campaign_stats.missions_since_last_friendly_kill, 4);
+        assert_eq!(
+            book.campaign_stats.total_score, -7,
+            "scores can go negative"
+        );
+        assert_eq!(book.commissioned, "01/02/24");
+        let errors = check(Utf8Path::new("Viper.lbk"), &book, &Policy::default());
+        assert!(errors.iter().all(|f| f.severity != Severity::Error));
+
+        book.ace_factor = f32::NAN;
+        book.voice = -1;
+        book.campaign_stats.friendly_kills = 0;
+        sanitize(&mut book).unwrap();
+        assert_eq!(book.ace_factor, 1.0);
+        assert_eq!(book.voice, 0);
+        assert_eq!(book.campaign_stats.missions_since_last_friendly_kill, 4);
+    }
+
+    /// A default logbook with the given counters set
+    fn with_counters(counters: &[(&str, i64)]) -> Logbook {
+        let mut value = serde_json::to_value(Logbook::default()).unwrap();
+        for (name, n) in counters {
+            set_counter(&mut value, name, *n);
+        }
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn checks_each_relation() {
+        for (part, whole, why) in SUBSETS {
+            let mut book = with_counters(&[(part, 1)]);
+            assert_eq!(
+                problems(&book, &Policy::default()),
+                [format!("{part} (1) is more than {whole} (0), but {why}")]
+            );
+            sanitize(&mut book).unwrap();
+            assert_eq!(problems(&book, &Policy::default()), [] as [String; 0]);
+
+            let book = with_counters(&[(part, 1), (whole, 1)]);
+            let problems = problems(&book, &Policy::default());
+            assert!(!problems.iter().any(|p| p.ends_with(why)), "{problems:?}");
+        }
+
+        for (parts, whole, why) in SUMS {
+            let mut counters: Vec<_> = parts.iter().map(|p| (*p, 1)).collect();
+            let book = with_counters(&counters);
+            assert_eq!(
+                problems(&book, &Policy::default()),
+                [format!(
+                    "{} ({}) is more than {whole} (0), but {why}",
+                    parts.join(" + "),
+                    parts.len()
+                )]
+            );
+            counters.push((whole, parts.len() as i64));
+            let book = with_counters(&counters);
+            let problems = problems(&book, &Policy::default());
+            assert!(!problems.iter().any(|p| p.ends_with(why)), "{problems:?}");
+        }
+
+        for (name, needs, why) in NEEDS {
+            let book = with_counters(&[(name, 1)]);
+            assert_eq!(
+                problems(&book, &Policy::default()),
+                [format!("{name} is 1 with no {needs}, but {why}")]
+            );
+            let book = with_counters(&[(name, 1), (needs, 1)]);
+            let problems = problems(&book, &Policy::default());
+            assert!(!problems.iter().any(|p| p.ends_with(why)), "{problems:?}");
+        }
+    }
+
+    #[test]
+    fn writes_commissioned_dates_as_bms_does() {
+        assert_eq!(commissioned("2024-01-02"), "01/02/24");
+        assert_eq!(commissioned(" 1/2/24 "), "01/02/24");
+        assert_eq!(commissioned("yesterday"), "yesterday");
+    }
+}
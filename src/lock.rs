@@ -0,0 +1,42 @@
+//! Cross-process advisory locks, so that our tools (watch mode, edits, squadron scripts)
+//! don't interleave writes to the same logbook.
+//!
+//! Locks are released when the returned file is closed.
+//! BMS itself doesn't take them, hence the separate check in `running`.
+
+use std::fs::{File, OpenOptions};
+
+use anyhow::{Context, Result};
+use camino::Utf8Path;
+use fs2::FileExt;
+use log::*;
+
+/// Open a file for reading, holding a shared lock.
+pub fn open_shared(path: &Utf8Path) -> Result<File> {
+    let f = File::open(path).with_context(|| format!("Couldn't read {path}"))?;
+    if f.try_lock_shared().is_err() {
+        info!("Waiting for another process to finish writing {path}");
+        f.lock_shared()
+            .with_context(|| format!("Couldn't lock {path}"))?;
+    }
+    Ok(f)
+}
+
+/// Create or truncate a file for writing, holding an exclusive lock.
+pub fn create_exclusive(path: &Utf8Path) -> Result<File> {
+    // Don't truncate until we hold the lock - someone else might be reading it.
+    let f = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .with_context(|| format!("Couldn't write to {path}"))?;
+    if f.try_lock_exclusive().is_err() {
+        info!("Waiting for other processes to finish with {path}");
+        f.lock_exclusive()
+            .with_context(|| format!("Couldn't lock {path}"))?;
+    }
+    f.set_len(0)
+        .with_context(|| format!("Couldn't truncate {path}"))?;
+    Ok(f)
+}
@@ -2,13 +2,28 @@ use std::{collections::BTreeSet, io::prelude::*};
 
 use anyhow::{anyhow, ensure, Result};
 use byte_struct::*;
-use byteorder::{ReadBytesExt, WriteBytesExt, LE};
+use byteorder::{ByteOrder, WriteBytesExt, LE};
 use camino::Utf8PathBuf;
 use enum_iterator::IntoEnumIterator;
+use log::*;
 use num_enum::{IntoPrimitive, TryFromPrimitive};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Copy, Clone, IntoPrimitive, TryFromPrimitive, Serialize, Deserialize)]
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    PartialOrd,
+    Ord,
+    PartialEq,
+    Eq,
+    IntoPrimitive,
+    TryFromPrimitive,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+)]
 #[repr(i32)]
 pub enum Rank {
     SecondLt,
@@ -20,6 +35,8 @@ pub enum Rank {
     BrigadierGeneral,
 }
 
+// Not derived: num_enum would take `#[default]` as the rank for any unknown index.
+#[allow(clippy::derivable_impls)]
 impl Default for Rank {
     fn default() -> Self {
         Rank::SecondLt
@@ -27,7 +44,17 @@ impl Default for Rank {
 }
 
 #[derive(
-    Debug, Copy, Clone, PartialOrd, Ord, PartialEq, Eq, IntoEnumIterator, Serialize, Deserialize,
+    Debug,
+    Copy,
+    Clone,
+    PartialOrd,
+    Ord,
+    PartialEq,
+    Eq,
+    IntoEnumIterator,
+    Serialize,
+    Deserialize,
+    JsonSchema,
 )]
 pub enum Medals {
     AirForceCross,
@@ -38,8 +65,12 @@ pub enum Medals {
     Longevity,
 }
 
-#[derive(Debug, Default, ByteStruct, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, ByteStruct, Serialize, Deserialize, JsonSchema)]
 #[byte_struct_le]
+#[cfg_attr(
+    feature = "python",
+    pyo3::pyclass(get_all, set_all, module = "bms_logcat")
+)]
 pub struct DogfightStats {
     pub matches_won: i16,
     pub matches_lost: i16,
@@ -51,8 +82,12 @@ pub struct DogfightStats {
     pub killed_versus_humans: i16,
 }
 
-#[derive(Debug, Default, ByteStruct, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, ByteStruct, Serialize, Deserialize, JsonSchema)]
 #[byte_struct_le]
+#[cfg_attr(
+    feature = "python",
+    pyo3::pyclass(get_all, set_all, module = "bms_logcat")
+)]
 pub struct CampaignStats {
     pub games_won: i16,
     pub game_lost: i16,
@@ -73,12 +108,13 @@ pub struct CampaignStats {
     pub missions_since_last_friendly_kill: i16,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Logbook {
     pub name: String,
     pub callsign: String,
     pub password: String,
     pub commissioned: String,
+    #[schemars(with = "String")]
     pub options_file: Utf8PathBuf,
     pub flight_hours: f32,
     pub ace_factor: f32,
@@ -86,27 +122,64 @@ pub struct Logbook {
     pub dogfight_stats: DogfightStats,
     pub campaign_stats: CampaignStats,
     pub medals: BTreeSet<Medals>,
+    #[schemars(with = "String")]
     pub picture_file: Utf8PathBuf,
+    #[schemars(with = "String")]
     pub patch_file: Utf8PathBuf,
     pub personal_text: String,
     pub squadron: String,
     pub voice: i16,
 }
 
-const FILENAME_LEN: usize = 32;
-const PASSWORD_LEN: usize = 10;
-const CALLSIGN_LEN: usize = 12;
-const PERSONAL_TEXT_LEN: usize = 120;
-const COMM_LEN: usize = 12;
-const NAME_LEN: usize = 20;
+pub(crate) const FILENAME_LEN: usize = 32;
+pub(crate) const PASSWORD_LEN: usize = 10;
+pub(crate) const CALLSIGN_LEN: usize = 12;
+pub(crate) const PERSONAL_TEXT_LEN: usize = 120;
+pub(crate) const COMM_LEN: usize = 12;
+pub(crate) const NAME_LEN: usize = 20;
+
+/// How many voices BMS has for pilots to pick from
+pub const VOICES: i16 = 12;
+
+/// How big a logbook is on disk
+pub const LOGBOOK_LEN: usize = (NAME_LEN + 1)
+    + (CALLSIGN_LEN + 1)
+    + (PASSWORD_LEN + 1)
+    + (COMM_LEN + 1)
+    + (CALLSIGN_LEN + 1)
+    + 1
+    + 4 // flight hours
+    + 4 // ace factor
+    + 4 // rank
+    + DogfightStats::BYTE_LEN
+    + CampaignStats::BYTE_LEN
+    + 2
+    + Medals::VARIANT_COUNT
+    + 2
+    + 4 // picture resource ID
+    + (FILENAME_LEN + 1)
+    + 3
+    + 4 // patch resource ID
+    + (FILENAME_LEN + 1)
+    + (PERSONAL_TEXT_LEN + 1)
+    + NAME_LEN
+    + 2 // voice
+    + 4; // checksum
 
 impl Logbook {
     pub fn new(name: String, callsign: String, password: String) -> Result<Self> {
         let options_file = Utf8PathBuf::from(&callsign);
 
-        let commissioned = time::OffsetDateTime::now_local()?.format(
-            time::macros::format_description!("[month]/[day]/[year repr:last_two]"),
-        )?;
+        // The local offset can't always be found (e.g., with other threads running),
+        // and UTC is at most a day off.
+        #[cfg(feature = "local-offset")]
+        let now =
+            time::OffsetDateTime::now_local().unwrap_or_else(|_| time::OffsetDateTime::now_utc());
+        #[cfg(not(feature = "local-offset"))]
+        let now = time::OffsetDateTime::now_utc();
+        let commissioned = now.format(time::macros::format_description!(
+            "[month]/[day]/[year repr:last_two]"
+        ))?;
 
         Ok(Self {
             name,
@@ -118,93 +191,144 @@ impl Logbook {
         })
     }
 
+    /// Cut every string down to what its field holds, as [`Validation::Lenient`] writes them.
+    pub fn truncate_strings(&mut self) {
+        let cut = |s: &mut String, max: usize| *s = truncate(s, max).to_owned();
+        let cut_path = |p: &mut Utf8PathBuf, max: usize| *p = truncate(p.as_str(), max).into();
+        cut(&mut self.name, NAME_LEN);
+        cut(&mut self.callsign, CALLSIGN_LEN);
+        cut(&mut self.password, PASSWORD_LEN);
+        cut(&mut self.commissioned, COMM_LEN);
+        cut_path(&mut self.options_file, CALLSIGN_LEN);
+        cut_path(&mut self.picture_file, FILENAME_LEN);
+        cut_path(&mut self.patch_file, FILENAME_LEN);
+        cut(&mut self.personal_text, PERSONAL_TEXT_LEN);
+        cut(&mut self.squadron, NAME_LEN - 1);
+    }
+
     pub fn parse<R: Read>(r: R) -> Result<Self> {
-        let mut r = DecryptRead::new(r, 0x58);
+        Self::parse_with(r, Validation::Strict)
+    }
 
-        let mut name_buf = [0; NAME_LEN + 1];
-        r.read_exact(&mut name_buf)?;
-        let name = buf_to_str(&name_buf)?.to_owned();
+    /// Like [`Logbook::parse`], but lets [`Validation::Lenient`] read back
+    /// out-of-range values it wrote.
+    pub fn parse_with<R: Read>(mut r: R, options: impl Into<ParseOptions>) -> Result<Self> {
+        let mut buf = [0; LOGBOOK_LEN];
+        r.read_exact(&mut buf)?;
+        Self::parse_bytes_with(&buf, options)
+    }
 
-        let mut callsign_buf = [0; CALLSIGN_LEN + 1];
-        r.read_exact(&mut callsign_buf)?;
-        let callsign = buf_to_str(&callsign_buf)?.to_owned();
+    /// Parse a logbook already in memory, like a whole `.lbk` file.
+    ///
+    /// This skips the [`Read`] plumbing, which adds up when reading thousands of them.
+    pub fn parse_bytes(bytes: &[u8]) -> Result<Self> {
+        Self::parse_bytes_with(bytes, Validation::Strict)
+    }
 
-        let mut pw_buf = [0; PASSWORD_LEN + 1];
-        r.read_exact(&mut pw_buf)?;
-        xor_password(&mut pw_buf);
-        let password = buf_to_str(&pw_buf)?.to_owned();
+    pub fn parse_bytes_with(bytes: &[u8], options: impl Into<ParseOptions>) -> Result<Self> {
+        Self::parse_with_report(bytes, options).map(|(book, _)| book)
+    }
+
+    /// Like [`Logbook::parse_bytes_with`], but also describes anything odd
+    /// that didn't stop the logbook from parsing.
+    pub fn parse_with_report(
+        bytes: &[u8],
+        options: impl Into<ParseOptions>,
+    ) -> Result<(Self, Vec<ParseWarning>)> {
+        let ParseOptions {
+            validation,
+            ignore_checksum,
+            keys,
+        } = options.into();
+        keys.check()?;
+        ensure!(
+            bytes.len() >= LOGBOOK_LEN,
+            "Logbook is {} bytes, but should be {LOGBOOK_LEN}",
+            bytes.len()
+        );
+        let mut buf = [0; LOGBOOK_LEN];
+        buf.copy_from_slice(&bytes[..LOGBOOK_LEN]);
+        decrypt(&mut buf, 0x58, &keys.master_key);
+        let mut r = Fields::new(&buf);
+        let mut warnings = Vec::new();
+        if bytes.len() > LOGBOOK_LEN {
+            warnings.push(ParseWarning::TrailingBytes(bytes.len() - LOGBOOK_LEN));
+        }
 
-        let mut commission_buf = [0; COMM_LEN + 1];
-        r.read_exact(&mut commission_buf)?;
-        let commissioned = buf_to_str(&commission_buf)?.to_owned();
+        let name = buf_to_str(r.take(NAME_LEN + 1))?.to_owned();
+        let callsign = buf_to_str(r.take(CALLSIGN_LEN + 1))?.to_owned();
+
+        let mut pw_buf = [0; PASSWORD_LEN + 1];
+        pw_buf.copy_from_slice(r.take(PASSWORD_LEN + 1));
+        validation.check(pw_buf[PASSWORD_LEN] == 0, || {
+            "password isn't NUL-terminated".to_owned()
+        })?;
+        xor_password(&mut pw_buf, &keys.password_masks);
+        let password = buf_to_str(&pw_buf[..PASSWORD_LEN])?.to_owned();
 
-        let mut options_buf = [0; CALLSIGN_LEN + 1];
-        r.read_exact(&mut options_buf)?;
-        let options_file: Utf8PathBuf = buf_to_str(&options_buf)?.into();
+        let commissioned = buf_to_str(r.take(COMM_LEN + 1))?.to_owned();
+        let options_file: Utf8PathBuf = buf_to_str(r.take(CALLSIGN_LEN + 1))?.into();
 
-        r.read_exact(&mut [0; 1])?;
+        r.padding(1);
 
-        let flight_hours = r.read_f32::<LE>()?;
-        let ace_factor = r.read_f32::<LE>()?;
+        let flight_hours = LE::read_f32(r.take(4));
+        let ace_factor = LE::read_f32(r.take(4));
 
-        let rank = Rank::try_from(r.read_i32::<LE>()?)
+        let rank = Rank::try_from(LE::read_i32(r.take(4)))
             .map_err(|e| anyhow!("{} isn't a valid rank index", e.number))?;
 
         assert_eq!(r.position() % 4, 0);
-        let mut dogfight_buf = [0; DogfightStats::BYTE_LEN];
-        r.read_exact(&mut dogfight_buf)?;
-        let dogfight_stats = DogfightStats::read_bytes(&dogfight_buf);
+        let dogfight_stats = DogfightStats::read_bytes(r.take(DogfightStats::BYTE_LEN));
 
         assert_eq!(r.position() % 4, 0);
-        let mut campaign_buf = [0; CampaignStats::BYTE_LEN];
-        r.read_exact(&mut campaign_buf)?;
-        let campaign_stats = CampaignStats::read_bytes(&campaign_buf);
+        let campaign_stats = CampaignStats::read_bytes(r.take(CampaignStats::BYTE_LEN));
 
-        r.read_exact(&mut [0; 2])?;
+        r.padding(2);
         assert_eq!(r.position() % 4, 0);
 
         let mut medals = BTreeSet::default();
         for m in Medals::into_enum_iter() {
-            if r.read_u8()? > 0 {
+            if r.take(1)[0] > 0 {
                 medals.insert(m);
             }
         }
 
-        r.read_exact(&mut [0; 2])?;
+        r.padding(2);
         assert_eq!(r.position() % 4, 0);
 
         // Skip picture resource ID
-        r.read_exact(&mut [0; 4])?;
+        r.skip(4);
 
-        let mut picture_buf = [0; FILENAME_LEN + 1];
-        r.read_exact(&mut picture_buf)?;
-        let picture_file = buf_to_str(&picture_buf)?.into();
+        let picture_file = buf_to_str(r.take(FILENAME_LEN + 1))?.into();
 
-        r.read_exact(&mut [0; 3])?;
+        r.padding(3);
         assert_eq!(r.position() % 4, 0);
 
         // Skip patch resource ID
-        r.read_exact(&mut [0; 4])?;
+        r.skip(4);
 
-        let mut patch_buf = [0; FILENAME_LEN + 1];
-        r.read_exact(&mut patch_buf)?;
-        let patch_file = buf_to_str(&patch_buf)?.into();
+        let patch_file = buf_to_str(r.take(FILENAME_LEN + 1))?.into();
+        let personal_text = buf_to_str(r.take(PERSONAL_TEXT_LEN + 1))?.into();
+        let squadron = buf_to_str(r.take(NAME_LEN))?.into();
 
-        let mut personal_buf = [0; PERSONAL_TEXT_LEN + 1];
-        r.read_exact(&mut personal_buf)?;
-        let personal_text = buf_to_str(&personal_buf)?.into();
+        let voice = LE::read_i16(r.take(2));
+        validation.check(voice < VOICES, || {
+            format!("voice index {} > {}", voice, VOICES - 1)
+        })?;
 
-        let mut squadron_buf = [0; NAME_LEN];
-        r.read_exact(&mut squadron_buf)?;
-        let squadron = buf_to_str(&squadron_buf)?.into();
-
-        let voice = r.read_i16::<LE>()?;
-        ensure!(voice < 12, "voice index {} > 11", voice);
+        let checksum = LE::read_u32(r.take(4));
+        if checksum != 0 {
+            ensure!(ignore_checksum, "Decryption failed - bad checksum");
+            warnings.push(ParseWarning::Checksum(checksum));
+        }
+        assert_eq!(r.position(), LOGBOOK_LEN);
 
-        let checksum = r.read_u32::<LE>()?;
-        ensure!(checksum == 0, "Decryption failed - bad checksum");
+        warnings.extend(r.padding.iter().map(|o| ParseWarning::NonzeroPadding(*o)));
+        if !is_date(&commissioned) {
+            warnings.push(ParseWarning::OddDate(commissioned.clone()));
+        }
 
-        Ok(Self {
+        let book = Self {
             name,
             callsign,
             password,
@@ -221,20 +345,36 @@ impl Logbook {
             personal_text,
             squadron,
             voice,
-        })
+        };
+        Ok((book, warnings))
+    }
+
+    /// Parse a logbook in memory, as [`Logbook::parse_bytes`] does.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Self::parse_bytes(bytes)
     }
 
-    pub fn write<W: Write>(&self, w: W) -> Result<()> {
-        let mut w = EncryptWrite::new(w, 0x58);
+    /// The logbook as BMS keeps it on disk, checked as [`Validation::Strict`] writes do
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(LOGBOOK_LEN);
+        self.write(&mut bytes, Validation::Strict)?;
+        Ok(bytes)
+    }
+
+    pub fn write<W: Write>(&self, w: W, options: impl Into<WriteOptions>) -> Result<()> {
+        let WriteOptions { validation, keys } = options.into();
+        keys.check()?;
+        let mut w = EncryptWrite::new(w, 0x58, &keys.master_key);
         let w = &mut w;
+        let v = validation;
 
-        write_padded(w, &self.name, NAME_LEN + 1)?;
-        write_padded(w, &self.callsign, CALLSIGN_LEN + 1)?;
+        write_padded(w, &self.name, NAME_LEN + 1, v)?;
+        write_padded(w, &self.callsign, CALLSIGN_LEN + 1, v)?;
 
-        write_password(w, &self.password)?;
+        write_password(w, &self.password, v, &keys.password_masks)?;
 
-        write_padded(w, &self.commissioned, COMM_LEN + 1)?;
-        write_padded(w, &self.options_file, CALLSIGN_LEN + 1)?;
+        write_padded(w, &self.commissioned, COMM_LEN + 1, v)?;
+        write_padded(w, &self.options_file, CALLSIGN_LEN + 1, v)?;
         w.write_all(&[0; 1])?;
         w.write_f32::<LE>(self.flight_hours)?;
         w.write_f32::<LE>(self.ace_factor)?;
@@ -263,7 +403,7 @@ impl Logbook {
         // Skip picture resource ID
         w.write_all(&[0; 4])?;
 
-        write_padded(w, &self.picture_file, FILENAME_LEN + 1)?;
+        write_padded(w, &self.picture_file, FILENAME_LEN + 1, v)?;
 
         w.write_all(&[0; 3])?;
         assert_eq!(w.position() % 4, 0);
@@ -271,15 +411,18 @@ impl Logbook {
         // Skip patch resource ID
         w.write_all(&[0; 4])?;
 
-        write_padded(w, &self.patch_file, FILENAME_LEN + 1)?;
-        write_padded(w, &self.personal_text, PERSONAL_TEXT_LEN + 1)?;
-        write_padded(w, &self.squadron, NAME_LEN)?;
+        write_padded(w, &self.patch_file, FILENAME_LEN + 1, v)?;
+        write_padded(w, &self.personal_text, PERSONAL_TEXT_LEN + 1, v)?;
+        write_padded(w, &self.squadron, NAME_LEN, v)?;
 
-        ensure!(self.voice < 12, "voice index {} > 11", self.voice);
+        v.check(self.voice < VOICES, || {
+            format!("voice index {} > {}", self.voice, VOICES - 1)
+        })?;
         w.write_i16::<LE>(self.voice)?;
 
         w.write_u32::<LE>(0)?; // "checksum
 
+        w.flush()?;
         Ok(())
     }
 }
@@ -288,13 +431,117 @@ fn buf_to_str(buf: &[u8]) -> Result<&str> {
     Ok(std::str::from_utf8(buf)?.split('\0').next().unwrap())
 }
 
-fn write_padded<W: Write, S: AsRef<str>>(w: &mut W, s: S, pad_to: usize) -> Result<()> {
-    let s = s.as_ref();
-    ensure!(
-        s.len() < pad_to,
-        "{s} is longer than the allowed length ({})",
-        pad_to - 1
-    );
+impl TryFrom<&[u8]> for Logbook {
+    type Error = anyhow::Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self> {
+        Self::from_bytes(bytes)
+    }
+}
+
+/// How [`Logbook::parse_with`] and friends read a logbook.
+///
+/// A bare [`Validation`] converts into one, checking the checksum as usual
+/// and using BMS's keys.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    pub validation: Validation,
+    /// Parse logbooks whose checksum (their last four bytes) isn't zero,
+    /// as some BMS builds write them, with a [`ParseWarning::Checksum`] instead of failing.
+    ///
+    /// A bad checksum usually means the file isn't a logbook or is corrupt,
+    /// so only ignore it for files whose fields are known to decode fine.
+    pub ignore_checksum: bool,
+    /// What the logbook was obfuscated with
+    pub keys: Keys,
+}
+
+impl From<Validation> for ParseOptions {
+    fn from(validation: Validation) -> Self {
+        Self {
+            validation,
+            ignore_checksum: false,
+            keys: Keys::default(),
+        }
+    }
+}
+
+/// How [`Logbook::write`] writes a logbook.
+///
+/// A bare [`Validation`] converts into one using BMS's keys.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteOptions {
+    pub validation: Validation,
+    /// What to obfuscate the logbook with
+    pub keys: Keys,
+}
+
+impl From<Validation> for WriteOptions {
+    fn from(validation: Validation) -> Self {
+        Self {
+            validation,
+            keys: Keys::default(),
+        }
+    }
+}
+
+/// How strictly [`Logbook::write`] and [`Logbook::parse_with`] check values.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Validation {
+    /// Refuse to write values BMS doesn't expect.
+    Strict,
+    /// Warn about them and write them anyways.
+    /// Strings that don't fit their field are truncated
+    /// since writing them whole would corrupt everything after them.
+    Lenient,
+}
+
+impl Validation {
+    /// Fail if `ok` is false, or just warn about it when lenient.
+    pub fn check<F: FnOnce() -> String>(self, ok: bool, problem: F) -> Result<()> {
+        match (ok, self) {
+            (true, _) => Ok(()),
+            (false, Validation::Strict) => Err(crate::exit::Invalid(problem()).into()),
+            (false, Validation::Lenient) => {
+                warn!("{}; continuing anyways", problem());
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Cut `s` down to at most `max` bytes without splitting a character.
+fn truncate(s: &str, max: usize) -> &str {
+    if s.len() <= max {
+        return s;
+    }
+    let mut end = max;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+/// A NUL would end the string early when BMS reads it back,
+/// so refuse it, or cut the string there when lenient.
+fn nul_free(s: &str, validation: Validation) -> Result<&str> {
+    validation.check(!s.contains('\0'), || {
+        format!("{s:?} contains a NUL character")
+    })?;
+    Ok(s.split('\0').next().unwrap())
+}
+
+pub(crate) fn write_padded<W: Write, S: AsRef<str>>(
+    w: &mut W,
+    s: S,
+    pad_to: usize,
+    validation: Validation,
+) -> Result<()> {
+    let s = nul_free(s.as_ref(), validation)?;
+    validation.check(s.len() < pad_to, || {
+        format!("{s} is longer than the allowed length ({})", pad_to - 1)
+    })?;
+    let s = truncate(s, pad_to - 1);
 
     w.write_all(s.as_bytes())?;
     let padding = vec![0; pad_to - s.len()];
@@ -303,126 +550,462 @@ fn write_padded<W: Write, S: AsRef<str>>(w: &mut W, s: S, pad_to: usize) -> Resu
     Ok(())
 }
 
-const MASTER_KEY: &[u8] = b"Falcon is your Master";
-
-struct DecryptRead<R> {
-    inner: R,
-    start: u8,
-    bytes_read: usize,
+/// The strings logbooks are obfuscated with.
+///
+/// BMS uses the same ones as Falcon 4, but some Falcon-derived sims changed them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keys {
+    /// XORed over the whole logbook
+    pub master_key: Vec<u8>,
+    /// XORed over the password, one after the other
+    pub password_masks: [Vec<u8>; 2],
 }
 
-impl<R: Read> DecryptRead<R> {
-    fn new(inner: R, start: u8) -> Self {
+impl Default for Keys {
+    fn default() -> Self {
         Self {
-            inner,
-            start,
-            bytes_read: 0,
+            master_key: b"Falcon is your Master".to_vec(),
+            password_masks: [
+                b"Who needs a password!".to_vec(),
+                b"Repend, Falcon is coming!".to_vec(),
+            ],
         }
     }
+}
 
-    fn position(&self) -> usize {
-        self.bytes_read
+impl Keys {
+    /// Make sure none of the keys are empty, since there'd be nothing to XOR with.
+    pub fn check(&self) -> Result<()> {
+        ensure!(!self.master_key.is_empty(), "The master key is empty");
+        ensure!(
+            self.password_masks.iter().all(|m| !m.is_empty()),
+            "A password mask is empty"
+        );
+        Ok(())
+    }
+}
+
+/// Decrypt a logbook in place.
+pub(crate) fn decrypt(buf: &mut [u8], mut start: u8, key: &[u8]) {
+    for (i, b) in buf.iter_mut().enumerate() {
+        let next = *b;
+        *b ^= start;
+        *b ^= key[i % key.len()];
+        start = next;
+    }
+}
+
+/// Encrypt a logbook in place from byte `from` on,
+/// continuing the chain from the already encrypted bytes before it.
+pub(crate) fn encrypt_from(buf: &mut [u8], from: usize, start: u8, key: &[u8]) {
+    let mut start = if from == 0 { start } else { buf[from - 1] };
+    for (i, b) in buf.iter_mut().enumerate().skip(from) {
+        *b ^= key[i % key.len()];
+        *b ^= start;
+        start = *b;
+    }
+}
+
+/// Something odd about a logbook that didn't stop it from parsing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum ParseWarning {
+    /// Padding between fields, at this offset, isn't zero like BMS writes it.
+    NonzeroPadding(usize),
+    /// The file has this many bytes past the end of the logbook.
+    TrailingBytes(usize),
+    /// The commissioned date isn't `MM/DD/YY`.
+    OddDate(String),
+    /// The checksum isn't zero, but [`ParseOptions::ignore_checksum`] was asked for.
+    Checksum(u32),
+}
+
+impl std::fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParseWarning::NonzeroPadding(offset) => {
+                write!(f, "padding at byte {offset} isn't zero")
+            }
+            ParseWarning::TrailingBytes(n) => {
+                write!(f, "{n} extra byte(s) after the logbook were ignored")
+            }
+            ParseWarning::OddDate(date) => {
+                write!(f, "commissioned date {date:?} isn't MM/DD/YY")
+            }
+            ParseWarning::Checksum(checksum) => {
+                write!(f, "checksum is {checksum:#010x}, not zero")
+            }
+        }
     }
 }
 
-impl<R: Read> Read for DecryptRead<R> {
-    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        let amount_read = self.inner.read(buf)?;
+/// `M/D/YY`, zero-padded or not
+fn is_date(s: &str) -> bool {
+    let parts: Vec<&str> = s.split('/').collect();
+    parts.len() == 3
+        && parts
+            .iter()
+            .all(|p| (1..=2).contains(&p.len()) && p.bytes().all(|b| b.is_ascii_digit()))
+}
 
-        for b in &mut buf[..amount_read] {
-            let next = *b;
-            *b ^= self.start;
-            *b ^= MASTER_KEY[self.bytes_read % MASTER_KEY.len()];
+/// Reads a decrypted logbook's fields in order.
+struct Fields<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    /// Where [`Fields::padding`] found bytes that weren't zero
+    padding: Vec<usize>,
+}
 
-            self.bytes_read += 1;
-            self.start = next;
+impl<'a> Fields<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            position: 0,
+            padding: Vec::new(),
         }
+    }
+
+    fn position(&self) -> usize {
+        self.position
+    }
 
-        Ok(amount_read)
+    /// The next `len` bytes. The caller checked there are enough of them.
+    fn take(&mut self, len: usize) -> &'a [u8] {
+        let field = &self.bytes[self.position..self.position + len];
+        self.position += len;
+        field
+    }
+
+    fn skip(&mut self, len: usize) {
+        self.position += len;
+    }
+
+    /// Skip padding, noting if it isn't zero.
+    fn padding(&mut self, len: usize) {
+        if self.take(len).iter().any(|b| *b != 0) {
+            self.padding.push(self.position - len);
+        }
     }
 }
 
-struct EncryptWrite<W> {
+/// Encrypts into a buffer and hands it to the inner writer in chunks,
+/// since the XOR chain makes every byte depend on the one before it.
+struct EncryptWrite<'k, W: Write> {
     inner: W,
+    key: &'k [u8],
     start: u8,
     bytes_written: usize,
+    /// Encrypted bytes not yet written to `inner`
+    buf: Vec<u8>,
 }
 
-impl<W: Write> EncryptWrite<W> {
-    fn new(inner: W, start: u8) -> Self {
+impl<'k, W: Write> EncryptWrite<'k, W> {
+    /// Buffer about a logbook's worth before writing any of it.
+    const CAPACITY: usize = 4096;
+
+    fn new(inner: W, start: u8, key: &'k [u8]) -> Self {
         Self {
             inner,
+            key,
             start,
             bytes_written: 0,
+            buf: Vec::with_capacity(Self::CAPACITY),
         }
     }
 
     fn position(&self) -> usize {
         self.bytes_written
     }
-}
-
-impl<W: Write> Write for EncryptWrite<W> {
-    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        let mut this_write: usize = 0;
 
-        for b in buf {
+    /// Encrypt all of `data` into the buffer.
+    fn encrypt(&mut self, data: &[u8]) {
+        let key = self.key;
+        for b in data {
             let mut to_write = *b;
-            to_write ^= MASTER_KEY[self.bytes_written % MASTER_KEY.len()];
+            to_write ^= key[self.bytes_written % key.len()];
             to_write ^= self.start;
 
-            match self.inner.write(&[to_write]) {
-                Ok(0) => break,
-                Ok(1) => {
-                    this_write += 1;
-                    self.bytes_written += 1;
-                    self.start = to_write;
-                }
-                Ok(_) => unreachable!(),
-                Err(e) => {
-                    if this_write == 0 {
-                        return Err(e);
-                    } else {
-                        break;
-                    }
-                }
+            self.buf.push(to_write);
+            self.bytes_written += 1;
+            self.start = to_write;
+        }
+    }
+
+    /// Write out everything we've encrypted so far.
+    ///
+    /// What the inner writer accepts is dropped from the buffer even if a later write fails,
+    /// so retrying picks up where it left off.
+    fn write_buf(&mut self) -> std::io::Result<()> {
+        let mut written = 0;
+        let result = loop {
+            if written == self.buf.len() {
+                break Ok(());
+            }
+            match self.inner.write(&self.buf[written..]) {
+                Ok(0) => break Err(std::io::ErrorKind::WriteZero.into()),
+                Ok(n) => written += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => break Err(e),
             }
+        };
+        self.buf.drain(..written);
+        result
+    }
+}
+
+impl<W: Write> Write for EncryptWrite<'_, W> {
+    /// Takes all of `buf` unless it has to make room and the inner writer fails,
+    /// in which case it takes none of it.
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.buf.len() + buf.len() > Self::CAPACITY {
+            self.write_buf()?;
         }
+        self.encrypt(buf);
+        Ok(buf.len())
+    }
 
-        Ok(this_write)
+    fn write_vectored(&mut self, bufs: &[std::io::IoSlice<'_>]) -> std::io::Result<usize> {
+        let total = bufs.iter().map(|b| b.len()).sum();
+        if self.buf.len() + total > Self::CAPACITY {
+            self.write_buf()?;
+        }
+        for b in bufs {
+            self.encrypt(b);
+        }
+        Ok(total)
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
+        self.write_buf()?;
         self.inner.flush()
     }
 }
 
-fn xor_password(pw: &mut [u8]) {
-    const MASK1: &[u8] = b"Who needs a password!";
-    const MASK2: &[u8] = b"Repend, Falcon is coming!";
+impl<W: Write> Drop for EncryptWrite<'_, W> {
+    /// Like [`std::io::BufWriter`], make a last attempt to write what's buffered.
+    /// Callers that care about errors should flush first.
+    fn drop(&mut self) {
+        let _ = self.write_buf();
+    }
+}
 
-    assert_eq!(pw.len(), PASSWORD_LEN + 1);
+pub(crate) fn xor_password(pw: &mut [u8], masks: &[Vec<u8>; 2]) {
+    let [mask1, mask2] = masks;
 
-    // Despite being XOR'd to hell, the password is null-terminated
-    assert_eq!(pw[PASSWORD_LEN], 0);
+    assert_eq!(pw.len(), PASSWORD_LEN + 1);
 
+    // Despite being XOR'd to hell, the password is null-terminated,
+    // so the last byte is left alone.
     for (i, b) in pw.iter_mut().take(PASSWORD_LEN).enumerate() {
-        *b ^= MASK1[i % MASK1.len()];
-        *b ^= MASK2[i % MASK2.len()];
+        *b ^= mask1[i % mask1.len()];
+        *b ^= mask2[i % mask2.len()];
     }
 }
 
-fn write_password<W: Write>(w: &mut W, pw: &str) -> Result<()> {
-    ensure!(
-        pw.len() <= PASSWORD_LEN,
-        "password {pw} is longer than the allowed length ({PASSWORD_LEN})"
-    );
+pub(crate) fn write_password<W: Write>(
+    w: &mut W,
+    pw: &str,
+    validation: Validation,
+    masks: &[Vec<u8>; 2],
+) -> Result<()> {
+    let pw = nul_free(pw, validation)?;
+    validation.check(pw.len() <= PASSWORD_LEN, || {
+        format!("password {pw} is longer than the allowed length ({PASSWORD_LEN})")
+    })?;
+    let pw = truncate(pw, PASSWORD_LEN);
 
     let mut buf: Vec<u8> = pw.as_bytes().to_owned();
     buf.resize(PASSWORD_LEN + 1, 0);
-    xor_password(&mut buf);
+    xor_password(&mut buf, masks);
 
     w.write_all(&buf)?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8] = b"Falcon is your Master";
+
+    /// Takes at most `max` bytes per write, and is interrupted every other call.
+    struct Stingy {
+        written: Vec<u8>,
+        max: usize,
+        interrupt: bool,
+    }
+
+    impl Write for Stingy {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return Err(std::io::ErrorKind::Interrupted.into());
+            }
+            let n = buf.len().min(self.max);
+            self.written.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn encrypt(data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut w = EncryptWrite::new(&mut out, 0x58, KEY);
+        w.write_all(data).unwrap();
+        w.flush().unwrap();
+        drop(w);
+        out
+    }
+
+    fn decrypted(data: &[u8]) -> Vec<u8> {
+        let mut out = data.to_vec();
+        decrypt(&mut out, 0x58, KEY);
+        out
+    }
+
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 7 % 251) as u8).collect()
+    }
+
+    #[test]
+    fn round_trips_past_the_buffer() {
+        let data = sample(3 * 4096 + 17);
+        assert_eq!(decrypted(&encrypt(&data)), data);
+    }
+
+    #[test]
+    fn partial_writes_lose_nothing() {
+        let data = sample(10_000);
+        let mut sink = Stingy {
+            written: Vec::new(),
+            max: 3,
+            interrupt: false,
+        };
+        let mut w = EncryptWrite::new(&mut sink, 0x58, KEY);
+        for chunk in data.chunks(100) {
+            w.write_all(chunk).unwrap();
+        }
+        w.flush().unwrap();
+        drop(w);
+        assert_eq!(sink.written, encrypt(&data));
+    }
+
+    #[test]
+    fn vectored_matches_sequential() {
+        let data = sample(5000);
+        let (a, b) = data.split_at(1234);
+        let mut out = Vec::new();
+        let mut w = EncryptWrite::new(&mut out, 0x58, KEY);
+        let n = w
+            .write_vectored(&[std::io::IoSlice::new(a), std::io::IoSlice::new(b)])
+            .unwrap();
+        assert_eq!(n, data.len());
+        w.flush().unwrap();
+        drop(w);
+        assert_eq!(out, encrypt(&data));
+    }
+
+    #[test]
+    fn logbook_round_trips() {
+        let book = Logbook {
+            name: "Jane Doe".into(),
+            callsign: "Viper".into(),
+            ..Logbook::default()
+        };
+        let bytes = book.to_bytes().unwrap();
+        let parsed = Logbook::try_from(&bytes[..]).unwrap();
+        assert_eq!(
+            serde_json::to_value(parsed).unwrap(),
+            serde_json::to_value(book).unwrap()
+        );
+    }
+
+    #[test]
+    fn reports_what_it_tolerated() {
+        let book = Logbook {
+            commissioned: "yesterday".into(),
+            ..Logbook::default()
+        };
+        let mut bytes = book.to_bytes().unwrap();
+        bytes.extend_from_slice(b"junk");
+        let (_, warnings) = Logbook::parse_with_report(&bytes, Validation::Strict).unwrap();
+        assert_eq!(
+            warnings,
+            [
+                ParseWarning::TrailingBytes(4),
+                ParseWarning::OddDate("yesterday".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_the_checksum_when_asked() {
+        let mut plain = decrypted(&Logbook::default().to_bytes().unwrap());
+        plain[LOGBOOK_LEN - 4] = 1;
+        let bytes = encrypt(&plain);
+        assert!(Logbook::parse_bytes(&bytes).is_err());
+        let options = ParseOptions {
+            ignore_checksum: true,
+            ..Validation::Strict.into()
+        };
+        let (_, warnings) = Logbook::parse_with_report(&bytes, options).unwrap();
+        assert!(warnings.contains(&ParseWarning::Checksum(1)));
+    }
+
+    #[test]
+    fn uses_the_keys_it_is_given() {
+        let keys = Keys {
+            master_key: b"Not Falcon".to_vec(),
+            password_masks: [b"first".to_vec(), b"second".to_vec()],
+        };
+        let book = Logbook {
+            callsign: "Viper".into(),
+            password: "hunter2".into(),
+            ..Logbook::default()
+        };
+        let mut bytes = Vec::new();
+        let writing = WriteOptions {
+            keys: keys.clone(),
+            ..Validation::Strict.into()
+        };
+        book.write(&mut bytes, writing).unwrap();
+        assert_ne!(bytes, book.to_bytes().unwrap());
+        assert!(Logbook::parse_bytes(&bytes).is_err());
+
+        let parsing = ParseOptions {
+            keys,
+            ..Validation::Strict.into()
+        };
+        let parsed = Logbook::parse_bytes_with(&bytes, parsing).unwrap();
+        assert_eq!(parsed.callsign, "Viper");
+        assert_eq!(parsed.password, "hunter2");
+
+        let empty = ParseOptions {
+            keys: Keys {
+                master_key: Vec::new(),
+                ..Keys::default()
+            },
+            ..Validation::Strict.into()
+        };
+        assert!(Logbook::parse_bytes_with(&bytes, empty).is_err());
+    }
+
+    #[test]
+    fn refuses_what_it_cant_read_back() {
+        // An unterminated password used to trip an assert.
+        let mut plain = decrypted(&Logbook::default().to_bytes().unwrap());
+        plain[NAME_LEN + 1 + CALLSIGN_LEN + 1 + PASSWORD_LEN] = 1;
+        let bytes = encrypt(&plain);
+        assert!(Logbook::parse_bytes(&bytes).is_err());
+        Logbook::parse_bytes_with(&bytes, Validation::Lenient).unwrap();
+
+        let book = Logbook {
+            name: "Jane\0Doe".into(),
+            ..Logbook::default()
+        };
+        assert!(book.to_bytes().is_err());
+    }
+}
@@ -1,15 +1,54 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, prelude::*};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
 use anyhow::*;
+use camino::{Utf8Path, Utf8PathBuf};
 use simplelog::*;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 
-#[derive(clap::ArgEnum, Debug, Copy, Clone)]
+#[derive(clap::ValueEnum, Debug, Copy, Clone)]
 pub enum Color {
     Auto,
     Always,
     Never,
 }
 
-/// Set up simplelog to spit messages to stderr.
-pub fn init_logger(verbosity: u8, color: Color) {
+#[derive(clap::ValueEnum, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable lines, colored if stderr is a terminal
+    Text,
+    /// One JSON object per line, for wrapper scripts and CI
+    Json,
+}
+
+/// Where `--log-file` writes, and how it treats what's already there
+#[derive(Debug, Clone)]
+pub struct LogFile {
+    pub path: Utf8PathBuf,
+    /// Add to the file instead of starting it over
+    pub append: bool,
+    /// Move the file aside once it reaches this many bytes
+    pub rotate_at: Option<u64>,
+    /// How many moved-aside files (`.1`, `.2`, ...) to keep
+    pub keep: usize,
+}
+
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// Target for [`log_fatal`]'s pre-rendered JSON, which [`JsonLogger`] passes through as-is
+const FATAL_TARGET: &str = "bms_logcat::fatal";
+
+/// Set up simplelog to spit messages to stderr, and to a file if given one.
+///
+/// If we can't open the file, we still log to stderr, but return why.
+pub fn init_logger(
+    verbosity: u8,
+    color: Color,
+    format: LogFormat,
+    log_file: Option<&LogFile>,
+) -> Result<()> {
     let mut builder = ConfigBuilder::new();
     // Shut a bunch of stuff off - we're just spitting to stderr.
     builder.set_location_level(LevelFilter::Trace);
@@ -27,7 +66,14 @@ pub fn init_logger(verbosity: u8, color: Color) {
     let config = builder.build();
 
     if cfg!(test) {
-        TestLogger::init(level, config).context("Couldn't init test logger")
+        TestLogger::init(level, config).context("Couldn't init test logger")?;
+        return Ok(());
+    }
+
+    let mut loggers: Vec<Box<dyn SharedLogger>> = Vec::new();
+    if format == LogFormat::Json {
+        JSON.store(true, Ordering::Relaxed);
+        loggers.push(Box::new(JsonLogger::new(level, io::stderr())));
     } else {
         let color = match color {
             Color::Always => ColorChoice::AlwaysAnsi,
@@ -40,10 +86,184 @@ pub fn init_logger(verbosity: u8, color: Color) {
             }
             Color::Never => ColorChoice::Never,
         };
+        loggers.push(TermLogger::new(level, config, TerminalMode::Stderr, color));
+    }
+
+    // Files are for looking back at long-running watches and servers,
+    // so they get at least info, and timestamps.
+    let (file, opened) = match log_file.map(RotatingFile::open).transpose() {
+        Result::Ok(file) => (file, Ok(())),
+        Err(e) => (None, Err(e)),
+    };
+    if let Some(file) = file {
+        let file_level = level.max(LevelFilter::Info);
+        if format == LogFormat::Json {
+            loggers.push(Box::new(JsonLogger::new(file_level, file)));
+        } else {
+            let mut builder = ConfigBuilder::new();
+            builder.set_target_level(LevelFilter::Off);
+            builder.set_thread_level(LevelFilter::Off);
+            builder.set_time_format_rfc3339();
+            loggers.push(WriteLogger::new(file_level, builder.build(), file));
+        }
+    }
+
+    CombinedLogger::init(loggers).context("Couldn't init logger")?;
+    opened
+}
+
+/// Report the error that ended the program, with the exit code we're about to use.
+///
+/// JSON logs get the error and each of its causes as separate fields
+/// so tools don't have to pick apart the text.
+pub fn log_fatal(e: &anyhow::Error, exit_code: i32) {
+    if !JSON.load(Ordering::Relaxed) {
+        log::error!("{:?}", e);
+        return;
+    }
+    let line = serde_json::json!({
+        "time": now(),
+        "level": "ERROR",
+        "message": e.to_string(),
+        "causes": e.chain().skip(1).map(|c| c.to_string()).collect::<Vec<_>>(),
+        "exit_code": exit_code,
+    });
+    log::error!(target: FATAL_TARGET, "{line}");
+}
+
+/// Writes each record as a JSON object:
+/// `{"time": ..., "level": ..., "target": ..., "message": ...}`
+struct JsonLogger<W: Write + Send> {
+    level: LevelFilter,
+    writer: Mutex<W>,
+}
+
+impl<W: Write + Send> JsonLogger<W> {
+    fn new(level: LevelFilter, writer: W) -> Self {
+        Self {
+            level,
+            writer: Mutex::new(writer),
+        }
+    }
+}
+
+impl<W: Write + Send> log::Log for JsonLogger<W> {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = if record.target() == FATAL_TARGET {
+            record.args().to_string()
+        } else {
+            serde_json::json!({
+                "time": now(),
+                "level": record.level().as_str(),
+                "target": record.target(),
+                "message": record.args().to_string(),
+            })
+            .to_string()
+        };
+        // Like simplelog, drop records we can't write rather than panic.
+        let mut w = self.writer.lock().unwrap();
+        let _ = writeln!(w, "{line}");
+        let _ = w.flush();
+    }
+
+    fn flush(&self) {
+        let _ = self.writer.lock().unwrap().flush();
+    }
+}
+
+impl<W: Write + Send + 'static> SharedLogger for JsonLogger<W> {
+    fn level(&self) -> LevelFilter {
+        self.level
+    }
+
+    fn config(&self) -> Option<&Config> {
+        None
+    }
 
-        TermLogger::init(level, config.clone(), TerminalMode::Stderr, color)
-            .or_else(|_| SimpleLogger::init(level, config))
-            .context("Couldn't init logger")
+    fn as_log(self: Box<Self>) -> Box<dyn log::Log> {
+        Box::new(*self)
     }
-    .unwrap()
+}
+
+/// A log file that moves itself aside (`log.1`, `log.2`, ...) when it gets too big
+struct RotatingFile {
+    options: LogFile,
+    file: File,
+    size: u64,
+    /// Loggers write lines in pieces; only rotate between them.
+    line_start: bool,
+}
+
+impl RotatingFile {
+    fn open(options: &LogFile) -> Result<Self> {
+        let path = &options.path;
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(options.append)
+            .truncate(!options.append)
+            .open(path)
+            .with_context(|| format!("Couldn't open log file {path}"))?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            options: options.clone(),
+            file,
+            size,
+            line_start: true,
+        })
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let path = &self.options.path;
+        let numbered = |n: usize| Utf8PathBuf::from(format!("{path}.{n}"));
+        if self.options.keep > 0 {
+            for n in (1..self.options.keep).rev() {
+                rename_if_exists(&numbered(n), &numbered(n + 1))?;
+            }
+            rename_if_exists(path, &numbered(1))?;
+        }
+        self.file = File::create(path)?;
+        self.size = 0;
+        io::Result::Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(limit) = self.options.rotate_at {
+            if self.line_start && self.size >= limit {
+                self.rotate()?;
+            }
+        }
+        let n = self.file.write(buf)?;
+        self.size += n as u64;
+        if n > 0 {
+            self.line_start = buf[n - 1] == b'\n';
+        }
+        io::Result::Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn rename_if_exists(from: &Utf8Path, to: &Utf8Path) -> io::Result<()> {
+    match std::fs::rename(from, to) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => io::Result::Ok(()),
+        other => other,
+    }
+}
+
+fn now() -> String {
+    OffsetDateTime::now_utc()
+        .format(&Rfc3339)
+        .unwrap_or_default()
 }
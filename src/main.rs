@@ -1,135 +1,2746 @@
-mod logbook;
+mod ace;
+mod acmi;
+mod aggregate;
+mod archive;
+mod backup;
+mod batch;
+mod canonical;
+mod case;
+mod chart;
+mod clipboard;
+mod compare;
+mod config;
+mod debrief;
+mod diff;
+mod doctor;
+mod edit;
+mod formats;
+mod generate;
+mod history;
+mod humanize;
+mod ini;
+mod installs;
+mod keys;
+mod lang;
+mod leaderboard;
+mod lint;
+mod lock;
 mod logsetup;
+mod man;
+mod metrics;
+mod notify;
+mod patch;
+mod percentile;
+mod plugin;
+mod pop;
+mod preset;
+mod profile;
+mod progress;
+mod promotion;
+mod query;
+mod redact;
+mod resources;
+mod roster;
+mod running;
+mod script;
+mod seal;
+mod serve;
+mod signature;
+mod stats;
+mod status;
+mod sync;
+mod table;
+mod template;
+mod version;
+mod watch;
 
-use std::fs::File;
+use std::collections::HashMap;
 use std::io::{prelude::*, BufReader, BufWriter};
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
 use camino::{Utf8Path, Utf8PathBuf};
-use clap::{Parser, Subcommand};
+use clap::builder::FalseyValueParser;
+use clap::{ArgAction, ArgGroup, CommandFactory, Parser, Subcommand, ValueHint};
 use log::*;
 
-use logbook::Logbook;
+use bms_logcat::exit;
+use bms_logcat::layout;
+use bms_logcat::logbook::{self, Logbook};
 use logsetup::init_logger;
 
 #[derive(Debug, Subcommand)]
 enum Command {
-    /// Read the given BMS logbook and print it as JSON
+    /// Read the given BMS logbook and print it as JSON (or another --format)
     Read {
+        /// Output format (defaults to what --output's extension says, or JSON)
+        #[arg(short, long, value_enum)]
+        format: Option<formats::Format>,
+
+        /// Write to this file instead of --output (repeatable, so one read can make several),
+        /// as `FORMAT=PATH` (e.g., `html=pilot.html`) or a path whose extension gives the format
+        #[arg(long = "out", value_name = "[FORMAT=]PATH", value_hint = ValueHint::FilePath)]
+        outs: Vec<formats::Out>,
+
+        /// Pretty-print the JSON output
+        #[arg(short, long)]
+        pretty: bool,
+
+        /// Add computed metrics (K/D, win rates, averages) under `derived`
+        #[arg(short, long)]
+        derived: bool,
+
+        /// jq-style expression to shape the output with,
+        /// e.g., `.campaign_stats | {kills, missions}`
+        #[arg(short, long)]
+        query: Option<String>,
+
+        /// Only output these comma-separated fields, e.g., `name,campaign_stats.kills`
+        #[arg(long, value_delimiter = ',')]
+        fields: Vec<String>,
+
+        /// Output `{"logbook": ..., "options": ...}`, including the pilot's .pop options file
+        #[arg(long)]
+        with_options: bool,
+
+        /// Warn if the logbook's picture or patch isn't in the BMS art directories
+        /// (or only matches ignoring case). `doctor` always checks.
+        #[arg(long)]
+        check_resources: bool,
+
+        /// Output one array of every logbook given, each with its `source` path.
+        /// --query runs on the whole array.
+        #[arg(long)]
+        combine: bool,
+
+        #[command(flatten)]
+        logbook: LogbookArg,
+
+        /// Encrypt the output with AES-256-GCM under a passphrase,
+        /// e.g., to back up a profile and its password to cloud storage. `write` decrypts it.
+        #[arg(long)]
+        encrypt: bool,
+
+        /// Passphrase for --encrypt (prompted for if not given)
+        #[arg(long, env = "BMS_LOGCAT_PASSPHRASE", hide_env_values = true)]
+        passphrase: Option<String>,
+
+        /// More `*.lbk` files to read, with --combine or --name-template
+        #[arg(value_hint = ValueHint::FilePath)]
+        more: Vec<Utf8PathBuf>,
+    },
+    /// Show the given logbook laid out for reading: who the pilot is, their stats,
+    /// and their medals
+    Show {
+        #[command(flatten)]
+        logbook: LogbookArg,
+    },
+    /// List every field of a logbook with its offset, its raw (decrypted) bytes,
+    /// and what they decode to, padding included
+    Inspect {
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: table::Format,
+
+        /// Pretty-print JSON output
+        #[arg(short, long)]
+        pretty: bool,
+
+        #[command(flatten)]
+        logbook: LogbookArg,
+    },
+    /// Export a logbook as JSON along with copies of its picture and patch,
+    /// so the whole profile can be archived or shared.
+    ///
+    /// Writes `{callsign}.json` into the directory, with `picture_file` and `patch_file`
+    /// pointing at the copies beside it.
+    Extract {
+        /// Directory to extract into
+        #[arg(long, value_hint = ValueHint::DirPath)]
+        to: Utf8PathBuf,
+
         /// Pretty-print the JSON output
-        #[clap(short, long)]
+        #[arg(short, long)]
         pretty: bool,
 
-        /// `*.lbk` to read
-        logbook: Utf8PathBuf,
+        #[command(flatten)]
+        logbook: LogbookArg,
     },
-    /// Read the given JSON and write it as a BMS logbook
+    /// Read the given JSON and write it as a BMS logbook.
+    ///
+    /// Also takes the output of `read --with-options`,
+    /// writing the options file next to the logbook.
+    /// JSON from older releases (see its `format_version`) is brought up to date.
+    /// Comments and trailing commas (JSON5 or JSONC) are fine.
     Write {
-        /// JSON file to read, or `-` for stdin
+        /// Promote the pilot to the rank their career has earned
+        #[arg(long)]
+        auto_rank: bool,
+
+        /// JSON file of rank thresholds to use with --auto-rank
+        #[arg(long, requires = "auto_rank", value_hint = ValueHint::FilePath)]
+        rank_rules: Option<Utf8PathBuf>,
+
+        /// Treat the JSON as a merge patch (RFC 7386) against this logbook,
+        /// taking any fields it doesn't mention from the logbook
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        patch: Option<Utf8PathBuf>,
+
+        /// Input format (defaults to what the file's extension says, or what it looks like)
+        #[arg(short, long, value_enum)]
+        format: Option<formats::Format>,
+
+        /// Passphrase for input from `read --encrypt` (prompted for if not given)
+        #[arg(long, env = "BMS_LOGCAT_PASSPHRASE", hide_env_values = true)]
+        passphrase: Option<String>,
+
+        /// JSON (or YAML, TOML, or CSV) file to read, or `-` for stdin
+        #[arg(value_hint = ValueHint::FilePath)]
         json: Utf8PathBuf,
     },
+    /// Show what differs between two logbooks, each a `.lbk` or what `read` wrote,
+    /// e.g., to see what `write` would change before running it
+    Diff {
+        /// Print changes as JSON
+        #[arg(short, long)]
+        json: bool,
+
+        /// Pretty-print JSON output
+        #[arg(short, long, requires = "json")]
+        pretty: bool,
+
+        /// Passphrase for input from `read --encrypt` (prompted for if needed)
+        #[arg(long, env = "BMS_LOGCAT_PASSPHRASE", hide_env_values = true)]
+        passphrase: Option<String>,
+
+        /// `*.lbk`, or JSON (or YAML, TOML, or CSV) to compare against
+        #[arg(value_hint = ValueHint::FilePath)]
+        old: Utf8PathBuf,
+
+        /// `*.lbk`, or JSON (or YAML, TOML, or CSV) to compare
+        #[arg(value_hint = ValueHint::FilePath)]
+        new: Utf8PathBuf,
+    },
+    /// Promote the pilot in the given logbook to the rank they've earned
+    Promote {
+        /// JSON file of rank thresholds (defaults to built-in BMS-like rules)
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        rank_rules: Option<Utf8PathBuf>,
+
+        #[command(flatten)]
+        logbook: LogbookArg,
+    },
+    /// Use an image (PNG or JPG) as the pilot's picture,
+    /// converting it into the pictures directory beside their logbook.
+    SetPicture {
+        /// Image to use
+        #[arg(value_hint = ValueHint::FilePath)]
+        image: Utf8PathBuf,
+
+        /// Picture width in pixels
+        #[arg(long, default_value = "128", value_parser = clap::value_parser!(u32).range(1..))]
+        width: u32,
+
+        /// Picture height in pixels. The image is cropped to fit, not stretched.
+        #[arg(long, default_value = "128", value_parser = clap::value_parser!(u32).range(1..))]
+        height: u32,
+
+        #[command(flatten)]
+        logbook: LogbookArg,
+    },
+    /// Add a pilot's kills and deaths from a Tacview ACMI recording to their logbook
+    ImportAcmi {
+        /// Recording to read (`.txt.acmi` or `.zip.acmi`)
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        acmi: Utf8PathBuf,
+
+        /// Which stats to add to. Campaign stats also count the recording as a mission.
+        #[arg(long, value_enum, default_value = "campaign")]
+        stats: acmi::Stats,
+
+        #[command(flatten)]
+        logbook: LogbookArg,
+    },
+    /// Add a mission's results (score, kills, hours) from debriefing text to a logbook
+    ImportDebrief {
+        /// Debriefing text to read, or `-` for stdin
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        debrief: Utf8PathBuf,
+
+        #[command(flatten)]
+        logbook: LogbookArg,
+    },
+    /// Set a pilot's flight hours from the time they spent flying in BMS's ACMI recordings,
+    /// e.g., after a corrupted logbook reset them.
+    ///
+    /// These are the Tacview `.acmi` recordings BMS saves, not its event logs:
+    /// turn on recording in BMS for the flights you want counted.
+    ///
+    /// Use --dry-run to preview the change.
+    ReconcileHours {
+        /// Directory of recordings (defaults to `User/Acmi` beside the logbook's directory)
+        #[arg(long, value_hint = ValueHint::DirPath)]
+        acmi_dir: Option<Utf8PathBuf>,
+
+        /// Lower the pilot's hours if the recordings add up to less.
+        /// By default they're only raised, since recordings may not cover every flight.
+        #[arg(long)]
+        allow_decrease: bool,
+
+        #[command(flatten)]
+        logbook: LogbookArg,
+    },
+    /// Merge two copies of a pilot's logbook (e.g., from two machines),
+    /// reporting which copy each differing field came from.
+    ///
+    /// Counters, rank, and medals take the most either copy has;
+    /// everything else comes from the more recently modified copy.
+    Sync {
+        #[arg(value_hint = ValueHint::FilePath)]
+        first: Utf8PathBuf,
+        #[arg(value_hint = ValueHint::FilePath)]
+        second: Utf8PathBuf,
+    },
+    /// Apply a JSON Patch (RFC 6902) to a logbook
+    ApplyPatch {
+        /// JSON Patch file to read, or `-` for stdin
+        #[arg(value_hint = ValueHint::FilePath)]
+        patch: Utf8PathBuf,
+
+        #[command(flatten)]
+        logbook: LogbookArg,
+    },
+    /// Change one field's bytes and leave every other byte as it was
+    ///
+    /// The safest way to edit logbooks from BMS versions this doesn't fully understand,
+    /// since nothing else is parsed and rewritten. Fields are named as `inspect` shows them.
+    PatchField {
+        /// Field to change, like `name` or `campaign_stats.kills`
+        field: String,
+        /// Its new value: text, a number, a rank, or `true`/`false` for medals
+        value: String,
+
+        #[command(flatten)]
+        logbook: LogbookArg,
+    },
+    /// Work out the ace factor the pilot's record earns: one plus the margin of
+    /// matches won over matches lost, from 0 to 2
+    ///
+    /// Dogfight matches count if the pilot has flown any, then campaign games.
+    /// Use `edit --auto-ace` to set it.
+    CalcAce {
+        /// Print the calculation as JSON
+        #[arg(short, long)]
+        json: bool,
+
+        /// Pretty-print JSON output
+        #[arg(short, long, requires = "json")]
+        pretty: bool,
+
+        #[command(flatten)]
+        logbook: LogbookArg,
+    },
+    /// Change fields by name, or go through them one at a time with --interactive
+    ///
+    /// Fields are named as `diff` shows them. Text is taken as-is,
+    /// medals as a comma-separated list, and anything else as JSON.
+    #[command(group = clap::ArgGroup::new("edits").required(true).multiple(true))]
+    Edit {
+        /// Prompt for each field in turn, showing its current value.
+        /// Enter keeps it; anything BMS won't take is asked for again.
+        #[arg(short, long, group = "edits")]
+        interactive: bool,
+
+        /// Set a field, like `squadron=80th FS` or `campaign_stats.kills=12`
+        #[arg(long = "set", value_name = "FIELD=VALUE", group = "edits")]
+        sets: Vec<edit::Assignment>,
+
+        /// Promote the pilot to the rank their career has earned, after any other edits
+        #[arg(long, group = "edits")]
+        auto_rank: bool,
+
+        /// JSON file of rank thresholds to use with --auto-rank
+        #[arg(long, requires = "auto_rank", value_hint = ValueHint::FilePath)]
+        rank_rules: Option<Utf8PathBuf>,
+
+        /// Set the ace factor from the pilot's record (see `calc-ace`), after any other edits
+        #[arg(long, group = "edits")]
+        auto_ace: bool,
+
+        #[command(flatten)]
+        logbook: LogbookArg,
+    },
+    /// Edit a logbook with a Rhai script, which sees it as `book`
+    Transform {
+        /// Rhai script to run
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        script: Utf8PathBuf,
+
+        #[command(flatten)]
+        logbook: LogbookArg,
+    },
+    /// Show pilots side by side, one column each and a row per stat,
+    /// with the best in each row marked
+    Compare {
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: table::Format,
+
+        /// Pretty-print JSON output
+        #[arg(short, long)]
+        pretty: bool,
+
+        /// Compare the logbook for this callsign from the BMS install too
+        #[arg(long)]
+        callsign: Vec<String>,
+
+        /// `*.lbk` files to compare (relative to the --install's config directory, if given)
+        #[arg(required_unless_present = "callsign", value_hint = ValueHint::FilePath)]
+        logbooks: Vec<Utf8PathBuf>,
+    },
+    /// Show where a pilot falls among the rest of a squadron,
+    /// as a percentile for each stat `compare` shows
+    RankPilot {
+        /// Directory of the squadron's `*.lbk` files (defaults to the --install's config directory)
+        #[arg(long, value_hint = ValueHint::DirPath)]
+        against: Option<Utf8PathBuf>,
+
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: table::Format,
+
+        /// Pretty-print JSON output
+        #[arg(short, long)]
+        pretty: bool,
+
+        #[command(flatten)]
+        logbook: LogbookArg,
+    },
+    /// Rank every logbook in a directory
+    Leaderboard {
+        /// Stat to rank pilots by
+        #[arg(short, long, value_enum, default_value = "score")]
+        sort: leaderboard::SortKey,
+
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: table::Format,
+
+        /// Pretty-print JSON output
+        #[arg(short, long)]
+        pretty: bool,
+
+        /// Directory of `*.lbk` files (defaults to the --install's config directory)
+        #[arg(value_hint = ValueHint::DirPath)]
+        dir: Option<Utf8PathBuf>,
+    },
+    /// Group every logbook in a directory by squadron
+    Roster {
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: roster::Format,
+
+        /// Pretty-print JSON output
+        #[arg(short, long)]
+        pretty: bool,
+
+        /// Directory of `*.lbk` files (defaults to the --install's config directory)
+        #[arg(value_hint = ValueHint::DirPath)]
+        dir: Option<Utf8PathBuf>,
+    },
+    /// Total up every logbook in a directory: hours, kills, average K/D,
+    /// and how many pilots have each medal
+    Aggregate {
+        /// Give totals for each squadron instead
+        #[arg(long)]
+        by_squadron: bool,
+
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: table::Format,
+
+        /// Pretty-print JSON output
+        #[arg(short, long)]
+        pretty: bool,
+
+        /// Directory of `*.lbk` files (defaults to the --install's config directory)
+        #[arg(value_hint = ValueHint::DirPath)]
+        dir: Option<Utf8PathBuf>,
+    },
+    /// List the squadron patches BMS can use
+    ListPatches {
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: table::Format,
+
+        /// Pretty-print JSON output
+        #[arg(short, long)]
+        pretty: bool,
+
+        /// Save a PNG thumbnail of each patch into this directory
+        #[arg(long, value_hint = ValueHint::DirPath)]
+        thumbnails: Option<Utf8PathBuf>,
+
+        /// Check that this logbook's patch_file is one of them
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        check: Option<Utf8PathBuf>,
+
+        /// `User/Config` directory whose patches to list
+        /// (defaults to the --install's config directory)
+        #[arg(value_hint = ValueHint::DirPath)]
+        dir: Option<Utf8PathBuf>,
+    },
+    /// Record the current stats of the given logbooks in the history database
+    Snapshot {
+        /// SQLite database to record to (defaults to one in the user's data directory)
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        db: Option<Utf8PathBuf>,
+
+        /// Snapshot the logbook for this callsign from the BMS install
+        #[arg(long)]
+        callsign: Vec<String>,
+
+        /// `*.lbk` files to snapshot
+        #[arg(required_unless_present = "callsign", value_hint = ValueHint::FilePath)]
+        logbooks: Vec<Utf8PathBuf>,
+    },
+    /// Show how a pilot's stats evolved across snapshots
+    History {
+        /// SQLite database to read from (defaults to one in the user's data directory)
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        db: Option<Utf8PathBuf>,
+
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: table::Format,
+
+        /// Pretty-print JSON output
+        #[arg(short, long)]
+        pretty: bool,
+
+        /// Callsign of the pilot to show
+        callsign: String,
+    },
+    /// Chart pilots' progression across snapshots as SVG (or PNG, if --output ends in .png)
+    Chart {
+        /// SQLite database to read from (defaults to one in the user's data directory)
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        db: Option<Utf8PathBuf>,
+
+        #[arg(short, long, value_enum, default_value = "hours")]
+        metric: chart::Metric,
+
+        /// Plot gains per week instead of running totals
+        #[arg(short, long)]
+        weekly: bool,
+
+        /// Chart every pilot snapshotted while in this squadron
+        #[arg(short, long)]
+        squadron: Option<String>,
+
+        /// Callsigns of the pilots to chart
+        #[arg(required_unless_present = "squadron")]
+        callsigns: Vec<String>,
+    },
+    /// Watch a directory and act on every logbook BMS rewrites
+    #[command(group(
+        ArgGroup::new("actions")
+            .args(["json_dir", "snapshot", "webhook", "discord", "metrics"])
+            .required(true)
+            .multiple(true)
+    ))]
+    Watch {
+        /// Export each changed logbook as JSON into this directory
+        #[arg(long, value_hint = ValueHint::DirPath)]
+        json_dir: Option<Utf8PathBuf>,
+
+        /// Record a snapshot of each changed logbook
+        #[arg(long)]
+        snapshot: bool,
+
+        /// SQLite database for --snapshot (defaults to one in the user's data directory)
+        #[arg(long, requires = "snapshot", value_hint = ValueHint::FilePath)]
+        db: Option<Utf8PathBuf>,
+
+        /// POST each changed logbook's JSON to this URL,
+        /// without its password or whatever --redact-profile leaves out
+        #[arg(long, env = "BMS_LOGCAT_WEBHOOK", hide_env_values = true, value_hint = ValueHint::Url)]
+        webhook: Option<String>,
+
+        /// Post pilot updates (missions flown, milestones, promotions)
+        /// to this Discord webhook URL
+        #[arg(long, env = "BMS_LOGCAT_DISCORD", hide_env_values = true, value_hint = ValueHint::Url)]
+        discord: Option<String>,
+
+        /// Serve Prometheus metrics of everyone's stats at /metrics on this address
+        /// (e.g., 127.0.0.1:9100)
+        #[arg(long)]
+        metrics: Option<String>,
+
+        /// Directory to watch (defaults to the --install's config directory)
+        #[arg(value_hint = ValueHint::DirPath)]
+        dir: Option<Utf8PathBuf>,
+    },
+    /// Serve a directory of logbooks over a REST API and web editor
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+
+        /// Print the API's OpenAPI document instead of serving it
+        #[arg(long)]
+        openapi: bool,
+
+        /// API token clients must give (repeatable). Prefer --token-file or
+        /// BMS_LOGCAT_TOKEN, since other users on this machine can see command lines.
+        #[arg(long = "token", env = "BMS_LOGCAT_TOKEN", hide_env_values = true)]
+        tokens: Vec<String>,
+
+        /// API token that can also use admin-only endpoints (repeatable)
+        #[arg(
+            long = "admin-token",
+            env = "BMS_LOGCAT_ADMIN_TOKEN",
+            hide_env_values = true
+        )]
+        admin_tokens: Vec<String>,
+
+        /// File of API tokens, one per line. Lines starting with `admin:` are admin tokens.
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        token_file: Option<Utf8PathBuf>,
+
+        /// Require a pilot's logbook password to change their logbook,
+        /// and never send passwords out
+        #[arg(long)]
+        require_password: bool,
+
+        /// Post pilot updates to this Discord webhook URL when logbooks change
+        #[arg(long, env = "BMS_LOGCAT_DISCORD", hide_env_values = true, value_hint = ValueHint::Url)]
+        discord: Option<String>,
+
+        /// Directory of logbooks (defaults to the --install's config directory)
+        #[arg(value_hint = ValueHint::DirPath)]
+        dir: Option<Utf8PathBuf>,
+    },
+    /// Check a pilot's `{callsign}.ini` settings file and print it as JSON
+    ReadIni {
+        /// Pretty-print the JSON output
+        #[arg(short, long)]
+        pretty: bool,
+
+        /// `*.ini` to read
+        #[arg(value_hint = ValueHint::FilePath)]
+        ini: Utf8PathBuf,
+    },
+    /// Apply the settings in a template INI file to every pilot's `{callsign}.ini`,
+    /// creating any that are missing.
+    ///
+    /// `{callsign}`, `{name}`, and `{squadron}` in the template's values
+    /// are filled in from each pilot's logbook.
+    ApplyIni {
+        /// INI file of settings to apply
+        #[arg(value_hint = ValueHint::FilePath)]
+        template: Utf8PathBuf,
+
+        /// Directory of logbooks (defaults to the --install's config directory)
+        #[arg(value_hint = ValueHint::DirPath)]
+        dir: Option<Utf8PathBuf>,
+    },
+    /// Bundle a pilot's logbook, options, settings, picture, and patch into one zip,
+    /// with a manifest.json saying what's what
+    #[command(visible_alias = "backup-bundle")]
+    ExportProfile {
+        /// Write the bundle into this directory as `{callsign}-{timestamp}.zip`
+        /// instead of to --output, e.g., to keep a series of backups
+        #[arg(long, value_hint = ValueHint::DirPath)]
+        to: Option<Utf8PathBuf>,
+
+        #[command(flatten)]
+        logbook: LogbookArg,
+    },
+    /// Unpack a bundle from export-profile into a BMS install
+    #[command(visible_alias = "restore-bundle")]
+    ImportProfile {
+        /// Bundle to unpack
+        #[arg(value_hint = ValueHint::FilePath)]
+        bundle: Utf8PathBuf,
+
+        /// `User/Config` directory to unpack it into
+        /// (defaults to the --install's config directory)
+        #[arg(value_hint = ValueHint::DirPath)]
+        dir: Option<Utf8PathBuf>,
+    },
+    /// List the BMS installs found on this machine
+    Installs {
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: table::Format,
+
+        /// Pretty-print JSON output
+        #[arg(short, long)]
+        pretty: bool,
+    },
+    /// Check BMS installs and logbooks for problems
+    Doctor {
+        /// Print findings as JSON
+        #[arg(short, long)]
+        json: bool,
+
+        /// Pretty-print JSON output
+        #[arg(short, long, requires = "json")]
+        pretty: bool,
+
+        /// Directories of logbooks to check instead of the BMS installs
+        #[arg(value_hint = ValueHint::DirPath)]
+        dirs: Vec<Utf8PathBuf>,
+    },
+    /// Warn about impossible or suspicious numbers in logbooks,
+    /// like negative counters or more kills than missions could explain
+    #[command(visible_alias = "validate")]
+    Lint {
+        /// Print findings as JSON
+        #[arg(short, long)]
+        json: bool,
+
+        /// Pretty-print JSON output
+        #[arg(short, long, requires = "json")]
+        pretty: bool,
+
+        /// TOML file of limits to hold pilots to, instead of the config file's `[policy]`
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        policy: Option<Utf8PathBuf>,
+
+        /// Flag pilots with more flight hours than this (10000 unless the policy says)
+        #[arg(long, value_name = "HOURS")]
+        max_hours: Option<f32>,
+
+        /// Flag pilots with more campaign kills per mission than this
+        /// (10 unless the policy says)
+        #[arg(long, value_name = "KILLS")]
+        max_kills_per_mission: Option<f32>,
+
+        /// Logbooks, or directories of them (defaults to the --install's config directory)
+        #[arg(value_hint = ValueHint::AnyPath)]
+        paths: Vec<Utf8PathBuf>,
+    },
+    /// Fix the impossible values `lint` finds, writing the corrected logbook
+    /// and reporting what changed
+    Sanitize {
+        /// Write the changes here as JSON, instead of to stderr as text
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        report: Option<Utf8PathBuf>,
+
+        #[command(flatten)]
+        logbook: LogbookArg,
+    },
+    /// Sign logbooks with an HMAC of their contents, written beside each as `{logbook}.sig`,
+    /// so a league can tell if their stats change before they're submitted
+    Sign {
+        /// Key to sign with (defaults to the config's `signing-key`)
+        #[arg(long, env = "BMS_LOGCAT_SIGNING_KEY", hide_env_values = true)]
+        key: Option<String>,
+
+        #[arg(required = true, value_hint = ValueHint::FilePath)]
+        logbooks: Vec<Utf8PathBuf>,
+    },
+    /// Check logbooks against the `{logbook}.sig` files `sign` wrote,
+    /// failing if any changed since or aren't signed
+    VerifySignature {
+        /// Key they were signed with (defaults to the config's `signing-key`)
+        #[arg(long, env = "BMS_LOGCAT_SIGNING_KEY", hide_env_values = true)]
+        key: Option<String>,
+
+        #[arg(required = true, value_hint = ValueHint::FilePath)]
+        logbooks: Vec<Utf8PathBuf>,
+    },
+    /// Find logbooks that claim the same callsign or options file, and suggest renames.
+    /// `doctor` checks this too.
+    Dupes {
+        /// Print findings as JSON
+        #[arg(short, long)]
+        json: bool,
+
+        /// Pretty-print JSON output
+        #[arg(short, long, requires = "json")]
+        pretty: bool,
+
+        /// Directory of logbooks (defaults to the --install's config directory)
+        #[arg(value_hint = ValueHint::DirPath)]
+        dir: Option<Utf8PathBuf>,
+    },
+    /// List logbooks that changed, appeared, or disappeared since the last `status`
+    /// of their directory, e.g., before and after a flying session or a BMS update.
+    ///
+    /// Each run records what it saw for next time (unless --dry-run).
+    Status {
+        /// Print the report as JSON
+        #[arg(short, long)]
+        json: bool,
+
+        /// Pretty-print JSON output
+        #[arg(short, long, requires = "json")]
+        pretty: bool,
+
+        /// Where to keep what each run saw
+        /// (defaults to `status.json` beside the config file)
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        state: Option<Utf8PathBuf>,
+
+        /// Directory of logbooks (defaults to the --install's config directory)
+        #[arg(value_hint = ValueHint::DirPath)]
+        dir: Option<Utf8PathBuf>,
+    },
+    /// Print a logbook as stable, pretty JSON for `git diff`.
+    ///
+    /// Set it up with `*.lbk diff=bms-logcat` in `.gitattributes` and
+    /// `git config diff.bms-logcat.textconv "bms-logcat textconv"`.
+    /// Out-of-range values are shown instead of refused, so any logbook can be diffed.
+    Textconv {
+        /// Logbook file, as git passes it
+        #[arg(value_hint = ValueHint::FilePath)]
+        file: Utf8PathBuf,
+    },
+    /// Git clean filter: turn the logbook on stdin into JSON on stdout.
+    ///
+    /// With `filter-smudge`, lets a repository store logbooks as JSON
+    /// while checking out real .lbk files. Set it up with `*.lbk filter=bms-logcat`
+    /// in `.gitattributes`, then `git config filter.bms-logcat.clean "bms-logcat filter-clean"`
+    /// and `git config filter.bms-logcat.smudge "bms-logcat filter-smudge"`.
+    /// Input that's already JSON passes through untouched, as long as it's a valid logbook.
+    FilterClean,
+    /// Git smudge filter: turn the JSON on stdin back into a logbook on stdout.
+    ///
+    /// See `filter-clean`. Input that isn't JSON (like logbooks committed before the filter
+    /// was set up) passes through untouched; JSON that isn't a valid logbook is an error.
+    FilterSmudge,
     /// Create a default logbook, commissioned today.
     WriteDefault {
-        #[clap(short, long)]
+        #[arg(short, long)]
         name: String,
 
-        #[clap(short, long)]
+        #[arg(short, long)]
         callsign: String,
 
-        #[clap(short, long)]
+        /// Logbook password (prefer BMS_LOGCAT_PASSWORD to keep it off the command line)
+        #[arg(short, long, env = "BMS_LOGCAT_PASSWORD", hide_env_values = true)]
         password: Option<String>,
+
+        /// Start the pilot with this career: rank, hours, and stats to match
+        #[arg(long, value_enum)]
+        profile: Option<preset::Preset>,
+    },
+    /// Generate a random but internally consistent logbook
+    Generate {
+        /// Seed for the generator; the same seed always gives the same logbook
+        #[arg(short, long)]
+        seed: u64,
+
+        /// Pilot name (picked at random if not given)
+        #[arg(short, long)]
+        name: Option<String>,
+
+        /// Pilot callsign (picked at random if not given)
+        #[arg(short, long)]
+        callsign: Option<String>,
     },
+    /// Print a roff man page covering every subcommand, e.g., for packaging
+    ///
+    /// Try `bms-logcat man | man -l -`.
+    Man,
+    /// Print a shell completion script
+    ///
+    /// For PowerShell, add `bms-logcat completions powershell | Out-String | Invoke-Expression`
+    /// to your profile (see `$PROFILE`). For bash, source the output from your `.bashrc`.
+    Completions {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+}
+
+/// A logbook given by path or by its pilot's callsign
+#[derive(clap::Args, Debug)]
+struct LogbookArg {
+    /// `*.lbk` to read
+    #[arg(required_unless_present = "callsign", value_hint = ValueHint::FilePath)]
+    logbook: Option<Utf8PathBuf>,
+
+    /// Find the logbook for this callsign in the BMS install instead
+    #[arg(long, conflicts_with = "logbook")]
+    callsign: Option<String>,
+}
+
+impl LogbookArg {
+    fn resolve(self, install: Option<&installs::Install>) -> Result<Utf8PathBuf> {
+        match (self.logbook, self.callsign) {
+            (Some(path), _) => Ok(installs::resolve(install, path)),
+            (None, Some(callsign)) => installs::find_callsign(install, &callsign),
+            (None, None) => unreachable!("clap requires a logbook or callsign"),
+        }
+    }
 }
 
 /// Read and write Falcon BMS logbooks
+///
+/// Most options can also be set with a `BMS_LOGCAT_` environment variable,
+/// shown beside each one. Command-line flags win over those, which win over the config file.
 #[derive(Parser, Debug)]
+#[command(version, after_help = exit::HELP)]
 struct Args {
     /// Verbosity (-v, -vv, -vvv, etc.)
-    #[clap(short, long, parse(from_occurrences))]
+    #[arg(short, long, action = ArgAction::Count)]
     verbose: u8,
 
-    #[clap(short, long, arg_enum, default_value = "auto")]
+    #[arg(
+        short,
+        long,
+        value_enum,
+        default_value = "auto",
+        env = "BMS_LOGCAT_COLOR"
+    )]
     color: logsetup::Color,
 
-    /// File to write to, or `-` for stdout
-    #[clap(short, long)]
+    /// How many logbooks to read at once in directory-wide commands
+    /// (defaults to one per CPU)
+    #[arg(
+        short,
+        long,
+        env = "BMS_LOGCAT_JOBS",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    jobs: Option<u32>,
+
+    /// Look for logbooks in subdirectories too, in commands that take a directory
+    #[arg(long, env = "BMS_LOGCAT_RECURSIVE", value_parser = FalseyValueParser::new())]
+    recursive: bool,
+
+    /// Only take logbooks whose path within the directory matches this glob (repeatable),
+    /// e.g., `'*/Viper*.lbk'`
+    #[arg(long, value_name = "GLOB")]
+    include: Vec<String>,
+
+    /// Skip logbooks whose path within the directory matches this glob (repeatable),
+    /// e.g., `'**/Backup*/**'`
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Skip logbooks last changed before this date (`YYYY-MM-DD`, in UTC) or RFC 3339 time
+    #[arg(long, value_name = "DATE", value_parser = batch::parse_time)]
+    newer_than: Option<time::OffsetDateTime>,
+
+    /// Don't show progress bars
+    #[arg(short, long, env = "BMS_LOGCAT_QUIET", value_parser = FalseyValueParser::new())]
+    quiet: bool,
+
+    /// How to write log messages and errors to stderr
+    #[arg(
+        long,
+        value_enum,
+        default_value = "text",
+        env = "BMS_LOGCAT_LOG_FORMAT"
+    )]
+    log_format: logsetup::LogFormat,
+
+    /// Also log to this file, at least at -v, with timestamps
+    #[arg(long, env = "BMS_LOGCAT_LOG_FILE", value_hint = ValueHint::FilePath)]
+    log_file: Option<Utf8PathBuf>,
+
+    /// Add to the log file instead of starting it over
+    #[arg(long, requires = "log_file", env = "BMS_LOGCAT_LOG_APPEND", value_parser = FalseyValueParser::new())]
+    log_append: bool,
+
+    /// Move the log file aside to `<file>.1` once it reaches this many megabytes
+    #[arg(
+        long,
+        requires = "log_file",
+        env = "BMS_LOGCAT_LOG_ROTATE",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    log_rotate: Option<u64>,
+
+    /// How many moved-aside log files to keep
+    #[arg(
+        long,
+        requires = "log_rotate",
+        default_value = "3",
+        env = "BMS_LOGCAT_LOG_KEEP"
+    )]
+    log_keep: usize,
+
+    /// File to write to, `-` for stdout, or `clipboard` for the system clipboard
+    #[arg(short, long, env = "BMS_LOGCAT_OUTPUT", value_hint = ValueHint::FilePath)]
     output: Option<Utf8PathBuf>,
 
-    #[clap(subcommand)]
-    command: Command,
+    /// Directory that relative --output paths and --name-template files go in
+    #[arg(long, env = "BMS_LOGCAT_OUTPUT_DIR", value_hint = ValueHint::DirPath)]
+    output_dir: Option<Utf8PathBuf>,
+
+    /// Write a file per logbook instead of one --output, when reading several,
+    /// named like `{callsign}_{rank}.json` from each logbook's fields (or `{file}`, its file name)
+    #[arg(long, env = "BMS_LOGCAT_NAME_TEMPLATE", conflicts_with = "output")]
+    name_template: Option<template::NameTemplate>,
+
+    /// Write logbooks even if BMS is running (it may overwrite them when it exits),
+    /// and binary output (like logbooks) even to a terminal
+    #[arg(long, conflicts_with = "wait", env = "BMS_LOGCAT_FORCE", value_parser = FalseyValueParser::new())]
+    force: bool,
+
+    /// If BMS is running, wait for it to exit before writing logbooks
+    #[arg(long, env = "BMS_LOGCAT_WAIT", value_parser = FalseyValueParser::new())]
+    wait: bool,
+
+    /// Don't write logbooks; print what would change in them instead
+    #[arg(long, env = "BMS_LOGCAT_DRY_RUN", value_parser = FalseyValueParser::new())]
+    dry_run: bool,
+
+    /// Warn about out-of-range values (like unknown voice indexes) instead of refusing
+    /// to read or write them. Strings too long for their field are truncated.
+    #[arg(long, env = "BMS_LOGCAT_NO_VALIDATE", value_parser = FalseyValueParser::new())]
+    no_validate: bool,
+
+    /// Read logbooks whose checksum isn't zero, as some BMS builds write them,
+    /// warning about it instead of refusing
+    #[arg(long, env = "BMS_LOGCAT_IGNORE_CHECKSUM", value_parser = FalseyValueParser::new())]
+    ignore_checksum: bool,
+
+    /// WebAssembly plugin to run on each logbook read or written (repeatable)
+    #[arg(long = "plugin", env = "BMS_LOGCAT_PLUGIN", value_hint = ValueHint::FilePath)]
+    plugins: Vec<Utf8PathBuf>,
+
+    /// Make text and JSON output byte-for-byte repeatable: sorted keys, `/` in paths,
+    /// zero-padded dates, and no trailing whitespace
+    #[arg(long, env = "BMS_LOGCAT_CANONICAL", value_parser = FalseyValueParser::new())]
+    canonical: bool,
+
+    /// Language for rank, medal, and column names in tables
+    #[arg(long, value_enum, env = "BMS_LOGCAT_LANG")]
+    lang: Option<lang::Lang>,
+
+    /// Write tables for people: flight hours as `1,234 h 30 m`, thousands separators
+    /// in counters, and how long ago pilots were commissioned
+    #[arg(
+        long,
+        env = "BMS_LOGCAT_HUMANIZE",
+        value_parser = FalseyValueParser::new(),
+        conflicts_with = "canonical"
+    )]
+    humanize: bool,
+
+    /// BMS version (e.g., `4.37`) or install directory whose `User/Config` directory
+    /// relative logbook paths and missing directories refer to
+    #[arg(short, long, env = "BMS_LOGCAT_BMS_DIR", value_hint = ValueHint::DirPath)]
+    install: Option<String>,
+
+    /// Key style for JSON output, and for `write`'s input
+    #[arg(long, value_enum, env = "BMS_LOGCAT_JSON_CASE")]
+    json_case: Option<case::Case>,
+
+    /// What to keep when overwriting a logbook
+    #[arg(long, value_enum, env = "BMS_LOGCAT_BACKUP")]
+    backup: Option<backup::Policy>,
+
+    /// Blank the fields this profile names (`public`, `league`, or one from the config file)
+    /// out of what `read`, reports, `serve`, and `watch --webhook` show
+    #[arg(long, env = "BMS_LOGCAT_REDACT_PROFILE")]
+    redact_profile: Option<String>,
+
+    /// Config file with defaults for these options
+    /// (defaults to `bms-logcat/config.toml` in your config directory)
+    #[arg(long, env = "BMS_LOGCAT_CONFIG", value_hint = ValueHint::FilePath)]
+    config: Option<Utf8PathBuf>,
+
+    /// Print the full help for every subcommand
+    #[arg(long, exclusive = true)]
+    help_all: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
 fn main() {
     run().unwrap_or_else(|e| {
-        error!("{:?}", e);
-        std::process::exit(1);
+        let code = exit::code(&e);
+        logsetup::log_fatal(&e, code);
+        std::process::exit(code);
     });
 }
 
 fn run() -> Result<()> {
     let args = Args::parse();
-    init_logger(args.verbose, args.color);
+    let log_file = args.log_file.map(|path| logsetup::LogFile {
+        path,
+        append: args.log_append,
+        rotate_at: args.log_rotate.map(|mb| mb * 1024 * 1024),
+        keep: args.log_keep,
+    });
+    init_logger(args.verbose, args.color, args.log_format, log_file.as_ref())?;
+    if args.canonical {
+        canonical::enable();
+    }
+    if args.humanize {
+        humanize::enable();
+    }
+    if args.quiet {
+        progress::quiet();
+    }
+    let filter = batch::Filter {
+        recursive: args.recursive,
+        include: batch::globs(&args.include).context("Bad --include")?,
+        exclude: batch::globs(&args.exclude).context("Bad --exclude")?,
+        newer_than: args.newer_than,
+    };
+    if let Some(jobs) = args.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs as usize)
+            .build_global()
+            .context("Couldn't start worker threads")?;
+    }
 
-    let output = args.output.unwrap_or_else(|| Utf8PathBuf::from("-"));
+    let config = config::Config::load(args.config.as_deref())?;
+    let keys = config
+        .keys
+        .as_ref()
+        .map(|k| k.keys())
+        .transpose()
+        .context("Couldn't use the config's [keys]")?
+        .unwrap_or_default();
 
-    match args.command {
-        Command::Read { pretty, logbook } => {
-            let r = reader(&logbook)?;
-            let book =
-                Logbook::parse(r).with_context(|| format!("Couldn't parse logbook {logbook}"))?;
+    case::set(
+        args.json_case
+            .or(config.json_case)
+            .unwrap_or(case::Case::Snake),
+    );
 
+    lang::set(args.lang.or(config.lang).unwrap_or(lang::Lang::En));
+
+    let redact = args
+        .redact_profile
+        .as_deref()
+        .map(|name| redact::Profile::named(name, &config.redact_profiles))
+        .transpose()?
+        .unwrap_or_default();
+
+    let output_dir = args.output_dir.or(config.output_dir);
+    let name_template = args.name_template;
+    let output = match (args.output, &output_dir) {
+        (Some(o), Some(dir)) if o.is_relative() && o != "-" && o != clipboard::NAME => dir.join(o),
+        (Some(o), _) => o,
+        (None, _) => Utf8PathBuf::from("-"),
+    };
+    let command = match args.command {
+        Some(c) => c,
+        None if args.help_all => {
             let mut w = writer(&output)?;
+            man::write_help_all(&mut w, &Args::command(), help_for)?;
+            w.flush()
+                .with_context(|| format!("Couldn't flush help to {output}"))?;
+            return Ok(());
+        }
+        None => Args::command()
+            .error(
+                clap::error::ErrorKind::MissingSubcommand,
+                "A subcommand is required (see --help)",
+            )
+            .exit(),
+    };
+    let install = args
+        .install
+        .or(config.install)
+        .as_deref()
+        .map(installs::find)
+        .transpose()?;
+    let install = install.as_ref();
+    let options = LogbookOptions {
+        force: args.force,
+        when_running: if args.force {
+            running::WhenRunning::Force
+        } else if args.wait {
+            running::WhenRunning::Wait
+        } else {
+            running::WhenRunning::Refuse
+        },
+        dry_run: args.dry_run,
+        validation: if args.no_validate || config.validate == Some(false) {
+            logbook::Validation::Lenient
+        } else {
+            logbook::Validation::Strict
+        },
+        ignore_checksum: args.ignore_checksum,
+        keys,
+        filter,
+        plugins: plugin::Plugins::load(&args.plugins)?,
+        backup: args.backup.or(config.backup).unwrap_or_default(),
+    };
+
+    match command {
+        Command::Read {
+            format,
+            outs,
+            pretty,
+            derived,
+            query,
+            fields,
+            with_options,
+            check_resources,
+            combine,
+            encrypt,
+            passphrase,
+            logbook,
+            more,
+        } => {
+            let query = query
+                .as_deref()
+                .map(query::Expr::parse)
+                .transpose()
+                .context("Couldn't parse --query")?;
+
+            let shape = ReadShape {
+                derived,
+                fields: &fields,
+                with_options,
+                check_resources,
+                redact: &redact,
+            };
+            let mut paths = Vec::new();
+            for path in std::iter::once(logbook.resolve(install)?)
+                .chain(more.into_iter().map(|p| installs::resolve(install, p)))
+            {
+                // A zip stands for every logbook in it.
+                if archive::is_zip(&path) {
+                    paths.extend(batch::logbooks_in(&path, &options.filter)?);
+                } else {
+                    paths.push(path);
+                }
+            }
+            let passphrase = if encrypt {
+                Some(read_passphrase(passphrase, true)?)
+            } else {
+                None
+            };
+            let eval = |value| match &query {
+                Some(q) => q.eval(&value).context("Couldn't run --query"),
+                None => Ok(vec![value]),
+            };
+
+            if let Some(template) = &name_template {
+                ensure!(!combine, "Give --combine or --name-template, not both");
+                ensure!(outs.is_empty(), "Give --out or --name-template, not both");
+                let dir = output_dir.as_deref().unwrap_or(Utf8Path::new("."));
+                let mut written = HashMap::new();
+                for path in paths {
+                    let (book, value) = shape.read(&path, &options)?;
+                    let out = dir.join(template.render(&book, &path)?);
+                    if let Some(other) = written.insert(out.clone(), path.clone()) {
+                        bail!("{other} and {path} would both be written to {out}");
+                    }
+                    if let Some(parent) = out.parent() {
+                        std::fs::create_dir_all(parent)
+                            .with_context(|| format!("Couldn't create {parent}"))?;
+                    }
+                    let format = format
+                        .or_else(|| formats::Format::from_path(&out))
+                        .unwrap_or(formats::Format::Json);
+                    write_read(&out, &eval(value)?, format, pretty, passphrase.as_deref())?;
+                    info!("Wrote {out}");
+                }
+                return Ok(());
+            }
 
-            if pretty {
-                writeln!(w, "{}", serde_json::to_string_pretty(&book)?)?;
+            let value = if combine {
+                let mut books = Vec::new();
+                for path in paths {
+                    let (_, value) = shape.read(&path, &options)?;
+                    books.push(with_source(&path, value));
+                }
+                serde_json::Value::Array(books)
+            } else {
+                ensure!(
+                    paths.len() == 1,
+                    "Give --combine or --name-template to read more than one logbook"
+                );
+                shape.read(&paths[0], &options)?.1
+            };
+            let values = eval(value)?;
+            let outs = if outs.is_empty() {
+                let format = format
+                    .or_else(|| formats::Format::from_path(&output))
+                    .unwrap_or(formats::Format::Json);
+                vec![formats::Out {
+                    format,
+                    path: output,
+                }]
             } else {
-                writeln!(w, "{}", serde_json::to_string(&book)?)?;
+                ensure!(output == "-", "Give --output or --out, not both");
+                outs
+            };
+            for out in outs {
+                write_read(
+                    &out.path,
+                    &values,
+                    out.format,
+                    pretty,
+                    passphrase.as_deref(),
+                )?;
             }
+        }
+        Command::Inspect {
+            format,
+            pretty,
+            logbook,
+        } => {
+            let logbook = logbook.resolve(install)?;
+            let bytes = batch::read_bytes(&logbook)?;
+            let events: Vec<layout::FieldEvent> = layout::events(&bytes, &options.keys)
+                .with_context(|| format!("Couldn't parse {logbook}"))?
+                .collect();
 
+            let mut w = writer(&output)?;
+            if let table::Format::Json = format {
+                write_json(&mut w, &events, pretty)?;
+            } else {
+                let mut t = table::Table::new(["Offset", "Length", "Field", "Raw", "Value"]);
+                for e in &events {
+                    let value = match &e.value {
+                        serde_json::Value::String(s) => format!("{s:?}"),
+                        serde_json::Value::Null => String::new(),
+                        v => v.to_string(),
+                    };
+                    t.push(vec![
+                        e.offset.to_string(),
+                        e.raw.len().to_string(),
+                        e.field.clone(),
+                        layout::to_hex(&e.raw),
+                        value,
+                    ]);
+                }
+                t.write(&mut w, format, "Inspect")?;
+            }
             w.flush()
-                .with_context(|| format!("Couldn't flush JSON to {output}"))?;
+                .with_context(|| format!("Couldn't flush {logbook} to {output}"))?;
         }
-        Command::Write { json } => {
-            let r = reader(&json)?;
-            let book: Logbook =
-                serde_json::from_reader(r).with_context(|| format!("Couldn't parse {json}"))?;
+        Command::Show { logbook } => {
+            let logbook = logbook.resolve(install)?;
+            let book = read_logbook(&logbook, &options)?;
+            let color = match args.color {
+                logsetup::Color::Always => true,
+                logsetup::Color::Never => false,
+                logsetup::Color::Auto => output == "-" && atty::is(atty::Stream::Stdout),
+            };
 
             let mut w = writer(&output)?;
-            book.write(&mut w)?;
+            write!(w, "{}", book.show(color))?;
+            w.flush()
+                .with_context(|| format!("Couldn't flush {logbook} to {output}"))?;
+        }
+        Command::Extract {
+            to,
+            pretty,
+            logbook,
+        } => {
+            let logbook = logbook.resolve(install)?;
+            let mut book = read_logbook(&logbook, &options)?;
+
+            let dir = logbook.parent().unwrap_or_else(|| Utf8Path::new("."));
+            let art = resources::ArtDirs::for_config_dir(dir);
+            std::fs::create_dir_all(&to).with_context(|| format!("Couldn't create {to}"))?;
+            resources::extract(&mut book, &art, &to)?;
 
+            let json = to.join(format!("{}.json", book.callsign));
+            let mut w = writer(&json)?;
+            write_json(&mut w, &version::to_value(&book)?, pretty)?;
             w.flush()
-                .with_context(|| format!("Couldn't flush logbook to {output}"))?;
-        },
-        Command::WriteDefault { name, callsign, password } => {
-            let password = password.unwrap_or_default();
-            let book = Logbook::new(name, callsign, password)?;
+                .with_context(|| format!("Couldn't flush JSON to {json}"))?;
+            info!("Extracted {} to {to}", book.callsign);
+        }
+        Command::Write {
+            auto_rank,
+            rank_rules,
+            patch,
+            format,
+            passphrase,
+            json,
+        } => {
+            let (value, pilot_options) = read_export(&json, format, passphrase)?;
+
+            let mut book: Logbook = match patch {
+                Some(base) => {
+                    let base = installs::resolve(install, base);
+                    let existing = read_logbook(&base, &options)?;
+                    patch::merge(&existing, &value)
+                        .with_context(|| format!("Couldn't apply {json} to {base}"))?
+                }
+                None => serde_json::from_value(value)
+                    .with_context(|| format!("Couldn't parse {json}"))?,
+            };
+
+            if auto_rank {
+                promote(&mut book, rank_rules.as_deref())?;
+            }
+
+            if pilot_options.is_some() {
+                ensure!(
+                    output != "-",
+                    "Can't write options files to stdout; give an --output logbook"
+                );
+            }
+            write_logbook(&book, &output, &options)?;
+            if let Some(pilot_options) = pilot_options {
+                if options.dry_run {
+                    let mut w = std::io::stdout().lock();
+                    writeln!(w, "Would write {}", pop::path_for(&output, &book))?;
+                } else {
+                    pilot_options.write(&output, &book)?;
+                }
+            }
+        }
+        Command::Diff {
+            json,
+            pretty,
+            passphrase,
+            old,
+            new,
+        } => {
+            // Both go through the model, so only differences it keeps show up.
+            let read_either = |path: Utf8PathBuf| -> Result<Logbook> {
+                if path
+                    .extension()
+                    .is_some_and(|e| e.eq_ignore_ascii_case("lbk"))
+                {
+                    return read_logbook(&installs::resolve(install, path), &options);
+                }
+                let (value, _) = read_export(&path, None, passphrase.clone())?;
+                serde_json::from_value(value).with_context(|| format!("Couldn't parse {path}"))
+            };
+            let changes = diff::changes(&read_either(old)?, &read_either(new)?)?;
 
             let mut w = writer(&output)?;
-            book.write(&mut w)?;
+            if json {
+                write_json(&mut w, &changes, pretty)?;
+            } else {
+                diff::write_text(&mut w, &changes)?;
+            }
 
             w.flush()
-                .with_context(|| format!("Couldn't flush logbook to {output}"))?;
+                .with_context(|| format!("Couldn't flush changes to {output}"))?;
+            if !changes.is_empty() {
+                return Err(exit::Found(format!("Found {} change(s)", changes.len())).into());
+            }
         }
-    }
-    Ok(())
-}
+        Command::Promote {
+            rank_rules,
+            logbook,
+        } => {
+            let logbook = logbook.resolve(install)?;
+            let mut book = read_logbook(&logbook, &options)?;
 
-fn reader(path: &Utf8Path) -> Result<BufReader<Box<dyn Read>>> {
-    let reader: Box<dyn Read> = match path.as_str() {
-        "-" => Box::new(std::io::stdin()),
-        p => {
-            let f = File::open(p).with_context(|| format!("Couldn't read {p}"))?;
-            Box::new(f)
+            promote(&mut book, rank_rules.as_deref())?;
+
+            write_logbook(&book, &output, &options)?;
+        }
+        Command::SetPicture {
+            image,
+            width,
+            height,
+            logbook,
+        } => {
+            let logbook = logbook.resolve(install)?;
+            let mut book = read_logbook(&logbook, &options)?;
+
+            let dir = logbook.parent().unwrap_or_else(|| Utf8Path::new("."));
+            let art = resources::ArtDirs::for_config_dir(dir);
+            book.picture_file = resources::import_picture(
+                &image,
+                &art,
+                &book.callsign,
+                (width, height),
+                options.dry_run,
+            )?;
+
+            write_logbook(&book, &output, &options)?;
+        }
+        Command::ImportAcmi {
+            acmi,
+            stats,
+            logbook,
+        } => {
+            let logbook = logbook.resolve(install)?;
+            let mut book = read_logbook(&logbook, &options)?;
+            let recording = acmi::Recording::read(&acmi)?;
+
+            ensure!(
+                recording
+                    .pilots
+                    .iter()
+                    .any(|p| p.eq_ignore_ascii_case(&book.callsign)),
+                "{} isn't in {acmi} (pilots: {})",
+                book.callsign,
+                recording.pilots.join(", ")
+            );
+            let tally = recording.tally(&book.callsign);
+            info!("{}: {tally:?}", book.callsign);
+            acmi::apply(&mut book, &tally, stats);
+
+            write_logbook(&book, &output, &options)?;
+        }
+        Command::ImportDebrief { debrief, logbook } => {
+            let logbook = logbook.resolve(install)?;
+            let mut book = read_logbook(&logbook, &options)?;
+
+            let mut text = String::new();
+            reader(&debrief)?
+                .read_to_string(&mut text)
+                .with_context(|| format!("Couldn't read {debrief}"))?;
+            let debriefs =
+                debrief::parse(&text).with_context(|| format!("Couldn't parse {debrief}"))?;
+            let results = debrief::for_pilot(debriefs, &book.callsign)?;
+            info!("{}: {results:?}", book.callsign);
+            debrief::apply(&mut book, &results);
+
+            write_logbook(&book, &output, &options)?;
+        }
+        Command::ReconcileHours {
+            acmi_dir,
+            allow_decrease,
+            logbook,
+        } => {
+            let logbook = logbook.resolve(install)?;
+            let mut book = read_logbook(&logbook, &options)?;
+            let acmi_dir = acmi_dir.unwrap_or_else(|| {
+                let config = logbook.parent().unwrap_or_else(|| Utf8Path::new("."));
+                config.parent().unwrap_or(config).join("Acmi")
+            });
+
+            let mut hours = 0.0;
+            let mut flown = 0;
+            for path in acmi::recordings_in(&acmi_dir)? {
+                match acmi::Recording::read(&path) {
+                    Ok(recording) => {
+                        let h = recording.hours(&book.callsign);
+                        if h > 0.0 {
+                            debug!("{} flew {h:.2} hours in {path}", book.callsign);
+                            hours += h;
+                            flown += 1;
+                        }
+                    }
+                    Err(e) => warn!("Skipping {path}: {e:#}"),
+                }
+            }
+            info!(
+                "{} flew {hours:.1} hours across {flown} recording(s) in {acmi_dir}",
+                book.callsign
+            );
+            ensure!(
+                flown > 0,
+                "{} isn't in any recordings in {acmi_dir}; leaving their hours alone",
+                book.callsign
+            );
+
+            let hours = hours as f32;
+            if hours < book.flight_hours && !allow_decrease {
+                bail!(
+                    "Recordings only add up to {hours:.1} of {}'s {:.1} hours; \
+                     use --allow-decrease to lower them anyways",
+                    book.callsign,
+                    book.flight_hours
+                );
+            }
+            book.flight_hours = hours;
+
+            write_logbook(&book, &output, &options)?;
+        }
+        Command::Sync { first, second } => {
+            let first = installs::resolve(install, first);
+            let second = installs::resolve(install, second);
+            let first_book = read_logbook(&first, &options)?;
+            let second_book = read_logbook(&second, &options)?;
+            ensure!(
+                first_book
+                    .callsign
+                    .eq_ignore_ascii_case(&second_book.callsign),
+                "{first} is {}, but {second} is {}",
+                first_book.callsign,
+                second_book.callsign
+            );
+
+            let modified = |path: &Utf8Path| {
+                std::fs::metadata(path)
+                    .and_then(|m| m.modified())
+                    .with_context(|| format!("Couldn't tell when {path} was modified"))
+            };
+            let (newer, older) = if modified(&second)? > modified(&first)? {
+                (
+                    (second.as_path(), &second_book),
+                    (first.as_path(), &first_book),
+                )
+            } else {
+                (
+                    (first.as_path(), &first_book),
+                    (second.as_path(), &second_book),
+                )
+            };
+            debug!("{} is newer", newer.0);
+
+            let (merged, report) = sync::merge(newer, older)?;
+            sync::write_report(&mut std::io::stderr().lock(), &report)?;
+
+            write_logbook(&merged, &output, &options)?;
+        }
+        Command::ApplyPatch { patch, logbook } => {
+            let logbook = logbook.resolve(install)?;
+            let book = read_logbook(&logbook, &options)?;
+            let ops: json_patch::Patch = serde_json::from_reader(reader(&patch)?)
+                .with_context(|| format!("Couldn't parse {patch}"))?;
+
+            let book = patch::apply(&book, &ops)
+                .with_context(|| format!("Couldn't apply {patch} to {logbook}"))?;
+
+            write_logbook(&book, &output, &options)?;
+        }
+        Command::PatchField {
+            field,
+            value,
+            logbook,
+        } => {
+            let logbook = logbook.resolve(install)?;
+            let mut bytes = batch::read_bytes(&logbook)?;
+            let old = layout::patch(&mut bytes, &field, &value, options.writing())
+                .with_context(|| format!("Couldn't patch {logbook}"))?;
+
+            if options.dry_run {
+                let new = layout::events(&bytes, &options.keys)?
+                    .find(|e| e.field == field)
+                    .unwrap();
+                let change = diff::Change {
+                    field,
+                    old: Some(old.value),
+                    new: Some(new.value),
+                };
+                let mut w = std::io::stdout().lock();
+                writeln!(w, "Would write {output}:")?;
+                diff::write_text(&mut w, &[change])?;
+                return Ok(());
+            }
+            if output != "-" {
+                running::guard(&output, options.when_running)?;
+                backup::backup(&output, options.backup)?;
+            }
+            let mut w = binary_writer(&output, options.force)?;
+            w.write_all(&bytes)?;
+            w.flush()
+                .with_context(|| format!("Couldn't flush logbook to {output}"))?;
+        }
+        Command::CalcAce {
+            json,
+            pretty,
+            logbook,
+        } => {
+            let logbook = logbook.resolve(install)?;
+            let book = read_logbook(&logbook, &options)?;
+            let calc = ace::calculate(&book);
+
+            let mut w = writer(&output)?;
+            if json {
+                write_json(&mut w, &calc, pretty)?;
+            } else {
+                ace::write_text(&mut w, &calc)?;
+            }
+            w.flush()
+                .with_context(|| format!("Couldn't flush ace factor to {output}"))?;
+        }
+        Command::Edit {
+            interactive,
+            sets,
+            auto_rank,
+            rank_rules,
+            auto_ace,
+            logbook,
+        } => {
+            let logbook = logbook.resolve(install)?;
+            if interactive && logbook == "-" {
+                bail!("Can't prompt for fields while reading the logbook from stdin");
+            }
+            let book = read_logbook(&logbook, &options)?;
+
+            let mut book = edit::set(&book, &sets, options.validation)?;
+            if interactive {
+                book = edit::interactive(
+                    &book,
+                    std::io::stdin().lock(),
+                    std::io::stderr().lock(),
+                    options.validation,
+                )?;
+            }
+            if auto_rank {
+                promote(&mut book, rank_rules.as_deref())?;
+            }
+            if auto_ace {
+                book.ace_factor = ace::calculate(&book).ace_factor;
+            }
+
+            write_logbook(&book, &output, &options)?;
+        }
+        Command::Transform { script, logbook } => {
+            let logbook = logbook.resolve(install)?;
+            let book = read_logbook(&logbook, &options)?;
+
+            let book = script::transform(&book, &script)?;
+
+            write_logbook(&book, &output, &options)?;
+        }
+        Command::Compare {
+            format,
+            pretty,
+            callsign,
+            logbooks,
+        } => {
+            let mut paths: Vec<Utf8PathBuf> = logbooks
+                .into_iter()
+                .map(|p| installs::resolve(install, p))
+                .collect();
+            for c in &callsign {
+                paths.push(installs::find_callsign(install, c)?);
+            }
+            if paths.len() < 2 {
+                Args::command()
+                    .error(
+                        clap::error::ErrorKind::TooFewValues,
+                        "compare needs at least two logbooks (files or --callsign)",
+                    )
+                    .exit();
+            }
+
+            let books = paths
+                .iter()
+                .map(|l| read_logbook(l, &options))
+                .collect::<Result<Vec<_>>>()?;
+            let comparison = compare::compare(&books);
+
+            let mut w = writer(&output)?;
+            compare::write(&mut w, &comparison, format, pretty)?;
+            w.flush()
+                .with_context(|| format!("Couldn't flush comparison to {output}"))?;
+        }
+        Command::RankPilot {
+            against,
+            format,
+            pretty,
+            logbook,
+        } => {
+            let logbook = logbook.resolve(install)?;
+            let book = read_logbook(&logbook, &options)?;
+            let dir = installs::dir_or_config(install, against)?;
+            let entries = batch::read_dir_with_progress(&dir, &options.filter, options.parsing())?;
+            let ranks = percentile::rank(&book, &logbook, &entries);
+
+            let mut w = writer(&output)?;
+            percentile::write(&mut w, &book.callsign, &ranks, format, pretty)?;
+            w.flush()
+                .with_context(|| format!("Couldn't flush ranking to {output}"))?;
+        }
+        Command::Leaderboard {
+            sort,
+            format,
+            pretty,
+            dir,
+        } => {
+            let dir = installs::dir_or_config(install, dir)?;
+            let entries = redact.apply_entries(batch::read_dir_with_progress(
+                &dir,
+                &options.filter,
+                options.parsing(),
+            )?);
+            let standings = leaderboard::standings(&entries, sort);
+
+            let mut w = writer(&output)?;
+            leaderboard::write(&mut w, &standings, format, pretty)?;
+
+            w.flush()
+                .with_context(|| format!("Couldn't flush leaderboard to {output}"))?;
+        }
+        Command::Aggregate {
+            by_squadron,
+            format,
+            pretty,
+            dir,
+        } => {
+            let dir = installs::dir_or_config(install, dir)?;
+            let entries = redact.apply_entries(batch::read_dir_with_progress(
+                &dir,
+                &options.filter,
+                options.parsing(),
+            )?);
+            let aggregates = aggregate::aggregate(&entries, by_squadron);
+
+            let mut w = writer(&output)?;
+            aggregate::write(&mut w, &aggregates, format, pretty)?;
+
+            w.flush()
+                .with_context(|| format!("Couldn't flush totals to {output}"))?;
+        }
+        Command::Roster {
+            format,
+            pretty,
+            dir,
+        } => {
+            let dir = installs::dir_or_config(install, dir)?;
+            let entries = redact.apply_entries(batch::read_dir_with_progress(
+                &dir,
+                &options.filter,
+                options.parsing(),
+            )?);
+            let squadrons = roster::squadrons(&entries);
+
+            let mut w = writer(&output)?;
+            roster::write(&mut w, &squadrons, format, pretty)?;
+
+            w.flush()
+                .with_context(|| format!("Couldn't flush roster to {output}"))?;
+        }
+        Command::ListPatches {
+            format,
+            pretty,
+            thumbnails,
+            check,
+            dir,
+        } => {
+            let dir = installs::dir_or_config(install, dir)?;
+            let art = resources::ArtDirs::for_config_dir(&dir);
+            let patches = resources::patches(&art)?;
+
+            let mut w = writer(&output)?;
+            resources::write_patches(&mut w, &patches, format, pretty)?;
+            w.flush()
+                .with_context(|| format!("Couldn't flush patches to {output}"))?;
+
+            if let Some(to_dir) = thumbnails {
+                resources::write_thumbnails(&art, &patches, &to_dir)?;
+            }
+
+            if let Some(logbook) = check {
+                let logbook = installs::resolve(install, logbook);
+                let book = read_logbook(&logbook, &options)?;
+                let patch = &book.patch_file;
+                ensure!(!patch.as_str().is_empty(), "{} has no patch", book.callsign);
+                match resources::find(&art.patches, patch) {
+                    resources::Lookup::Found(_) => info!("{} uses patch {patch}", book.callsign),
+                    resources::Lookup::CaseMismatch(actual) => {
+                        warn!("patch {patch} only matches {actual} if you ignore case")
+                    }
+                    resources::Lookup::Missing => {
+                        return Err(exit::Found(format!(
+                            "{}'s patch {patch} isn't in {}",
+                            book.callsign, art.patches
+                        ))
+                        .into())
+                    }
+                }
+            }
+        }
+        Command::Snapshot {
+            db,
+            callsign,
+            logbooks,
+        } => {
+            let db = history_db(db)?;
+
+            let mut paths: Vec<Utf8PathBuf> = logbooks
+                .into_iter()
+                .map(|p| installs::resolve(install, p))
+                .collect();
+            for c in &callsign {
+                paths.push(installs::find_callsign(install, c)?);
+            }
+
+            for logbook in paths {
+                let book = read_logbook(&logbook, &options)?;
+                db.record(&logbook, &book)?;
+                info!("Recorded a snapshot of {}", book.callsign);
+            }
+        }
+        Command::History {
+            db,
+            format,
+            pretty,
+            callsign,
+        } => {
+            let db = history_db(db)?;
+            let snapshots = db.snapshots(&callsign)?;
+            if snapshots.is_empty() {
+                warn!("No snapshots of {callsign}");
+            }
+
+            let mut w = writer(&output)?;
+            history::write(&mut w, &snapshots, format, pretty)?;
+
+            w.flush()
+                .with_context(|| format!("Couldn't flush history to {output}"))?;
+        }
+        Command::Chart {
+            db,
+            metric,
+            weekly,
+            squadron,
+            mut callsigns,
+        } => {
+            let db = history_db(db)?;
+            if let Some(squadron) = squadron {
+                callsigns.extend(db.squadron_callsigns(&squadron)?);
+            }
+
+            let mut series = Vec::with_capacity(callsigns.len());
+            for callsign in &callsigns {
+                let snapshots = db.snapshots(callsign)?;
+                if snapshots.is_empty() {
+                    warn!("No snapshots of {callsign}");
+                    continue;
+                }
+                series.push(chart::series(callsign, &snapshots, metric, weekly)?);
+            }
+
+            let is_png = output
+                .extension()
+                .map(|e| e.eq_ignore_ascii_case("png"))
+                .unwrap_or(false);
+            if is_png {
+                chart::render_png(&output, &series, metric, weekly)?;
+            } else {
+                let svg = chart::render_svg(&series, metric, weekly)?;
+
+                let mut w = writer(&output)?;
+                w.write_all(svg.as_bytes())?;
+
+                w.flush()
+                    .with_context(|| format!("Couldn't flush chart to {output}"))?;
+            }
+        }
+        Command::Watch {
+            json_dir,
+            snapshot,
+            db,
+            webhook,
+            discord,
+            metrics,
+            dir,
+        } => {
+            let actions = watch::Actions {
+                json_dir,
+                history: if snapshot {
+                    Some(history_db(db)?)
+                } else {
+                    None
+                },
+                webhook,
+                redact: redact.with(redact::Field::Password),
+                discord: discord.map(notify::Discord::new),
+                metrics,
+            };
+            let dir = installs::dir_or_config(install, dir)?;
+            watch::watch(&dir, &options.filter, options.parsing(), &actions)?;
+        }
+        Command::Serve {
+            addr,
+            openapi,
+            mut tokens,
+            mut admin_tokens,
+            token_file,
+            require_password,
+            discord,
+            dir,
+        } => {
+            if openapi {
+                let mut w = writer(&output)?;
+                // Describes the server's JSON, which --json-case doesn't change.
+                write_json_in(&mut w, &serve::openapi(), true, case::Case::Snake)?;
+                w.flush()
+                    .with_context(|| format!("Couldn't flush OpenAPI document to {output}"))?;
+                return Ok(());
+            }
+
+            if let Some(path) = token_file {
+                let file = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Couldn't read {path}"))?;
+                for line in file.lines().map(str::trim).filter(|l| !l.is_empty()) {
+                    match line.strip_prefix("admin:") {
+                        Some(admin) => admin_tokens.push(admin.trim().to_owned()),
+                        None => tokens.push(line.to_owned()),
+                    }
+                }
+            }
+            if tokens.is_empty() && admin_tokens.is_empty() {
+                warn!("No API tokens given; anyone who can reach the server can change logbooks");
+            }
+
+            let auth = serve::Auth {
+                tokens,
+                admin_tokens,
+                require_password,
+            };
+            let dir = installs::dir_or_config(install, dir)?;
+            let discord = discord.map(notify::Discord::new);
+            let server = serve::Server::new(dir, &options, auth, redact, discord);
+            server.run(&addr)?;
+        }
+        Command::ReadIni { pretty, ini } => {
+            let ini = installs::resolve(install, ini);
+            let settings = ini::Ini::read(&ini, options.validation)?;
+
+            let mut w = writer(&output)?;
+            // Keys are BMS's setting names, not ours to rename.
+            write_json_in(&mut w, &settings.to_json(), pretty, case::Case::Snake)?;
+
+            w.flush()
+                .with_context(|| format!("Couldn't flush JSON to {output}"))?;
+        }
+        Command::ApplyIni { template, dir } => {
+            let template = ini::Ini::read(&template, options.validation)?;
+            let dir = installs::dir_or_config(install, dir)?;
+
+            for entry in batch::read_dir_with_progress(&dir, &options.filter, options.parsing())? {
+                let book = &entry.logbook;
+                let path = ini::path_for(&dir, book);
+                let mut settings = if path.exists() {
+                    ini::Ini::read(&path, options.validation)?
+                } else {
+                    ini::Ini::default()
+                };
+
+                let changes = settings.apply(&template, book);
+                if changes.is_empty() {
+                    debug!("{path} is already up to date");
+                    continue;
+                }
+                if options.dry_run {
+                    let mut w = std::io::stdout().lock();
+                    writeln!(w, "Would write {path}:")?;
+                    for c in &changes {
+                        let old = c.old.as_deref().unwrap_or("(unset)");
+                        writeln!(w, "  [{}] {}: {old} -> {}", c.section, c.key, c.new)?;
+                    }
+                    continue;
+                }
+                running::guard(&path, options.when_running)?;
+                settings.write(&path)?;
+                info!("Updated {} setting(s) in {path}", changes.len());
+            }
+        }
+        Command::ExportProfile { to, logbook } => {
+            let logbook = logbook.resolve(install)?;
+            let book = read_logbook(&logbook, &options)?;
+            let bundle = profile::export(&logbook, &book)?;
+
+            let output = match to {
+                Some(dir) => {
+                    ensure!(output == "-", "Give --output or --to, not both");
+                    std::fs::create_dir_all(&dir)
+                        .with_context(|| format!("Couldn't create {dir}"))?;
+                    dir.join(profile::timestamped_name(&book)?)
+                }
+                None => output,
+            };
+            let mut w = binary_writer(&output, options.force)?;
+            w.write_all(&bundle)
+                .with_context(|| format!("Couldn't write bundle to {output}"))?;
+            w.flush()
+                .with_context(|| format!("Couldn't flush bundle to {output}"))?;
+            info!("Bundled {} into {output}", book.callsign);
+        }
+        Command::ImportProfile { bundle, dir } => {
+            let mut bytes = Vec::new();
+            reader(&bundle)?
+                .read_to_end(&mut bytes)
+                .with_context(|| format!("Couldn't read {bundle}"))?;
+            let dir = installs::dir_or_config(install, dir)?;
+
+            let book = profile::import(&bytes, &dir, &options)
+                .with_context(|| format!("Couldn't import {bundle}"))?;
+            info!("Imported {} into {dir}", book.callsign);
+        }
+        Command::Installs { format, pretty } => {
+            let installs = installs::discover();
+            if installs.is_empty() {
+                warn!("Couldn't find any BMS installs");
+            }
+
+            let mut w = writer(&output)?;
+            installs::write(&mut w, &installs, format, pretty)?;
+
+            w.flush()
+                .with_context(|| format!("Couldn't flush installs to {output}"))?;
+        }
+        Command::Doctor { json, pretty, dirs } => {
+            let installs = match (install, dirs.is_empty()) {
+                (Some(i), _) => vec![i.clone()],
+                (None, true) => installs::discover(),
+                (None, false) => Vec::new(),
+            };
+            let findings = doctor::examine(&installs, &dirs, &options.filter, options.parsing());
+
+            let mut w = writer(&output)?;
+            if json {
+                write_json(&mut w, &findings, pretty)?;
+            } else {
+                doctor::write_text(&mut w, &findings)?;
+            }
+
+            w.flush()
+                .with_context(|| format!("Couldn't flush findings to {output}"))?;
+
+            let errors = findings
+                .iter()
+                .filter(|f| f.severity == doctor::Severity::Error)
+                .count();
+            if errors > 0 {
+                return Err(
+                    exit::Found(format!("Found {errors} problem(s) that need fixing")).into(),
+                );
+            }
+        }
+        Command::Dupes { json, pretty, dir } => {
+            let dir = installs::dir_or_config(install, dir)?;
+            let entries = batch::read_dir_with_progress(&dir, &options.filter, options.parsing())?;
+            let findings = doctor::conflicts(&dir, &entries);
+
+            let mut w = writer(&output)?;
+            if json {
+                write_json(&mut w, &findings, pretty)?;
+            } else {
+                doctor::write_text(&mut w, &findings)?;
+            }
+
+            w.flush()
+                .with_context(|| format!("Couldn't flush findings to {output}"))?;
+            if !findings.is_empty() {
+                return Err(exit::Found(format!("Found {} conflict(s)", findings.len())).into());
+            }
+        }
+        Command::Status {
+            json,
+            pretty,
+            state,
+            dir,
+        } => {
+            let dir = installs::dir_or_config(install, dir)?;
+            let path = match state {
+                Some(p) => p,
+                None => status::default_path()?,
+            };
+            let mut state = status::State::load(&path)?;
+            let report = state.check(&dir, &options.filter, options.lenient())?;
+
+            let mut w = writer(&output)?;
+            if json {
+                write_json(&mut w, &report, pretty)?;
+            } else {
+                status::write_text(&mut w, &report)?;
+            }
+            w.flush()
+                .with_context(|| format!("Couldn't flush status to {output}"))?;
+
+            if !options.dry_run {
+                state.save(&path)?;
+            }
+        }
+        Command::Lint {
+            json,
+            pretty,
+            policy,
+            max_hours,
+            max_kills_per_mission,
+            paths,
+        } => {
+            let paths = if paths.is_empty() {
+                vec![installs::dir_or_config(install, None)?]
+            } else {
+                paths
+            };
+            let mut policy = match policy {
+                Some(path) => lint::Policy::load(&path)?,
+                None => config.policy.clone().unwrap_or_default(),
+            };
+            if let Some(max) = max_hours {
+                policy.max_hours = max;
+            }
+            if let Some(max) = max_kills_per_mission {
+                policy.max_kills_per_mission = max;
+            }
+            let findings = lint::lint(&paths, &policy, &options.filter, options.parsing());
+
+            let mut w = writer(&output)?;
+            if json {
+                write_json(&mut w, &findings, pretty)?;
+            } else {
+                doctor::write_text(&mut w, &findings)?;
+            }
+
+            w.flush()
+                .with_context(|| format!("Couldn't flush findings to {output}"))?;
+
+            // Notes are just oddities; they don't fail the lint.
+            let suspicious = findings
+                .iter()
+                .filter(|f| f.severity < doctor::Severity::Note)
+                .count();
+            if suspicious > 0 {
+                return Err(exit::Found(format!("Found {suspicious} suspicious value(s)")).into());
+            }
+        }
+        Command::Sanitize { report, logbook } => {
+            let logbook = logbook.resolve(install)?;
+            // Read what strict validation would refuse; fixing it is the point.
+            let before = read_logbook_with(&logbook, &options, options.lenient())?;
+            let mut book = before.clone();
+            lint::sanitize(&mut book)?;
+
+            write_logbook(&book, &output, &options)?;
+
+            let changes = diff::changes(&before, &book)?;
+            match report {
+                Some(path) => {
+                    let mut w = writer(&path)?;
+                    write_json(&mut w, &changes, true)?;
+                    w.flush()
+                        .with_context(|| format!("Couldn't flush report to {path}"))?;
+                }
+                None => diff::write_text(&mut std::io::stderr().lock(), &changes)?,
+            }
+        }
+        Command::Sign { key, logbooks } => {
+            let key = signing_key(key.or_else(|| config.signing_key.clone()))?;
+            for logbook in logbooks {
+                let logbook = installs::resolve(install, logbook);
+                let book = read_logbook(&logbook, &options)?;
+                let sig = signature::sidecar(&logbook);
+                std::fs::write(&sig, signature::sign(&book, &key)? + "\n")
+                    .with_context(|| format!("Couldn't write {sig}"))?;
+                info!("Signed {logbook} as {sig}");
+            }
+        }
+        Command::VerifySignature { key, logbooks } => {
+            let key = signing_key(key.or_else(|| config.signing_key.clone()))?;
+            let mut w = writer(&output)?;
+            let mut bad = 0;
+            for logbook in logbooks {
+                let logbook = installs::resolve(install, logbook);
+                let book = read_logbook(&logbook, &options)?;
+                let sig = signature::sidecar(&logbook);
+                let status = match std::fs::read_to_string(&sig) {
+                    Ok(s) if signature::verify(&book, &key, &s)? => "OK",
+                    Ok(_) => "CHANGED",
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => "UNSIGNED",
+                    Err(e) => return Err(e).with_context(|| format!("Couldn't read {sig}")),
+                };
+                if status != "OK" {
+                    bad += 1;
+                }
+                writeln!(w, "{status}\t{logbook}")?;
+            }
+            w.flush()
+                .with_context(|| format!("Couldn't flush results to {output}"))?;
+            if bad > 0 {
+                return Err(exit::Found(format!(
+                    "{bad} logbook(s) changed since they were signed, or weren't signed"
+                ))
+                .into());
+            }
+        }
+        Command::Textconv { file } => {
+            let book = read_logbook_with(&file, &options, options.lenient())?;
+
+            let mut w = writer(&output)?;
+            write_json(&mut w, &version::to_value(&book)?, true)?;
+            w.flush()
+                .with_context(|| format!("Couldn't flush JSON to {output}"))?;
+        }
+        Command::FilterClean => {
+            let input = read_stdin()?;
+            let mut w = std::io::stdout().lock();
+            if is_json(&input) {
+                // Still refuse JSON that smudging couldn't turn back into a logbook.
+                from_json(&input)?;
+                w.write_all(&input)?;
+            } else {
+                let book = parse_logbook(&input, "stdin", &options, options.lenient())?;
+                write_json(&mut w, &version::to_value(&book)?, true)?;
+            }
+            w.flush().context("Couldn't flush stdout")?;
+        }
+        Command::FilterSmudge => {
+            let input = read_stdin()?;
+            let mut w = std::io::stdout().lock();
+            if is_json(&input) {
+                let writing = logbook::WriteOptions {
+                    validation: logbook::Validation::Lenient,
+                    ..options.writing()
+                };
+                from_json(&input)?.write(&mut w, writing)?;
+            } else {
+                w.write_all(&input)?;
+            }
+            w.flush().context("Couldn't flush stdout")?;
+        }
+        Command::WriteDefault {
+            name,
+            callsign,
+            password,
+            profile,
+        } => {
+            let password = password.unwrap_or_default();
+            let mut book = Logbook::new(name, callsign, password)?;
+            if let Some(preset) = profile {
+                preset.apply(&mut book);
+            }
+
+            write_logbook(&book, &output, &options)?;
+        }
+        Command::Generate {
+            seed,
+            name,
+            callsign,
+        } => {
+            let book = generate::generate(seed, name, callsign)?;
+
+            write_logbook(&book, &output, &options)?;
+        }
+        Command::Man => {
+            let mut w = writer(&output)?;
+            man::write_man(&mut w, &Args::command(), exit::HELP)?;
+
+            w.flush()
+                .with_context(|| format!("Couldn't flush man page to {output}"))?;
+        }
+        Command::Completions { shell } => {
+            let mut w = writer(&output)?;
+            clap_complete::generate(shell, &mut Args::command(), "bms-logcat", &mut w);
+
+            w.flush()
+                .with_context(|| format!("Couldn't flush completions to {output}"))?;
+        }
+    }
+    Ok(())
+}
+
+/// What `read` outputs for each logbook
+struct ReadShape<'a> {
+    derived: bool,
+    fields: &'a [String],
+    with_options: bool,
+    check_resources: bool,
+    redact: &'a redact::Profile,
+}
+
+impl ReadShape<'_> {
+    /// The logbook (as shown) and what to output for it
+    fn read(
+        &self,
+        path: &Utf8Path,
+        options: &LogbookOptions,
+    ) -> Result<(Logbook, serde_json::Value)> {
+        let book = read_logbook(path, options)?;
+        if self.check_resources {
+            warn_missing_resources(path, &book);
+        }
+
+        let shown = self.redact.apply(book.clone());
+        let value = if self.derived {
+            serde_json::to_value(stats::WithDerived::new(&shown))?
+        } else {
+            serde_json::to_value(&shown)?
+        };
+        // So --fields and --query use the same names as the output.
+        let mut value = case::to_case(value, case::get());
+        if !self.fields.is_empty() {
+            value = query::select(&value, self.fields)?;
+        }
+        if self.with_options {
+            let options = match pop::PilotOptions::read(path, &book) {
+                Ok(o) => Some(o),
+                Err(e) => {
+                    warn!("{e:#}");
+                    None
+                }
+            };
+            value = serde_json::json!({ "logbook": value, "options": options });
+        }
+        if self.fields.is_empty() {
+            value = version::stamp(value);
+        }
+        Ok((shown, value))
+    }
+}
+
+/// Write what `read` outputs, sealed if there's a passphrase.
+fn write_read(
+    path: &Utf8Path,
+    values: &[serde_json::Value],
+    format: formats::Format,
+    pretty: bool,
+    passphrase: Option<&str>,
+) -> Result<()> {
+    let mut w = writer(path)?;
+    match passphrase {
+        Some(passphrase) => {
+            let mut plain = Vec::new();
+            formats::write(&mut plain, values, format, pretty)?;
+            w.write_all(&seal::seal(&plain, passphrase)?)?;
+        }
+        None => formats::write(&mut w, values, format, pretty)?,
+    }
+    w.flush()
+        .with_context(|| format!("Couldn't flush output to {path}"))
+}
+
+/// Tag `value` with the file it came from, as its first field.
+fn with_source(path: &Utf8Path, value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(fields) => {
+            let mut tagged = serde_json::Map::with_capacity(fields.len() + 1);
+            tagged.insert("source".to_owned(), path.as_str().into());
+            tagged.extend(fields);
+            serde_json::Value::Object(tagged)
+        }
+        v => v,
+    }
+}
+
+fn warn_missing_resources(path: &Utf8Path, book: &Logbook) {
+    let dir = path.parent().unwrap_or_else(|| Utf8Path::new("."));
+    let art = resources::ArtDirs::for_config_dir(dir);
+    for resource in resources::check(book, &art) {
+        let (kind, file, dir) = (resource.kind, resource.file, resource.dir);
+        match resource.lookup {
+            resources::Lookup::Found(_) => debug!("Found {kind} {file} in {dir}"),
+            resources::Lookup::CaseMismatch(actual) => {
+                warn!("{kind} {file} only matches {actual} if you ignore case")
+            }
+            resources::Lookup::Missing => warn!("{kind} {file} isn't in {dir}"),
+        }
+    }
+}
+
+/// `--help` for a subcommand (or the program, given no path), as clap renders it
+fn help_for(path: &[&str]) -> String {
+    let argv = std::iter::once("bms-logcat")
+        .chain(path.iter().copied())
+        .chain(std::iter::once("--help"));
+    match Args::try_parse_from(argv) {
+        Err(e) => e.to_string(),
+        Ok(_) => String::new(),
+    }
+}
+
+fn read_stdin() -> Result<Vec<u8>> {
+    let mut input = Vec::new();
+    std::io::stdin()
+        .lock()
+        .read_to_end(&mut input)
+        .context("Couldn't read stdin")?;
+    Ok(input)
+}
+
+/// Parse JSON, or failing that, JSON5 (which allows comments, trailing commas, and so on),
+/// so hand-maintained files can be annotated.
+fn parse_json5(text: &str) -> Result<serde_json::Value> {
+    match serde_json::from_str(text) {
+        Ok(value) => Ok(value),
+        // If it isn't JSON5 either, the JSON error is the one to show.
+        Err(e) => json5::from_str(text).map_err(|_| e.into()),
+    }
+}
+
+/// Is `input` a logbook's JSON (an object), rather than a logbook?
+fn is_json(input: &[u8]) -> bool {
+    input.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'{')
+}
+
+/// The logbook in `input`, which [`is_json`]
+fn from_json(input: &[u8]) -> Result<Logbook> {
+    let value = serde_json::from_slice(input).context("Couldn't parse the JSON on stdin")?;
+    let value =
+        version::migrate(case::to_snake(value)).context("Couldn't read the JSON on stdin")?;
+    serde_json::from_value(value).context("Couldn't parse the JSON on stdin")
+}
+
+fn history_db(path: Option<Utf8PathBuf>) -> Result<history::History> {
+    let path = match path {
+        Some(p) => p,
+        None => history::default_path()?,
+    };
+    history::History::open(&path)
+}
+
+fn promote(book: &mut Logbook, rules: Option<&Utf8Path>) -> Result<()> {
+    let rules = match rules {
+        Some(path) => promotion::PromotionRules::load(path)?,
+        None => promotion::PromotionRules::default(),
+    };
+
+    match rules.apply(book) {
+        Some(rank) => info!("Promoted {} to {:?}", book.callsign, rank),
+        None => info!("{} keeps the rank of {:?}", book.callsign, book.rank),
+    }
+    Ok(())
+}
+
+/// How to go about reading and writing logbooks
+#[derive(Debug)]
+pub struct LogbookOptions {
+    when_running: running::WhenRunning,
+    /// Write binary output to a terminal if asked to
+    force: bool,
+    dry_run: bool,
+    validation: logbook::Validation,
+    ignore_checksum: bool,
+    /// What logbooks are obfuscated with (the config's `[keys]`)
+    keys: logbook::Keys,
+    /// Which logbooks directory-wide commands pick up
+    filter: batch::Filter,
+    plugins: plugin::Plugins,
+    backup: backup::Policy,
+}
+
+impl LogbookOptions {
+    /// How to parse logbooks read with these options
+    fn parsing(&self) -> logbook::ParseOptions {
+        logbook::ParseOptions {
+            validation: self.validation,
+            ignore_checksum: self.ignore_checksum,
+            keys: self.keys.clone(),
+        }
+    }
+
+    /// How to write logbooks with these options
+    fn writing(&self) -> logbook::WriteOptions {
+        logbook::WriteOptions {
+            validation: self.validation,
+            keys: self.keys.clone(),
+        }
+    }
+
+    /// [`LogbookOptions::parsing`], but taking values BMS doesn't expect whatever
+    /// `--no-validate` says, for commands that read logbooks to fix or show them
+    fn lenient(&self) -> logbook::ParseOptions {
+        logbook::ParseOptions {
+            validation: logbook::Validation::Lenient,
+            ..self.parsing()
+        }
+    }
+}
+
+/// Read what `read` wrote (in any format, and sealed or not), giving the logbook's JSON
+/// and the pilot options `read --with-options` put beside it, if any.
+fn read_export(
+    path: &Utf8Path,
+    format: Option<formats::Format>,
+    passphrase: Option<String>,
+) -> Result<(serde_json::Value, Option<pop::PilotOptions>)> {
+    let mut text = String::new();
+    reader(path)?
+        .read_to_string(&mut text)
+        .with_context(|| format!("Couldn't read {path}"))?;
+    if seal::is_sealed(&text) {
+        let passphrase = read_passphrase(passphrase, false)?;
+        let plain =
+            seal::open(&text, &passphrase).with_context(|| format!("Couldn't decrypt {path}"))?;
+        text = String::from_utf8(plain).with_context(|| format!("Couldn't decrypt {path}"))?;
+    }
+    let format = formats::Format::detect(format, path, &text);
+    let value = formats::parse(&text, format).with_context(|| format!("Couldn't parse {path}"))?;
+    let value =
+        version::migrate(case::to_snake(value)).with_context(|| format!("Couldn't read {path}"))?;
+    // Is this a logbook and its options (see `read --with-options`)?
+    if value.get("logbook").is_some() {
+        let profile: pop::Profile =
+            serde_json::from_value(value).with_context(|| format!("Couldn't parse {path}"))?;
+        return Ok((serde_json::to_value(profile.logbook)?, profile.options));
+    }
+    Ok((value, None))
+}
+
+/// The `--passphrase` given, or one typed at the terminal (twice, if `confirm`)
+fn read_passphrase(given: Option<String>, confirm: bool) -> Result<String> {
+    let passphrase = match given {
+        Some(p) => p,
+        None => {
+            ensure!(
+                atty::is(atty::Stream::Stdin),
+                "No passphrase; pass --passphrase or set BMS_LOGCAT_PASSPHRASE"
+            );
+            let p = rpassword::prompt_password("Passphrase: ")?;
+            if confirm {
+                let again = rpassword::prompt_password("Again: ")?;
+                ensure!(p == again, "Passphrases didn't match");
+            }
+            p
+        }
+    };
+    ensure!(!passphrase.is_empty(), "The passphrase can't be empty");
+    Ok(passphrase)
+}
+
+/// The `--key` given (or the config's `signing-key`), which there has to be
+fn signing_key(key: Option<String>) -> Result<String> {
+    key.ok_or_else(|| {
+        anyhow!(
+            "No signing key; pass --key, or set BMS_LOGCAT_SIGNING_KEY or signing-key in the config"
+        )
+    })
+}
+
+/// Parse the logbook at the given path, or stdin for `-`, and run plugins on it.
+///
+/// Anything odd that didn't stop it parsing is logged as a warning.
+pub fn read_logbook(path: &Utf8Path, options: &LogbookOptions) -> Result<Logbook> {
+    read_logbook_with(path, options, options.parsing())
+}
+
+/// [`read_logbook`], but parsing as `parsing` says
+fn read_logbook_with(
+    path: &Utf8Path,
+    options: &LogbookOptions,
+    parsing: logbook::ParseOptions,
+) -> Result<Logbook> {
+    let mut bytes = Vec::with_capacity(logbook::LOGBOOK_LEN);
+    reader(path)?
+        .read_to_end(&mut bytes)
+        .with_context(|| format!("Couldn't read {path}"))?;
+    parse_logbook(&bytes, path.as_str(), options, parsing)
+}
+
+/// Parse the logbook in `bytes`, read from `from`, and run plugins on it.
+fn parse_logbook(
+    bytes: &[u8],
+    from: &str,
+    options: &LogbookOptions,
+    parsing: logbook::ParseOptions,
+) -> Result<Logbook> {
+    let (book, warnings) = Logbook::parse_with_report(bytes, parsing)
+        .with_context(|| exit::Unparsable(format!("Couldn't parse logbook {from}")))?;
+    for warning in warnings {
+        warn!("{from}: {warning}");
+    }
+    options.plugins.run(plugin::Hook::Parse, book)
+}
+
+/// Write the logbook to the given path, or stdout for `-`.
+pub fn write_logbook(book: &Logbook, output: &Utf8Path, options: &LogbookOptions) -> Result<()> {
+    write_logbook_with(book, output, options, options.backup)
+}
+
+/// [`write_logbook`], but backing up what's there as `backup` says
+pub fn write_logbook_with(
+    book: &Logbook,
+    output: &Utf8Path,
+    options: &LogbookOptions,
+    backup: backup::Policy,
+) -> Result<()> {
+    let book = &options.plugins.run(plugin::Hook::Write, book.clone())?;
+
+    if options.dry_run {
+        return dry_run(book, output, options);
+    }
+
+    if output != "-" {
+        running::guard(output, options.when_running)?;
+        backup::backup(output, backup)?;
+    }
+
+    let mut w = binary_writer(output, options.force)?;
+    book.write(&mut w, options.writing())?;
+
+    w.flush()
+        .with_context(|| format!("Couldn't flush logbook to {output}"))?;
+    Ok(())
+}
+
+/// Print the fields that writing the logbook would change.
+fn dry_run(book: &Logbook, output: &Utf8Path, options: &LogbookOptions) -> Result<()> {
+    // Compare against whatever's there now, or an empty logbook if nothing is.
+    let existing = if output != "-" && output.exists() {
+        let r = reader(output)?;
+        Logbook::parse_with(r, options.parsing())
+            .with_context(|| format!("Couldn't parse logbook {output}"))?
+    } else {
+        Logbook::default()
+    };
+
+    let changes = diff::changes(&existing, book)?;
+    // Still check it could be written.
+    book.write(std::io::sink(), options.writing())?;
+
+    let mut w = std::io::stdout().lock();
+    writeln!(w, "Would write {output}:")?;
+    diff::write_text(&mut w, &changes)?;
+    Ok(())
+}
+
+pub fn write_json<W: Write, T: serde::Serialize>(w: &mut W, value: &T, pretty: bool) -> Result<()> {
+    write_json_in(w, value, pretty, case::get())
+}
+
+/// [`write_json`], with keys in the given case instead of `--json-case`'s
+fn write_json_in<W: Write, T: serde::Serialize>(
+    w: &mut W,
+    value: &T,
+    pretty: bool,
+    case: case::Case,
+) -> Result<()> {
+    if case == case::Case::Snake && !canonical::enabled() {
+        return write_json_value(w, value, pretty);
+    }
+    write_json_value(w, &output_value(value, case)?, pretty)
+}
+
+/// `value` as [`write_json`] writes it: keys in `case`, and canonical if asked
+pub fn output_value<T: serde::Serialize>(value: &T, case: case::Case) -> Result<serde_json::Value> {
+    let value = serde_json::to_value(value)?;
+    Ok(if canonical::enabled() {
+        // Before renaming, since it goes by our field names. And after, to re-sort keys.
+        canonical::canonicalize(case::to_case(canonical::canonicalize(value), case))
+    } else {
+        case::to_case(value, case)
+    })
+}
+
+fn write_json_value<W: Write, T: serde::Serialize>(
+    w: &mut W,
+    value: &T,
+    pretty: bool,
+) -> Result<()> {
+    if pretty {
+        writeln!(w, "{}", serde_json::to_string_pretty(value)?)?;
+    } else {
+        writeln!(w, "{}", serde_json::to_string(value)?)?;
+    }
+    Ok(())
+}
+
+fn reader(path: &Utf8Path) -> Result<BufReader<Box<dyn Read>>> {
+    let reader: Box<dyn Read> = match path.as_str() {
+        "-" => Box::new(std::io::stdin()),
+        _ if archive::split(path).is_some() => Box::new(std::io::Cursor::new(archive::read(path)?)),
+        p => {
+            let f = lock::open_shared(p.into())?;
+            Box::new(f)
         }
     };
     Ok(BufReader::new(reader))
 }
 
+/// Like [`writer`], for bytes that aren't text.
+///
+/// Refuses to put them on a terminal, where they're garbage
+/// (and Windows consoles reject anything that isn't UTF-8), unless forced.
+/// Redirected stdout gets the bytes as-is on every platform;
+/// Rust's stdio doesn't translate line endings the way C's text mode does.
+fn binary_writer(path: &Utf8Path, force: bool) -> Result<BufWriter<Box<dyn Write>>> {
+    ensure!(
+        archive::split(path).is_none(),
+        "Can't write into zip archives; give an --output outside {path}"
+    );
+    ensure!(
+        path != clipboard::NAME,
+        "Can't copy binary output to the clipboard; give an --output file"
+    );
+    if path == "-" && !force && atty::is(atty::Stream::Stdout) {
+        bail!("Not writing binary output to the terminal; redirect it, give --output, or --force");
+    }
+    writer(path)
+}
+
 fn writer(path: &Utf8Path) -> Result<BufWriter<Box<dyn Write>>> {
     let writer: Box<dyn Write> = match path.as_str() {
         "-" => Box::new(std::io::stdout()),
+        clipboard::NAME => Box::new(clipboard::Clipboard::default()),
         p => {
-            let f = File::create(p).with_context(|| format!("Couldn't write to {p}"))?;
+            let f = lock::create_exclusive(p.into())?;
             Box::new(f)
         }
     };
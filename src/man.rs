@@ -0,0 +1,146 @@
+//! A man page and long-form help, generated from the command-line definitions
+//! so they can't drift from `--help`.
+
+use std::io::prelude::*;
+
+use anyhow::Result;
+use clap::{Arg, Command};
+
+/// Write a roff man page covering the program and every subcommand.
+pub fn write_man<W: Write>(w: &mut W, cmd: &Command, exit_codes: &str) -> Result<()> {
+    let name = cmd.get_name();
+    writeln!(
+        w,
+        ".TH {} 1 \"\" \"{name} {}\"",
+        name.to_uppercase(),
+        env!("CARGO_PKG_VERSION")
+    )?;
+
+    writeln!(w, ".SH NAME")?;
+    writeln!(
+        w,
+        "{name} \\- {}",
+        escape(&cmd.get_about().map(ToString::to_string).unwrap_or_default())
+    )?;
+
+    writeln!(w, ".SH SYNOPSIS")?;
+    writeln!(w, "\\fB{name}\\fR [\\fIOPTIONS\\fR] \\fICOMMAND\\fR")?;
+
+    if let Some(long) = cmd.get_long_about() {
+        writeln!(w, ".SH DESCRIPTION")?;
+        write_paragraphs(w, &long.to_string())?;
+    }
+
+    writeln!(w, ".SH OPTIONS")?;
+    for arg in visible(cmd) {
+        write_arg(w, arg)?;
+    }
+
+    writeln!(w, ".SH COMMANDS")?;
+    for sub in cmd.get_subcommands().filter(|s| !s.is_hide_set()) {
+        writeln!(w, ".SS {}", escape(sub.get_name()))?;
+        let about = sub.get_long_about().or_else(|| sub.get_about());
+        if let Some(about) = about {
+            write_paragraphs(w, &about.to_string())?;
+        }
+        for arg in visible(sub) {
+            write_arg(w, arg)?;
+        }
+    }
+
+    writeln!(w, ".SH EXIT STATUS")?;
+    for line in exit_codes.lines().skip(1) {
+        if let Some((code, meaning)) = line.trim().split_once(char::is_whitespace) {
+            writeln!(w, ".TP\n\\fB{code}\\fR\n{}", escape(meaning.trim()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Write `--help` for the program, then for each subcommand in turn.
+pub fn write_help_all<W: Write, F>(w: &mut W, cmd: &Command, help_for: F) -> Result<()>
+where
+    F: Fn(&[&str]) -> String,
+{
+    write!(w, "{}", help_for(&[]))?;
+    for sub in cmd.get_subcommands().filter(|s| !s.is_hide_set()) {
+        let rule = "=".repeat(72);
+        writeln!(w, "\n{rule}\n")?;
+        write!(w, "{}", help_for(&[sub.get_name()]))?;
+    }
+    Ok(())
+}
+
+/// Arguments worth documenting: not hidden, and not the `--help` and `--version`
+/// every command gets
+fn visible(cmd: &Command) -> impl Iterator<Item = &Arg> {
+    cmd.get_arguments()
+        .filter(|a| !a.is_hide_set() && !matches!(a.get_id().as_str(), "help" | "version"))
+}
+
+fn write_arg<W: Write>(w: &mut W, arg: &Arg) -> Result<()> {
+    let value = arg
+        .get_value_names()
+        .and_then(|names| names.first())
+        .map(|name| name.as_str())
+        .unwrap_or_else(|| arg.get_id().as_str())
+        .to_uppercase();
+
+    let mut flags = Vec::new();
+    if let Some(short) = arg.get_short() {
+        flags.push(format!("\\fB\\-{short}\\fR"));
+    }
+    if let Some(long) = arg.get_long() {
+        flags.push(format!("\\fB\\-\\-{}\\fR", escape(long)));
+    }
+    let mut header = flags.join(", ");
+    if arg.is_positional() {
+        header = format!("\\fI{}\\fR", escape(&value));
+    } else if arg.get_action().takes_values() {
+        header.push_str(&format!(" \\fI{}\\fR", escape(&value)));
+    }
+
+    writeln!(w, ".TP")?;
+    writeln!(w, "{header}")?;
+    let help = arg.get_long_help().or_else(|| arg.get_help());
+    if let Some(help) = help {
+        writeln!(w, "{}", escape(&help.to_string()))?;
+    }
+    let values = arg.get_possible_values();
+    if arg.get_action().takes_values() && !values.is_empty() {
+        let values: Vec<&str> = values.iter().map(|v| v.get_name()).collect();
+        writeln!(w, ".br\nOne of: {}", escape(&values.join(", ")))?;
+    }
+    let defaults = arg.get_default_values();
+    if !defaults.is_empty() && !arg.is_hide_default_value_set() {
+        let defaults: Vec<_> = defaults.iter().map(|d| d.to_string_lossy()).collect();
+        writeln!(w, ".br\nDefault: {}", escape(&defaults.join(", ")))?;
+    }
+    Ok(())
+}
+
+fn write_paragraphs<W: Write>(w: &mut W, text: &str) -> Result<()> {
+    for (i, paragraph) in text.split("\n\n").enumerate() {
+        if i > 0 {
+            writeln!(w, ".PP")?;
+        }
+        writeln!(w, "{}", escape(paragraph))?;
+    }
+    Ok(())
+}
+
+/// Escape text so roff doesn't take it for requests or escapes.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\e")
+        .replace('-', "\\-")
+        .lines()
+        .map(|line| {
+            if line.starts_with('.') || line.starts_with('\'') {
+                format!("\\&{line}")
+            } else {
+                line.to_owned()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
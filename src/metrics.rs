@@ -0,0 +1,123 @@
+//! Squadron stats as Prometheus metrics, so they can be scraped and charted.
+
+use std::fmt::Write;
+
+use anyhow::{anyhow, Result};
+use log::*;
+use tiny_http::{Header, Response};
+
+use crate::batch::Entry;
+use crate::logbook::Logbook;
+
+/// What Prometheus expects to be served
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// A gauge's values, each with the `kind` label to give it (if any)
+type Values = Vec<(Option<&'static str>, f64)>;
+
+/// A per-pilot gauge: its name, help text, and how to get its value(s)
+struct Gauge {
+    name: &'static str,
+    help: &'static str,
+    values: fn(&Logbook) -> Values,
+}
+
+/// Campaign stats are used throughout, like the leaderboard.
+const GAUGES: &[Gauge] = &[
+    Gauge {
+        name: "bms_pilot_flight_hours",
+        help: "Total flight hours",
+        values: |b| vec![(None, b.flight_hours.into())],
+    },
+    Gauge {
+        name: "bms_pilot_missions",
+        help: "Campaign missions flown",
+        values: |b| vec![(None, b.campaign_stats.missions.into())],
+    },
+    Gauge {
+        name: "bms_pilot_kills",
+        help: "Campaign kills, by kind",
+        values: |b| {
+            let c = &b.campaign_stats;
+            vec![
+                (Some("air"), c.kills.into()),
+                (Some("ground"), c.air_to_ground_kills.into()),
+                (Some("static"), c.static_kills.into()),
+                (Some("naval"), c.naval_kills.into()),
+                (Some("friendly"), c.friendly_kills.into()),
+            ]
+        },
+    },
+    Gauge {
+        name: "bms_pilot_deaths",
+        help: "Times killed in the campaign",
+        values: |b| vec![(None, b.campaign_stats.killed.into())],
+    },
+    Gauge {
+        name: "bms_pilot_score",
+        help: "Total campaign score",
+        values: |b| vec![(None, b.campaign_stats.total_score.into())],
+    },
+];
+
+/// Render the pilots' stats in Prometheus' text exposition format.
+pub fn render(entries: &[Entry]) -> String {
+    let mut out = String::new();
+    writeln!(out, "# HELP bms_pilots Logbooks being tracked").unwrap();
+    writeln!(out, "# TYPE bms_pilots gauge").unwrap();
+    writeln!(out, "bms_pilots {}", entries.len()).unwrap();
+
+    for gauge in GAUGES {
+        writeln!(out, "# HELP {} {}", gauge.name, gauge.help).unwrap();
+        writeln!(out, "# TYPE {} gauge", gauge.name).unwrap();
+        for entry in entries {
+            let book = &entry.logbook;
+            let labels = format!(
+                "callsign=\"{}\",squadron=\"{}\"",
+                escape(&book.callsign),
+                escape(&book.squadron)
+            );
+            for (kind, value) in (gauge.values)(book) {
+                match kind {
+                    Some(kind) => {
+                        writeln!(out, "{}{{{labels},kind=\"{kind}\"}} {value}", gauge.name)
+                    }
+                    None => writeln!(out, "{}{{{labels}}} {value}", gauge.name),
+                }
+                .unwrap();
+            }
+        }
+    }
+    out
+}
+
+/// Escape a label value as the exposition format wants.
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Listen on the given address, so a port that's taken fails right away.
+pub fn bind(addr: &str) -> Result<tiny_http::Server> {
+    let server =
+        tiny_http::Server::http(addr).map_err(|e| anyhow!("Couldn't listen on {addr}: {e}"))?;
+    info!("Serving metrics on http://{addr}/metrics");
+    Ok(server)
+}
+
+/// Serve `/metrics` until the server is unblocked,
+/// rendering whatever `entries` returns for each scrape.
+pub fn serve<F: Fn() -> Vec<Entry>>(server: &tiny_http::Server, entries: F) {
+    for request in server.incoming_requests() {
+        let response = if request.url().split('?').next() == Some("/metrics") {
+            Response::from_string(render(&entries()))
+                .with_header(Header::from_bytes("Content-Type", CONTENT_TYPE).unwrap())
+        } else {
+            Response::from_string("Nothing here; try /metrics").with_status_code(404)
+        };
+        if let Err(e) = request.respond(response) {
+            warn!("Couldn't respond to a request: {e}");
+        }
+    }
+}
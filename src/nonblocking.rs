@@ -0,0 +1,95 @@
+//! Reading and writing logbooks from async code without blocking the runtime
+//! (the `async` feature).
+//!
+//! Logbooks are small enough to parse inline once their bytes are in memory;
+//! it's the disk I/O, and batches of thousands of files, that need to stay off
+//! the runtime's threads.
+
+use anyhow::{Context, Result};
+use camino::Utf8Path;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::logbook::{Logbook, ParseOptions, WriteOptions, LOGBOOK_LEN};
+
+/// Read a logbook from `r`, as [`Logbook::parse_with`] does.
+pub async fn parse<R: AsyncRead + Unpin>(
+    mut r: R,
+    options: impl Into<ParseOptions>,
+) -> Result<Logbook> {
+    let mut buf = [0; LOGBOOK_LEN];
+    r.read_exact(&mut buf).await?;
+    Logbook::parse_bytes_with(&buf, options)
+}
+
+/// Write a logbook to `w`, as [`Logbook::write`] does.
+pub async fn write<W: AsyncWrite + Unpin>(
+    book: &Logbook,
+    mut w: W,
+    options: impl Into<WriteOptions>,
+) -> Result<()> {
+    let mut bytes = Vec::with_capacity(LOGBOOK_LEN);
+    book.write(&mut bytes, options)?;
+    w.write_all(&bytes).await?;
+    w.flush().await?;
+    Ok(())
+}
+
+/// Read the logbook at `path`.
+pub async fn read_file(path: &Utf8Path, options: impl Into<ParseOptions>) -> Result<Logbook> {
+    let bytes = tokio::fs::read(path)
+        .await
+        .with_context(|| format!("Couldn't open {path}"))?;
+    Logbook::parse_bytes_with(&bytes, options).with_context(|| format!("Couldn't parse {path}"))
+}
+
+/// Write the logbook to `path`, replacing whatever's there.
+pub async fn write_file(
+    book: &Logbook,
+    path: &Utf8Path,
+    options: impl Into<WriteOptions>,
+) -> Result<()> {
+    let mut bytes = Vec::with_capacity(LOGBOOK_LEN);
+    book.write(&mut bytes, options)?;
+    tokio::fs::write(path, bytes)
+        .await
+        .with_context(|| format!("Couldn't write {path}"))
+}
+
+/// Run blocking work, like reading a whole directory of logbooks,
+/// on tokio's blocking thread pool and wait for it.
+pub async fn blocking<T, F>(f: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .context("Blocking task panicked")?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logbook::Validation;
+
+    #[test]
+    fn round_trips() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let book = crate::LogbookBuilder::new("Jane Doe", "Viper")
+                .unwrap()
+                .build();
+            let mut bytes = Vec::new();
+            write(&book, &mut bytes, Validation::Strict).await.unwrap();
+            assert_eq!(bytes, book.to_bytes().unwrap());
+
+            let parsed = parse(&bytes[..], Validation::Strict).await.unwrap();
+            assert_eq!(parsed.callsign, "Viper");
+
+            let n = blocking(|| Ok(2 + 2)).await.unwrap();
+            assert_eq!(n, 4);
+        });
+    }
+}
@@ -0,0 +1,136 @@
+//! Posting pilot updates to Discord.
+
+use anyhow::{Context, Result};
+use log::*;
+
+use crate::logbook::Logbook;
+
+/// Flight hour milestones are announced every this many hours.
+const HOURS_MILESTONE: f32 = 100.0;
+
+/// A Discord webhook to post updates to
+#[derive(Debug)]
+pub struct Discord {
+    url: String,
+}
+
+impl Discord {
+    pub fn new(url: String) -> Self {
+        Self { url }
+    }
+
+    /// Post what changed in the pilot's logbook, if it's worth mentioning.
+    ///
+    /// With no `old` logbook to compare against, only announces the pilot.
+    pub fn changed(&self, old: Option<&Logbook>, new: &Logbook) -> Result<()> {
+        let lines = match old {
+            Some(old) => updates(old, new),
+            None => vec![format!("**{}** joined the squadron", new.callsign)],
+        };
+        if lines.is_empty() {
+            return Ok(());
+        }
+        self.post(&lines.join("\n"))
+    }
+
+    fn post(&self, content: &str) -> Result<()> {
+        let body = serde_json::json!({
+            "username": "bms-logcat",
+            "content": content,
+        });
+        ureq::post(&self.url)
+            .set("Content-Type", "application/json")
+            .send_string(&body.to_string())
+            .context("Couldn't post to Discord")?;
+        debug!("Posted to Discord: {content}");
+        Ok(())
+    }
+}
+
+/// Lines describing what happened between the two versions of a logbook
+pub fn updates(old: &Logbook, new: &Logbook) -> Vec<String> {
+    let mut lines = Vec::new();
+    let callsign = &new.callsign;
+
+    let (o, n) = (&old.campaign_stats, &new.campaign_stats);
+    // Widen before subtracting, since hand-edited counters can be far enough apart to overflow.
+    let delta = |new: i16, old: i16| i64::from(new) - i64::from(old);
+    let missions = delta(n.missions, o.missions);
+    if missions > 0 {
+        let mut results = Vec::new();
+        let mut count = |what: &str, delta: i64| {
+            if delta != 0 {
+                results.push(format!("{delta:+} {what}"));
+            }
+        };
+        count("air kills", delta(n.kills, o.kills));
+        count(
+            "ground kills",
+            delta(n.air_to_ground_kills, o.air_to_ground_kills),
+        );
+        count("static kills", delta(n.static_kills, o.static_kills));
+        count("naval kills", delta(n.naval_kills, o.naval_kills));
+        count("friendly kills", delta(n.friendly_kills, o.friendly_kills));
+        count("deaths", delta(n.killed, o.killed));
+        count(
+            "score",
+            i64::from(n.total_mission_score) - i64::from(o.total_mission_score),
+        );
+
+        let plural = if missions == 1 { "" } else { "s" };
+        let mut line = format!("**{callsign}** flew {missions} mission{plural}");
+        if !results.is_empty() {
+            line += &format!(": {}", results.join(", "));
+        }
+        lines.push(line);
+    }
+
+    let milestone = (new.flight_hours / HOURS_MILESTONE).floor();
+    if milestone > (old.flight_hours / HOURS_MILESTONE).floor() && milestone > 0.0 {
+        let hours = milestone * HOURS_MILESTONE;
+        lines.push(format!("**{callsign}** reached {hours} flight hours"));
+    }
+
+    if new.rank > old.rank {
+        lines.push(format!("**{callsign}** was promoted to {:?}", new.rank));
+    }
+
+    for medal in new.medals.difference(&old.medals) {
+        lines.push(format!("**{callsign}** was awarded the {medal:?}"));
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logbook::CampaignStats;
+
+    #[test]
+    fn counts_far_apart_counters() {
+        let old = Logbook {
+            campaign_stats: CampaignStats {
+                missions: i16::MIN,
+                kills: i16::MIN,
+                total_mission_score: i32::MIN,
+                ..CampaignStats::default()
+            },
+            ..Logbook::default()
+        };
+        let new = Logbook {
+            callsign: "Viper".into(),
+            campaign_stats: CampaignStats {
+                missions: i16::MAX,
+                kills: i16::MAX,
+                total_mission_score: i32::MAX,
+                ..CampaignStats::default()
+            },
+            ..Logbook::default()
+        };
+        assert_eq!(
+            updates(&old, &new),
+            ["**Viper** flew 65535 missions: +65535 air kills, +4294967295 score"]
+        );
+    }
+}
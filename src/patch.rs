@@ -0,0 +1,25 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+use crate::logbook::Logbook;
+
+/// Apply a JSON merge patch (RFC 7386) to the logbook.
+///
+/// Fields in the patch replace the logbook's; everything else is left alone.
+/// Objects (like `campaign_stats`) are merged field by field,
+/// but arrays (like `medals`) are replaced whole.
+pub fn merge(book: &Logbook, patch: &Value) -> Result<Logbook> {
+    let mut target = serde_json::to_value(book)?;
+    json_patch::merge(&mut target, patch);
+    serde_json::from_value(target).context("Patched logbook isn't valid")
+}
+
+/// Apply a JSON Patch (RFC 6902) to the logbook.
+///
+/// Paths are JSON pointers into the logbook's JSON form,
+/// e.g., `/campaign_stats/kills`. Nothing changes unless every operation succeeds.
+pub fn apply(book: &Logbook, patch: &json_patch::Patch) -> Result<Logbook> {
+    let mut target = serde_json::to_value(book)?;
+    json_patch::patch(&mut target, patch)?;
+    serde_json::from_value(target).context("Patched logbook isn't valid")
+}
@@ -0,0 +1,106 @@
+//! `rank-pilot`: where a pilot falls among the rest of a squadron,
+//! as a percentile for each stat `compare` shows.
+
+use std::io::prelude::*;
+
+use anyhow::Result;
+use camino::Utf8Path;
+use serde::Serialize;
+
+use crate::batch::Entry;
+use crate::compare::{Better, STATS};
+use crate::logbook::Logbook;
+use crate::table::{Format, Table};
+
+#[derive(Debug, Serialize)]
+pub struct Rank {
+    pub stat: &'static str,
+    pub value: Option<f64>,
+    /// How much of the squadron the pilot does better than, from 0 to 100,
+    /// counting ties as half. None without a value to compare.
+    pub percentile: Option<f64>,
+    /// How many others had a value to compare against
+    pub pilots: usize,
+    #[serde(skip)]
+    shown: String,
+}
+
+/// Rank the pilot against everyone else in `entries`,
+/// leaving out the pilot's own logbook if it's there too.
+pub fn rank(book: &Logbook, path: &Utf8Path, entries: &[Entry]) -> Vec<Rank> {
+    let own = path.canonicalize_utf8().ok();
+    let others: Vec<&Logbook> = entries
+        .iter()
+        .filter(|e| own.is_none() || e.path.canonicalize_utf8().ok() != own)
+        .map(|e| &e.logbook)
+        .collect();
+
+    STATS
+        .iter()
+        .map(|stat| {
+            let value = stat.of(book);
+            let theirs: Vec<f64> = others.iter().filter_map(|b| stat.of(b)).collect();
+            Rank {
+                stat: stat.name,
+                value,
+                percentile: value.and_then(|v| percentile(v, &theirs, stat.better)),
+                pilots: theirs.len(),
+                shown: stat.show(value),
+            }
+        })
+        .collect()
+}
+
+fn percentile(value: f64, others: &[f64], better: Better) -> Option<f64> {
+    if others.is_empty() {
+        return None;
+    }
+    let beaten = others
+        .iter()
+        .map(|&o| match (better, value.total_cmp(&o)) {
+            (_, std::cmp::Ordering::Equal) => 0.5,
+            (Better::Higher, std::cmp::Ordering::Greater)
+            | (Better::Lower, std::cmp::Ordering::Less) => 1.0,
+            _ => 0.0,
+        })
+        .sum::<f64>();
+    Some(beaten * 100.0 / others.len() as f64)
+}
+
+pub fn write<W: Write>(
+    w: &mut W,
+    callsign: &str,
+    ranks: &[Rank],
+    format: Format,
+    pretty: bool,
+) -> Result<()> {
+    if let Format::Json = format {
+        return crate::write_json(w, &ranks, pretty);
+    }
+
+    let mut t = Table::new(["Stat", "Value", "Percentile", "Pilots"]);
+    for r in ranks {
+        t.push(vec![
+            r.stat.to_owned(),
+            r.shown.clone(),
+            r.percentile
+                .map_or_else(|| "-".to_owned(), |p| format!("{p:.0}")),
+            r.pilots.to_string(),
+        ]);
+    }
+    t.write(w, format, &format!("{callsign} against the squadron"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_ties_as_half() {
+        let others = [1.0, 2.0, 3.0, 4.0];
+        assert_eq!(percentile(3.0, &others, Better::Higher), Some(62.5));
+        assert_eq!(percentile(3.0, &others, Better::Lower), Some(37.5));
+        assert_eq!(percentile(5.0, &others, Better::Higher), Some(100.0));
+        assert_eq!(percentile(5.0, &[], Better::Higher), None);
+    }
+}
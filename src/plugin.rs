@@ -0,0 +1,148 @@
+//! WebAssembly plugins that can inspect, change, or veto logbooks.
+//!
+//! A plugin is a WASM module exporting:
+//!
+//! - `memory`
+//! - `alloc(len: i32) -> i32`, which returns `len` bytes for the host to fill
+//! - `on_parse(ptr: i32, len: i32) -> i64`, `on_write(ptr: i32, len: i32) -> i64`,
+//!   or both
+//!
+//! Each hook gets the logbook as UTF-8 JSON (the same JSON `read` prints),
+//! and returns `0` to let it through unchanged or a pointer (high 32 bits)
+//! and length (low 32 bits) of a JSON response in its memory:
+//!
+//! - `{"logbook": {...}}` to replace the logbook with a modified one
+//! - `{"veto": "reason"}` to refuse it
+//!
+//! `on_parse` runs after reading a single logbook, `on_write` before writing one.
+//! Every call gets a fresh instance, so plugins can't keep state between them.
+
+use anyhow::{anyhow, bail, Context, Result};
+use camino::Utf8PathBuf;
+use log::*;
+use serde::Deserialize;
+use wasmi::{Engine, Extern, Linker, Module, Store};
+
+use crate::logbook::Logbook;
+
+#[derive(Debug, Copy, Clone)]
+pub enum Hook {
+    Parse,
+    Write,
+}
+
+impl Hook {
+    fn export(self) -> &'static str {
+        match self {
+            Hook::Parse => "on_parse",
+            Hook::Write => "on_write",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Response {
+    Logbook(Box<Logbook>),
+    Veto(String),
+}
+
+#[derive(Debug)]
+struct Plugin {
+    path: Utf8PathBuf,
+    module: Module,
+}
+
+/// Plugins to run, in the order they were given
+#[derive(Debug, Default)]
+pub struct Plugins {
+    engine: Engine,
+    plugins: Vec<Plugin>,
+}
+
+impl Plugins {
+    pub fn load(paths: &[Utf8PathBuf]) -> Result<Self> {
+        let engine = Engine::default();
+        let mut plugins = Vec::with_capacity(paths.len());
+
+        for path in paths {
+            let wasm = std::fs::read(path).with_context(|| format!("Couldn't read {path}"))?;
+            let module = Module::new(&engine, &wasm[..])
+                .map_err(|e| anyhow!("{e}"))
+                .with_context(|| format!("Couldn't load plugin {path}"))?;
+            plugins.push(Plugin {
+                path: path.clone(),
+                module,
+            });
+        }
+
+        Ok(Self { engine, plugins })
+    }
+
+    /// Pass the logbook through each plugin's hook, returning what comes out the other end.
+    pub fn run(&self, hook: Hook, mut book: Logbook) -> Result<Logbook> {
+        for plugin in &self.plugins {
+            book = self
+                .call(plugin, hook, book)
+                .with_context(|| format!("Plugin {} failed", plugin.path))?;
+        }
+        Ok(book)
+    }
+
+    fn call(&self, plugin: &Plugin, hook: Hook, book: Logbook) -> Result<Logbook> {
+        let mut store = Store::new(&self.engine, ());
+        let linker = Linker::<()>::new(&self.engine);
+        let instance = linker
+            .instantiate(&mut store, &plugin.module)
+            .and_then(|i| i.start(&mut store))
+            .map_err(|e| anyhow!("{e}"))?;
+
+        // Plugins only have to export the hooks they care about.
+        if !matches!(
+            instance.get_export(&store, hook.export()),
+            Some(Extern::Func(_))
+        ) {
+            return Ok(book);
+        }
+        let on_hook = instance
+            .get_typed_func::<(i32, i32), i64>(&store, hook.export())
+            .map_err(|e| anyhow!("{}: {e}", hook.export()))?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&store, "alloc")
+            .map_err(|e| anyhow!("alloc: {e}"))?;
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| anyhow!("No exported memory"))?;
+
+        let json = serde_json::to_vec(&book)?;
+        let len = i32::try_from(json.len())?;
+        let ptr = alloc
+            .call(&mut store, len)
+            .map_err(|e| anyhow!("alloc: {e}"))?;
+        memory
+            .write(&mut store, ptr as u32 as usize, &json)
+            .map_err(|e| anyhow!("Couldn't copy the logbook to the plugin: {e}"))?;
+
+        let ret = on_hook
+            .call(&mut store, (ptr, len))
+            .map_err(|e| anyhow!("{}: {e}", hook.export()))?;
+        if ret == 0 {
+            return Ok(book);
+        }
+
+        let start = (ret as u64 >> 32) as usize;
+        let end = start + (ret as u64 & 0xffff_ffff) as usize;
+        let response = memory
+            .data(&store)
+            .get(start..end)
+            .ok_or_else(|| anyhow!("Response is outside the plugin's memory"))?;
+
+        match serde_json::from_slice(response).context("Couldn't parse the plugin's response")? {
+            Response::Logbook(changed) => {
+                debug!("{} changed {} on {:?}", plugin.path, book.callsign, hook);
+                Ok(*changed)
+            }
+            Response::Veto(reason) => bail!("Vetoed {}: {reason}", book.callsign),
+        }
+    }
+}
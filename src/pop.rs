@@ -0,0 +1,96 @@
+//! Pilot options (`.pop`) files, which BMS keeps next to each logbook.
+//!
+//! The logbook's `options_file` names it, sans extension.
+//! Its layout (difficulty, avionics, and control settings) changes between BMS versions
+//! and isn't documented, so we carry its bytes around as hex instead of picking them apart.
+
+use std::io::prelude::*;
+
+use anyhow::{ensure, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use log::*;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::lock;
+use crate::logbook::Logbook;
+
+/// A pilot options file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PilotOptions {
+    /// File name, e.g., `Viper.pop`
+    pub file: Utf8PathBuf,
+    /// Its contents, as-is
+    #[serde(serialize_with = "to_hex", deserialize_with = "from_hex")]
+    pub data: Vec<u8>,
+}
+
+/// A logbook and its options, as `read --with-options` prints them
+/// and `write` takes them.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Profile {
+    pub logbook: Logbook,
+    pub options: Option<PilotOptions>,
+}
+
+/// Where the options for the logbook at `logbook_path` live
+pub fn path_for(logbook_path: &Utf8Path, book: &Logbook) -> Utf8PathBuf {
+    logbook_path.with_file_name(format!("{}.pop", book.options_file))
+}
+
+impl PilotOptions {
+    /// Read the options belonging to the logbook at `logbook_path`.
+    pub fn read(logbook_path: &Utf8Path, book: &Logbook) -> Result<Self> {
+        let path = path_for(logbook_path, book);
+        let mut data = Vec::new();
+        lock::open_shared(&path)?
+            .read_to_end(&mut data)
+            .with_context(|| format!("Couldn't read {path}"))?;
+        Ok(Self {
+            file: path.file_name().unwrap().into(),
+            data,
+        })
+    }
+
+    /// Write the options next to the logbook at `logbook_path`.
+    pub fn write(&self, logbook_path: &Utf8Path, book: &Logbook) -> Result<()> {
+        let path = path_for(logbook_path, book);
+        // Keep the logbook and its options pointing at each other.
+        ensure!(
+            path.file_name() == Some(self.file.as_str()),
+            "Options are for {}, but {}'s logbook uses {}",
+            self.file,
+            book.callsign,
+            path.file_name().unwrap()
+        );
+
+        let mut f = lock::create_exclusive(&path)?;
+        f.write_all(&self.data)
+            .with_context(|| format!("Couldn't write {path}"))?;
+        debug!("Wrote {path}");
+        Ok(())
+    }
+}
+
+fn to_hex<S: Serializer>(data: &[u8], s: S) -> Result<S::Ok, S::Error> {
+    let hex: String = data.iter().map(|b| format!("{b:02x}")).collect();
+    s.serialize_str(&hex)
+}
+
+fn from_hex<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+    let hex = String::deserialize(d)?;
+    decode_hex(&hex).map_err(serde::de::Error::custom)
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>> {
+    hex.as_bytes()
+        .chunks(2)
+        .enumerate()
+        .map(|(i, pair)| {
+            std::str::from_utf8(pair)
+                .ok()
+                .filter(|p| p.len() == 2)
+                .and_then(|p| u8::from_str_radix(p, 16).ok())
+                .with_context(|| format!("Invalid hex at offset {}", i * 2))
+        })
+        .collect()
+}
@@ -0,0 +1,133 @@
+//! `write-default --profile`: careers to start pilots from, e.g., to fill out
+//! a training campaign's roster or make test data.
+//!
+//! Each preset's rank is the one the default promotion rules give its hours,
+//! missions, and score, its medals are the ones `generate` would hand out,
+//! and its ace factor comes from its dogfight record, so `lint` finds nothing to flag.
+
+use crate::logbook::*;
+
+#[derive(clap::ValueEnum, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Preset {
+    /// Fresh out of training, a handful of missions in
+    Rookie,
+    /// A couple of campaigns behind them
+    Veteran,
+    /// Plenty of campaigns, and plenty of dogfights teaching others
+    Instructor,
+    /// The best record in the squadron
+    Ace,
+}
+
+impl Preset {
+    /// Fill in the pilot's career, leaving who they are (name, callsign, and so on) alone.
+    pub fn apply(self, book: &mut Logbook) {
+        let (rank, flight_hours) = match self {
+            Preset::Rookie => (Rank::SecondLt, 5.0),
+            Preset::Veteran => (Rank::Major, 300.0),
+            Preset::Instructor => (Rank::LtColonel, 650.0),
+            Preset::Ace => (Rank::Colonel, 900.0),
+        };
+        book.rank = rank;
+        book.flight_hours = flight_hours;
+        book.campaign_stats = self.campaign_stats();
+        book.dogfight_stats = self.dogfight_stats();
+        book.ace_factor = crate::ace::from_matches(
+            book.dogfight_stats.matches_won as i32,
+            book.dogfight_stats.matches_lost as i32,
+        );
+        book.medals = crate::generate::earned_medals(flight_hours, &book.campaign_stats);
+    }
+
+    fn campaign_stats(self) -> CampaignStats {
+        let (missions, total_mission_score, total_score, (games_won, game_lost, games_tied)) =
+            match self {
+                Preset::Rookie => (3, 150, 150, (0, 0, 0)),
+                Preset::Veteran => (150, 9_000, 10_000, (3, 1, 1)),
+                Preset::Instructor => (300, 21_000, 24_000, (6, 2, 2)),
+                Preset::Ace => (450, 36_000, 42_000, (10, 1, 1)),
+            };
+        let (kills, killed, human_kills, killed_versus_humans, self_kills) = match self {
+            Preset::Rookie => (0, 0, 0, 0, 0),
+            Preset::Veteran => (60, 5, 6, 1, 0),
+            Preset::Instructor => (120, 8, 20, 2, 1),
+            Preset::Ace => (250, 4, 40, 1, 0),
+        };
+        let (air_to_ground_kills, static_kills, naval_kills, friendly_kills) = match self {
+            Preset::Rookie => (1, 0, 0, 0),
+            Preset::Veteran => (180, 40, 3, 1),
+            Preset::Instructor => (400, 90, 8, 2),
+            Preset::Ace => (500, 120, 15, 0),
+        };
+        let (missions_since_last_friendly_kill, consecutive_missions) = match self {
+            Preset::Rookie => (missions, missions),
+            Preset::Veteran => (90, 40),
+            Preset::Instructor => (150, 80),
+            Preset::Ace => (missions, 200),
+        };
+        CampaignStats {
+            games_won,
+            game_lost,
+            games_tied,
+            missions,
+            total_score,
+            total_mission_score,
+            consecutive_missions,
+            kills,
+            killed,
+            human_kills,
+            killed_versus_humans,
+            self_kills,
+            air_to_ground_kills,
+            static_kills,
+            naval_kills,
+            friendly_kills,
+            missions_since_last_friendly_kill,
+        }
+    }
+
+    fn dogfight_stats(self) -> DogfightStats {
+        let (matches_won, matches_lost, matches_won_versus_humans, matches_lost_versus_humans) =
+            match self {
+                Preset::Rookie => (2, 3, 0, 1),
+                Preset::Veteran => (30, 20, 8, 5),
+                Preset::Instructor => (120, 40, 50, 20),
+                Preset::Ace => (150, 10, 60, 4),
+            };
+        let (kills, killed, human_kills, killed_versus_humans) = match self {
+            Preset::Rookie => (2, 4, 0, 1),
+            Preset::Veteran => (45, 25, 10, 5),
+            Preset::Instructor => (180, 50, 60, 20),
+            Preset::Ace => (220, 12, 80, 5),
+        };
+        DogfightStats {
+            matches_won,
+            matches_lost,
+            matches_won_versus_humans,
+            matches_lost_versus_humans,
+            kills,
+            killed,
+            human_kills,
+            killed_versus_humans,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lint::{check, Policy};
+    use crate::promotion::PromotionRules;
+    use clap::ValueEnum;
+
+    #[test]
+    fn presets_are_consistent() {
+        for preset in Preset::value_variants() {
+            let mut book = Logbook::default();
+            preset.apply(&mut book);
+            assert_eq!(PromotionRules::default().rank_for(&book), book.rank);
+            let findings = check("pilot.lbk".into(), &book, &Policy::default());
+            assert!(findings.is_empty(), "{preset:?}: {findings:?}");
+        }
+    }
+}
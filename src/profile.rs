@@ -0,0 +1,178 @@
+//! Profile bundles: a pilot's whole identity in one zip, to move between BMS installs.
+//!
+//! A bundle holds their logbook, options (`.pop`) and settings (`.ini`) files,
+//! and the picture and patch their logbook uses, along with a `manifest.json`
+//! saying which member is which.
+
+use std::io::{prelude::*, Cursor};
+
+use anyhow::{bail, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use log::*;
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+use time::{macros::format_description, OffsetDateTime};
+use zip::{write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::logbook::Logbook;
+use crate::resources::{self, ArtDirs, Lookup};
+use crate::{ini, pop, running, LogbookOptions};
+
+const MANIFEST: &str = "manifest.json";
+
+/// Bump when the bundle layout changes in a way older versions can't read.
+const VERSION: u32 = 1;
+
+/// What's in a bundle. Every field but `version` and `callsign` names a member of the zip.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    callsign: String,
+    /// When the bundle was made, as RFC 3339 UTC (older bundles don't say)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created: Option<String>,
+    logbook: String,
+    options: Option<String>,
+    settings: Option<String>,
+    picture: Option<String>,
+    patch: Option<String>,
+}
+
+/// Bundle up the logbook at `path` and everything that goes with it.
+pub fn export(path: &Utf8Path, book: &Logbook) -> Result<Vec<u8>> {
+    let dir = path.parent().unwrap_or_else(|| Utf8Path::new("."));
+    let art = ArtDirs::for_config_dir(dir);
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let zip_options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut add = |name: String, from: &Utf8Path| -> Result<String> {
+        let bytes = std::fs::read(from).with_context(|| format!("Couldn't read {from}"))?;
+        zip.start_file(&name, zip_options)?;
+        zip.write_all(&bytes)?;
+        debug!("Bundled {from} as {name}");
+        Ok(name)
+    };
+
+    let mut manifest = Manifest {
+        version: VERSION,
+        callsign: book.callsign.clone(),
+        created: Some(
+            OffsetDateTime::now_utc()
+                .replace_nanosecond(0)?
+                .format(&Rfc3339)?,
+        ),
+        logbook: add(file_name(path), path)?,
+        ..Manifest::default()
+    };
+
+    // Everything else is optional; pilots don't always have them.
+    let options = pop::path_for(path, book);
+    let settings = ini::path_for(dir, book);
+    for (slot, from) in [
+        (&mut manifest.options, options),
+        (&mut manifest.settings, settings),
+    ] {
+        if from.exists() {
+            *slot = Some(add(file_name(&from), &from)?);
+        } else {
+            debug!("No {from} to bundle");
+        }
+    }
+
+    for resource in resources::check(book, &art) {
+        let slot = match resource.kind {
+            "picture" => &mut manifest.picture,
+            _ => &mut manifest.patch,
+        };
+        match resource.lookup {
+            Lookup::Found(from) | Lookup::CaseMismatch(from) => {
+                let name = format!("{}/{}", resource.dir.file_name().unwrap(), file_name(&from));
+                *slot = Some(add(name, &from)?);
+            }
+            Lookup::Missing => warn!(
+                "Leaving out {} {}; it isn't in {}",
+                resource.kind, resource.file, resource.dir
+            ),
+        }
+    }
+
+    zip.start_file(MANIFEST, zip_options)?;
+    serde_json::to_writer_pretty(&mut zip, &manifest)?;
+    Ok(zip.finish()?.into_inner())
+}
+
+/// A name for a bundle of the given pilot made now, like `Viper-20240501T183000Z.zip`,
+/// so backups sort by when they were made
+pub fn timestamped_name(book: &Logbook) -> Result<String> {
+    let now = OffsetDateTime::now_utc().format(format_description!(
+        "[year][month][day]T[hour][minute][second]Z"
+    ))?;
+    Ok(format!("{}-{now}.zip", book.callsign))
+}
+
+/// Unpack a bundle into the given `User/Config` directory
+/// (and the art directories beside it).
+pub fn import(bundle: &[u8], dir: &Utf8Path, options: &LogbookOptions) -> Result<Logbook> {
+    let mut zip = ZipArchive::new(Cursor::new(bundle)).context("Not a zip file")?;
+    let manifest: Manifest = serde_json::from_reader(
+        zip.by_name(MANIFEST)
+            .context("Not a profile bundle; it has no manifest.json")?,
+    )
+    .context("Couldn't parse manifest.json")?;
+    if manifest.version > VERSION {
+        bail!(
+            "Bundle is version {}, but this version of bms-logcat only understands up to {VERSION}",
+            manifest.version
+        );
+    }
+
+    let mut read = |name: &str| -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        zip.by_name(name)
+            .with_context(|| format!("Bundle is missing {name}"))?
+            .read_to_end(&mut bytes)?;
+        Ok(bytes)
+    };
+
+    let book = Logbook::parse_with(&read(&manifest.logbook)?[..], options.parsing())
+        .context("Couldn't parse the bundled logbook")?;
+    crate::write_logbook(&book, &dir.join(safe_name(&manifest.logbook)?), options)?;
+
+    let art = ArtDirs::for_config_dir(dir);
+    let others = [
+        (&manifest.options, dir),
+        (&manifest.settings, dir),
+        (&manifest.picture, art.pictures.as_path()),
+        (&manifest.patch, art.patches.as_path()),
+    ];
+    for (name, to_dir) in others {
+        let name = match name {
+            Some(n) => n,
+            None => continue,
+        };
+        let to = to_dir.join(safe_name(name)?);
+        let bytes = read(name)?;
+        if options.dry_run {
+            writeln!(std::io::stdout().lock(), "Would write {to}")?;
+            continue;
+        }
+        running::guard(&to, options.when_running)?;
+        std::fs::create_dir_all(to_dir).with_context(|| format!("Couldn't create {to_dir}"))?;
+        std::fs::write(&to, bytes).with_context(|| format!("Couldn't write {to}"))?;
+        debug!("Wrote {to}");
+    }
+
+    Ok(book)
+}
+
+fn file_name(path: &Utf8Path) -> String {
+    path.file_name().unwrap_or_default().to_owned()
+}
+
+/// Just the file name of a member, so a bundle can't write outside where it's put.
+fn safe_name(member: &str) -> Result<Utf8PathBuf> {
+    match Utf8Path::new(member).file_name() {
+        Some(name) if name != ".." => Ok(name.into()),
+        _ => bail!("Bundle has a strange member name: {member}"),
+    }
+}
@@ -0,0 +1,29 @@
+//! Progress bars for commands that chew through whole directories.
+//!
+//! They go to stderr, and only when it's a terminal (and we weren't told `--quiet`),
+//! so they never end up in redirected output or logs.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+pub fn quiet() {
+    QUIET.store(true, Ordering::Relaxed);
+}
+
+/// A bar counting up to `len` things, or a hidden one that ignores updates.
+pub fn bar(len: usize, what: &str) -> ProgressBar {
+    if QUIET.load(Ordering::Relaxed) || !atty::is(atty::Stream::Stderr) {
+        return ProgressBar::hidden();
+    }
+    let bar = ProgressBar::with_draw_target(Some(len as u64), ProgressDrawTarget::stderr());
+    bar.set_style(
+        ProgressStyle::with_template("{prefix} [{bar:30}] {pos}/{len} {wide_msg}")
+            .unwrap()
+            .progress_chars("=> "),
+    );
+    bar.set_prefix(what.to_owned());
+    bar
+}
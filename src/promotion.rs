@@ -0,0 +1,90 @@
+use anyhow::{ensure, Context, Result};
+use camino::Utf8Path;
+use serde::{Deserialize, Serialize};
+
+use crate::logbook::{Logbook, Rank};
+
+/// The career a pilot needs before they can hold a rank.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RankThreshold {
+    pub rank: Rank,
+    #[serde(default)]
+    pub flight_hours: f32,
+    #[serde(default)]
+    pub missions: i16,
+    #[serde(default)]
+    pub score: i32,
+}
+
+/// Thresholds for each rank, loaded from JSON or taken from the defaults.
+///
+/// The defaults loosely follow BMS, where promotion comes with
+/// time in the cockpit and campaign performance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromotionRules {
+    pub thresholds: Vec<RankThreshold>,
+}
+
+impl Default for PromotionRules {
+    fn default() -> Self {
+        let t = |rank, flight_hours, missions, score| RankThreshold {
+            rank,
+            flight_hours,
+            missions,
+            score,
+        };
+        Self {
+            thresholds: vec![
+                t(Rank::Leiutenant, 20.0, 10, 500),
+                t(Rank::Captain, 100.0, 50, 2500),
+                t(Rank::Major, 250.0, 120, 7500),
+                t(Rank::LtColonel, 500.0, 250, 15000),
+                t(Rank::Colonel, 800.0, 400, 30000),
+                t(Rank::BrigadierGeneral, 1200.0, 600, 50000),
+            ],
+        }
+    }
+}
+
+impl PromotionRules {
+    /// Load rules from a JSON file shaped like `{"thresholds": [{"rank": "Captain", ...}]}`
+    pub fn load(path: &Utf8Path) -> Result<Self> {
+        let json =
+            std::fs::read_to_string(path).with_context(|| format!("Couldn't read {path}"))?;
+        let rules: Self =
+            serde_json::from_str(&json).with_context(|| format!("Couldn't parse {path}"))?;
+        ensure!(
+            !rules.thresholds.is_empty(),
+            "{path} doesn't define any rank thresholds"
+        );
+        Ok(rules)
+    }
+
+    /// The highest rank whose thresholds the logbook meets.
+    pub fn rank_for(&self, book: &Logbook) -> Rank {
+        let stats = &book.campaign_stats;
+        self.thresholds
+            .iter()
+            .filter(|t| {
+                book.flight_hours >= t.flight_hours
+                    && stats.missions >= t.missions
+                    && stats.total_score >= t.score
+            })
+            .map(|t| t.rank)
+            .max()
+            .unwrap_or(Rank::SecondLt)
+    }
+
+    /// Promote the logbook to the rank it has earned.
+    ///
+    /// Like BMS, this never demotes a pilot.
+    pub fn apply(&self, book: &mut Logbook) -> Option<Rank> {
+        let earned = self.rank_for(book);
+        if earned > book.rank {
+            book.rank = earned;
+            Some(earned)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,227 @@
+//! A `bms_logcat` Python module (the `python` feature), so stat scripts can read
+//! logbooks directly instead of running the CLI and parsing its JSON.
+//!
+//! ```python
+//! import bms_logcat
+//!
+//! book = bms_logcat.Logbook.read("Viper.lbk")
+//! print(book.callsign, book.campaign_stats.kills)
+//! ```
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use crate::logbook::{CampaignStats, DogfightStats, Logbook, Medals, Rank, Validation};
+
+fn err(e: anyhow::Error) -> PyErr {
+    PyValueError::new_err(format!("{e:#}"))
+}
+
+/// Rank and medal names, as they're spelled in JSON
+fn from_name<T: serde::de::DeserializeOwned>(kind: &str, name: &str) -> PyResult<T> {
+    serde_json::from_value(name.into())
+        .map_err(|_| PyValueError::new_err(format!("{name} isn't a {kind}")))
+}
+
+#[pyclass(name = "Logbook", module = "bms_logcat")]
+#[derive(Clone)]
+struct PyLogbook(Logbook);
+
+#[pymethods]
+impl PyLogbook {
+    /// Parse a logbook from the bytes of a `.lbk` file.
+    #[staticmethod]
+    fn parse(bytes: &[u8]) -> PyResult<Self> {
+        Logbook::parse_bytes(bytes).map(Self).map_err(err)
+    }
+
+    /// Read the `.lbk` file at `path`.
+    #[staticmethod]
+    fn read(path: &str) -> PyResult<Self> {
+        let bytes = std::fs::read(path)?;
+        Self::parse(&bytes)
+    }
+
+    /// Make a logbook from JSON shaped like `bms-logcat read` output.
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        serde_json::from_str(json)
+            .map(Self)
+            .map_err(|e| err(e.into()))
+    }
+
+    /// The logbook as BMS keeps it on disk
+    fn to_bytes<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let bytes = self.0.to_bytes().map_err(err)?;
+        Ok(PyBytes::new(py, &bytes))
+    }
+
+    /// Write the logbook to `path`, replacing whatever's there.
+    fn write(&self, path: &str) -> PyResult<()> {
+        let bytes = self.0.to_bytes().map_err(err)?;
+        std::fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(&self.0).map_err(|e| err(e.into()))
+    }
+
+    /// The logbook as a dict, in the same shape as its JSON
+    fn to_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let json = self.to_json()?;
+        let loads = py.import("json")?.getattr("loads")?;
+        Ok(loads.call1((json,))?.unbind())
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<Logbook {} ({})>", self.0.callsign, self.0.name)
+    }
+
+    #[getter]
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    #[setter]
+    fn set_name(&mut self, name: String) {
+        self.0.name = name;
+    }
+
+    #[getter]
+    fn callsign(&self) -> &str {
+        &self.0.callsign
+    }
+
+    #[setter]
+    fn set_callsign(&mut self, callsign: String) {
+        self.0.callsign = callsign;
+    }
+
+    #[getter]
+    fn commissioned(&self) -> &str {
+        &self.0.commissioned
+    }
+
+    #[setter]
+    fn set_commissioned(&mut self, date: String) {
+        self.0.commissioned = date;
+    }
+
+    #[getter]
+    fn squadron(&self) -> &str {
+        &self.0.squadron
+    }
+
+    #[setter]
+    fn set_squadron(&mut self, squadron: String) {
+        self.0.squadron = squadron;
+    }
+
+    #[getter]
+    fn personal_text(&self) -> &str {
+        &self.0.personal_text
+    }
+
+    #[setter]
+    fn set_personal_text(&mut self, text: String) {
+        self.0.personal_text = text;
+    }
+
+    #[getter]
+    fn flight_hours(&self) -> f32 {
+        self.0.flight_hours
+    }
+
+    #[setter]
+    fn set_flight_hours(&mut self, hours: f32) {
+        self.0.flight_hours = hours;
+    }
+
+    #[getter]
+    fn ace_factor(&self) -> f32 {
+        self.0.ace_factor
+    }
+
+    #[setter]
+    fn set_ace_factor(&mut self, ace_factor: f32) {
+        self.0.ace_factor = ace_factor;
+    }
+
+    #[getter]
+    fn voice(&self) -> i16 {
+        self.0.voice
+    }
+
+    #[setter]
+    fn set_voice(&mut self, voice: i16) {
+        self.0.voice = voice;
+    }
+
+    /// The rank's name, like `"Captain"`
+    #[getter]
+    fn rank(&self) -> String {
+        format!("{:?}", self.0.rank)
+    }
+
+    #[setter]
+    fn set_rank(&mut self, rank: &str) -> PyResult<()> {
+        self.0.rank = from_name::<Rank>("rank", rank)?;
+        Ok(())
+    }
+
+    /// The medals' names, like `["AirMedal"]`
+    #[getter]
+    fn medals(&self) -> Vec<String> {
+        self.0.medals.iter().map(|m| format!("{m:?}")).collect()
+    }
+
+    #[setter]
+    fn set_medals(&mut self, medals: Vec<String>) -> PyResult<()> {
+        self.0.medals = medals
+            .iter()
+            .map(|m| from_name::<Medals>("medal", m))
+            .collect::<PyResult<_>>()?;
+        Ok(())
+    }
+
+    /// A copy of the dogfight stats; assign it back after changing it.
+    #[getter]
+    fn dogfight_stats(&self) -> DogfightStats {
+        self.0.dogfight_stats.clone()
+    }
+
+    #[setter]
+    fn set_dogfight_stats(&mut self, stats: DogfightStats) {
+        self.0.dogfight_stats = stats;
+    }
+
+    /// A copy of the campaign stats; assign it back after changing it.
+    #[getter]
+    fn campaign_stats(&self) -> CampaignStats {
+        self.0.campaign_stats.clone()
+    }
+
+    #[setter]
+    fn set_campaign_stats(&mut self, stats: CampaignStats) {
+        self.0.campaign_stats = stats;
+    }
+}
+
+/// Check the logbook would be written, without writing it, raising `ValueError` if not.
+#[pyfunction]
+fn validate(book: &PyLogbook) -> PyResult<()> {
+    book.0
+        .write(std::io::sink(), Validation::Strict)
+        .map_err(err)
+}
+
+#[pymodule]
+fn bms_logcat(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyLogbook>()?;
+    m.add_class::<DogfightStats>()?;
+    m.add_class::<CampaignStats>()?;
+    m.add_function(wrap_pyfunction!(validate, m)?)?;
+    Ok(())
+}
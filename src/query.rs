@@ -0,0 +1,464 @@
+//! A small subset of jq for shaping `read` output.
+//!
+//! Supported:
+//! - `.`, `.foo`, `.foo.bar`, `."foo"`, `.[2]`, `.[]`
+//! - pipes (`a | b`) and commas (`a, b`)
+//! - object construction (`{kills, hours: .flight_hours}`)
+//! - array construction (`[.medals[]]`)
+//! - literals (`1`, `"text"`, `true`, `false`, `null`), parentheses
+//! - `length` and `keys`
+
+use anyhow::{anyhow, bail, ensure, Result};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Identity,
+    Field(String),
+    Index(i64),
+    Iterate,
+    Pipe(Box<Expr>, Box<Expr>),
+    Comma(Box<Expr>, Box<Expr>),
+    Object(Vec<(String, Expr)>),
+    Array(Option<Box<Expr>>),
+    Literal(Value),
+    Length,
+    Keys,
+}
+
+impl Expr {
+    pub fn parse(query: &str) -> Result<Self> {
+        let tokens = tokenize(query)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.pipe()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(t) => bail!("Unexpected {t:?} in query"),
+        }
+    }
+
+    /// Run the query, returning every value it produces.
+    pub fn eval(&self, input: &Value) -> Result<Vec<Value>> {
+        match self {
+            Expr::Identity => Ok(vec![input.clone()]),
+            Expr::Field(name) => match input {
+                Value::Object(o) => Ok(vec![o.get(name).cloned().unwrap_or(Value::Null)]),
+                Value::Null => Ok(vec![Value::Null]),
+                other => bail!("Cannot index {} with \"{name}\"", type_name(other)),
+            },
+            Expr::Index(i) => match input {
+                Value::Array(a) => {
+                    // Negative indexes count from the end, like jq.
+                    let i = if *i < 0 { a.len() as i64 + i } else { *i };
+                    let v = usize::try_from(i).ok().and_then(|i| a.get(i));
+                    Ok(vec![v.cloned().unwrap_or(Value::Null)])
+                }
+                Value::Null => Ok(vec![Value::Null]),
+                other => bail!("Cannot index {} with a number", type_name(other)),
+            },
+            Expr::Iterate => match input {
+                Value::Array(a) => Ok(a.clone()),
+                Value::Object(o) => Ok(o.values().cloned().collect()),
+                other => bail!("Cannot iterate over {}", type_name(other)),
+            },
+            Expr::Pipe(lhs, rhs) => {
+                let mut out = Vec::new();
+                for v in lhs.eval(input)? {
+                    out.extend(rhs.eval(&v)?);
+                }
+                Ok(out)
+            }
+            Expr::Comma(lhs, rhs) => {
+                let mut out = lhs.eval(input)?;
+                out.extend(rhs.eval(input)?);
+                Ok(out)
+            }
+            Expr::Object(entries) => {
+                // Each entry can produce several values; build every combination.
+                let mut objects = vec![Map::new()];
+                for (key, expr) in entries {
+                    let values = expr.eval(input)?;
+                    let mut next = Vec::with_capacity(objects.len() * values.len());
+                    for o in &objects {
+                        for v in &values {
+                            let mut o = o.clone();
+                            o.insert(key.clone(), v.clone());
+                            next.push(o);
+                        }
+                    }
+                    objects = next;
+                }
+                Ok(objects.into_iter().map(Value::Object).collect())
+            }
+            Expr::Array(None) => Ok(vec![Value::Array(Vec::new())]),
+            Expr::Array(Some(e)) => Ok(vec![Value::Array(e.eval(input)?)]),
+            Expr::Literal(v) => Ok(vec![v.clone()]),
+            Expr::Length => {
+                let len = match input {
+                    Value::Null => 0.into(),
+                    Value::Bool(_) => bail!("boolean has no length"),
+                    Value::Number(n) => number(n.as_f64().unwrap_or_default().abs()),
+                    Value::String(s) => s.chars().count().into(),
+                    Value::Array(a) => a.len().into(),
+                    Value::Object(o) => o.len().into(),
+                };
+                Ok(vec![len])
+            }
+            Expr::Keys => match input {
+                Value::Object(o) => {
+                    let mut keys: Vec<&String> = o.keys().collect();
+                    keys.sort();
+                    Ok(vec![keys.into_iter().cloned().map(Value::String).collect()])
+                }
+                Value::Array(a) => Ok(vec![(0..a.len()).map(Value::from).collect()]),
+                other => bail!("{} has no keys", type_name(other)),
+            },
+        }
+    }
+}
+
+/// Keep only the given dotted fields (e.g., `campaign_stats.kills`) of `value`,
+/// nested the same way they were.
+pub fn select(value: &Value, fields: &[String]) -> Result<Value> {
+    let mut selected = Map::new();
+
+    for field in fields {
+        let mut from = value;
+        let mut to = &mut selected;
+        let mut parts = field.split('.').peekable();
+        while let Some(part) = parts.next() {
+            from = from
+                .get(part)
+                .ok_or_else(|| anyhow!("No field named {field}"))?;
+            if parts.peek().is_none() {
+                to.insert(part.to_owned(), from.clone());
+            } else {
+                to = to
+                    .entry(part)
+                    .or_insert_with(|| Value::Object(Map::new()))
+                    .as_object_mut()
+                    .ok_or_else(|| anyhow!("No field named {field}"))?;
+            }
+        }
+    }
+    Ok(Value::Object(selected))
+}
+
+/// Keep whole numbers integers so `1` doesn't come back as `1.0`.
+fn number(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+        Value::from(n as i64)
+    } else {
+        serde_json::Number::from_f64(n).map_or(Value::Null, Value::Number)
+    }
+}
+
+fn type_name(v: &Value) -> &'static str {
+    match v {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Dot,
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Pipe,
+    Comma,
+    Colon,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    LParen,
+    RParen,
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = query.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '.' => Token::Dot,
+            '|' => Token::Pipe,
+            ',' => Token::Comma,
+            ':' => Token::Colon,
+            '{' => Token::LBrace,
+            '}' => Token::RBrace,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '"' => {
+                let mut end = None;
+                let mut escaped = false;
+                for (i, c) in chars.by_ref() {
+                    match c {
+                        '\\' if !escaped => escaped = true,
+                        '"' if !escaped => {
+                            end = Some(i);
+                            break;
+                        }
+                        _ => escaped = false,
+                    }
+                }
+                let end = end.ok_or_else(|| anyhow!("Unterminated string in query"))?;
+                Token::Str(serde_json::from_str(&query[start..=end])?)
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let mut end = start + c.len_utf8();
+                while let Some(&(i, c)) = chars.peek() {
+                    if !(c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E') {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                let n = &query[start..end];
+                Token::Num(n.parse().map_err(|_| anyhow!("Bad number {n} in query"))?)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut end = start + c.len_utf8();
+                while let Some(&(i, c)) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                Token::Ident(query[start..end].to_owned())
+            }
+            other => bail!("Unexpected '{other}' in query"),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    fn eat(&mut self, t: &Token) -> bool {
+        if self.peek() == Some(t) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, t: Token) -> Result<()> {
+        match self.next() {
+            Some(n) if n == t => Ok(()),
+            Some(n) => bail!("Expected {t:?} in query, found {n:?}"),
+            None => bail!("Expected {t:?}, but the query ended"),
+        }
+    }
+
+    /// `a | b`, which binds loosest
+    fn pipe(&mut self) -> Result<Expr> {
+        let mut lhs = self.comma()?;
+        while self.eat(&Token::Pipe) {
+            let rhs = self.comma()?;
+            lhs = Expr::Pipe(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    /// `a, b`
+    fn comma(&mut self) -> Result<Expr> {
+        let mut lhs = self.postfix()?;
+        while self.eat(&Token::Comma) {
+            let rhs = self.postfix()?;
+            lhs = Expr::Comma(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    /// A term followed by any number of `.foo` or `[...]` suffixes
+    fn postfix(&mut self) -> Result<Expr> {
+        let mut expr = self.term()?;
+        loop {
+            let suffix = match self.peek() {
+                Some(Token::Dot) => {
+                    self.pos += 1;
+                    self.after_dot()?
+                }
+                Some(Token::LBracket) => {
+                    self.pos += 1;
+                    self.bracket()?
+                }
+                _ => break,
+            };
+            expr = Expr::Pipe(Box::new(expr), Box::new(suffix));
+        }
+        Ok(expr)
+    }
+
+    fn term(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::Dot) => match self.peek() {
+                Some(Token::Ident(_) | Token::Str(_) | Token::LBracket) => self.after_dot(),
+                _ => Ok(Expr::Identity),
+            },
+            Some(Token::LBrace) => self.object(),
+            Some(Token::LBracket) => {
+                if self.eat(&Token::RBracket) {
+                    return Ok(Expr::Array(None));
+                }
+                let inner = self.pipe()?;
+                self.expect(Token::RBracket)?;
+                Ok(Expr::Array(Some(Box::new(inner))))
+            }
+            Some(Token::LParen) => {
+                let inner = self.pipe()?;
+                self.expect(Token::RParen)?;
+                Ok(inner)
+            }
+            Some(Token::Str(s)) => Ok(Expr::Literal(Value::String(s))),
+            Some(Token::Num(n)) => Ok(Expr::Literal(number(n))),
+            Some(Token::Ident(i)) => match i.as_str() {
+                "null" => Ok(Expr::Literal(Value::Null)),
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "length" => Ok(Expr::Length),
+                "keys" => Ok(Expr::Keys),
+                other => bail!("Unknown function {other} in query"),
+            },
+            Some(t) => bail!("Unexpected {t:?} in query"),
+            None => bail!("Query ended unexpectedly"),
+        }
+    }
+
+    /// What follows a `.`: a field name, a quoted field name, or brackets
+    fn after_dot(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::Ident(i)) => Ok(Expr::Field(i)),
+            Some(Token::Str(s)) => Ok(Expr::Field(s)),
+            Some(Token::LBracket) => self.bracket(),
+            Some(t) => bail!("Expected a field name after '.', found {t:?}"),
+            None => bail!("Expected a field name after '.'"),
+        }
+    }
+
+    /// `[]`, `[2]`, or `["foo"]`, after the opening bracket
+    fn bracket(&mut self) -> Result<Expr> {
+        let expr = match self.next() {
+            Some(Token::RBracket) => return Ok(Expr::Iterate),
+            Some(Token::Num(n)) => {
+                ensure!(n.fract() == 0.0, "Array index {n} isn't a whole number");
+                Expr::Index(n as i64)
+            }
+            Some(Token::Str(s)) => Expr::Field(s),
+            Some(t) => bail!("Expected an index or field name in brackets, found {t:?}"),
+            None => bail!("Query ended inside brackets"),
+        };
+        self.expect(Token::RBracket)?;
+        Ok(expr)
+    }
+
+    /// `{a, "b", c: .foo}`, after the opening brace
+    fn object(&mut self) -> Result<Expr> {
+        let mut entries = Vec::new();
+        if self.eat(&Token::RBrace) {
+            return Ok(Expr::Object(entries));
+        }
+        loop {
+            let key = match self.next() {
+                Some(Token::Ident(i)) => i,
+                Some(Token::Str(s)) => s,
+                Some(t) => bail!("Expected an object key, found {t:?}"),
+                None => bail!("Query ended inside an object"),
+            };
+            let value = if self.eat(&Token::Colon) {
+                self.postfix()?
+            } else {
+                Expr::Field(key.clone())
+            };
+            entries.push((key, value));
+
+            if self.eat(&Token::RBrace) {
+                return Ok(Expr::Object(entries));
+            }
+            self.expect(Token::Comma)?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn run(query: &str, input: &Value) -> Vec<Value> {
+        Expr::parse(query).unwrap().eval(input).unwrap()
+    }
+
+    #[test]
+    fn follows_paths() {
+        let book = json!({
+            "callsign": "Viper",
+            "campaign_stats": {"kills": 12},
+            "medals": ["AirMedal", "SilverStar", "Longevity"],
+        });
+        assert_eq!(run(".", &book), vec![book.clone()]);
+        assert_eq!(run(".callsign", &book), [json!("Viper")]);
+        assert_eq!(run(".campaign_stats.kills", &book), [json!(12)]);
+        assert_eq!(run(r#"."campaign_stats"["kills"]"#, &book), [json!(12)]);
+        assert_eq!(run(".missing", &book), [Value::Null]);
+        assert_eq!(run(".medals[1]", &book), [json!("SilverStar")]);
+        assert_eq!(run(".medals[-1]", &book), [json!("Longevity")]);
+        assert_eq!(run(".medals[7]", &book), [Value::Null]);
+        assert_eq!(
+            run(".medals[]", &book),
+            book["medals"].as_array().unwrap()[..]
+        );
+        assert_eq!(
+            run(".campaign_stats | .kills, (.kills | length)", &book),
+            [json!(12), json!(12)]
+        );
+        assert_eq!(run("[.medals[] | length] | length", &book), [json!(3)]);
+        assert_eq!(
+            run("{callsign, kills: .campaign_stats.kills}", &book),
+            [json!({"callsign": "Viper", "kills": 12})]
+        );
+        assert!(Expr::parse(".callsign[0]").unwrap().eval(&book).is_err());
+    }
+
+    #[test]
+    fn refuses_bad_queries() {
+        for query in [
+            ".[",
+            ".foo |",
+            "{a:",
+            "frobnicate",
+            ".[1.5]",
+            "\"open",
+            ". .",
+        ] {
+            assert!(Expr::parse(query).is_err(), "{query}");
+        }
+    }
+}
@@ -0,0 +1,196 @@
+//! `--redact-profile`: named sets of personal fields to blank out of what
+//! `read`, the directory-wide reports, and `serve` show, e.g., before posting
+//! a squadron's stats publicly.
+//!
+//! `public` and `league` are built in; the config file can change them or add more:
+//!
+//! ```toml
+//! [redact-profiles.public]
+//! fields = ["password", "name", "personal_text"]
+//!
+//! [redact-profiles.tournament]
+//! fields = ["password", "squadron"]
+//! ```
+//!
+//! Redacted fields are blanked rather than removed, so the output is still a
+//! logbook `write` takes. Callsigns can't be redacted, since they're how pilots
+//! are told apart.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{anyhow, Result};
+use camino::Utf8PathBuf;
+use serde::Deserialize;
+
+use crate::batch::Entry;
+use crate::logbook::Logbook;
+
+/// A field that can be redacted, spelled as it is in (snake case) JSON
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Field {
+    Name,
+    Password,
+    Commissioned,
+    OptionsFile,
+    PictureFile,
+    PatchFile,
+    PersonalText,
+    Squadron,
+}
+
+impl Field {
+    fn name(self) -> &'static str {
+        match self {
+            Field::Name => "name",
+            Field::Password => "password",
+            Field::Commissioned => "commissioned",
+            Field::OptionsFile => "options_file",
+            Field::PictureFile => "picture_file",
+            Field::PatchFile => "patch_file",
+            Field::PersonalText => "personal_text",
+            Field::Squadron => "squadron",
+        }
+    }
+
+    fn is_blank(self, book: &Logbook) -> bool {
+        match self {
+            Field::Name => book.name.is_empty(),
+            Field::Password => book.password.is_empty(),
+            Field::Commissioned => book.commissioned.is_empty(),
+            Field::OptionsFile => book.options_file.as_str().is_empty(),
+            Field::PictureFile => book.picture_file.as_str().is_empty(),
+            Field::PatchFile => book.patch_file.as_str().is_empty(),
+            Field::PersonalText => book.personal_text.is_empty(),
+            Field::Squadron => book.squadron.is_empty(),
+        }
+    }
+
+    fn copy(self, to: &mut Logbook, from: &Logbook) {
+        match self {
+            Field::Name => to.name.clone_from(&from.name),
+            Field::Password => to.password.clone_from(&from.password),
+            Field::Commissioned => to.commissioned.clone_from(&from.commissioned),
+            Field::OptionsFile => to.options_file.clone_from(&from.options_file),
+            Field::PictureFile => to.picture_file.clone_from(&from.picture_file),
+            Field::PatchFile => to.patch_file.clone_from(&from.patch_file),
+            Field::PersonalText => to.personal_text.clone_from(&from.personal_text),
+            Field::Squadron => to.squadron.clone_from(&from.squadron),
+        }
+    }
+
+    fn blank(self, book: &mut Logbook) {
+        match self {
+            Field::Name => book.name.clear(),
+            Field::Password => book.password.clear(),
+            Field::Commissioned => book.commissioned.clear(),
+            Field::OptionsFile => book.options_file = Utf8PathBuf::new(),
+            Field::PictureFile => book.picture_file = Utf8PathBuf::new(),
+            Field::PatchFile => book.patch_file = Utf8PathBuf::new(),
+            Field::PersonalText => book.personal_text.clear(),
+            Field::Squadron => book.squadron.clear(),
+        }
+    }
+}
+
+/// Which fields to blank
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub fields: BTreeSet<Field>,
+}
+
+impl Profile {
+    /// Look up a profile by name, in the config's profiles and then the built-in ones.
+    pub fn named(name: &str, configured: &BTreeMap<String, Profile>) -> Result<Self> {
+        if let Some(profile) = configured.get(name) {
+            return Ok(profile.clone());
+        }
+        let fields: &[Field] = match name {
+            "public" => &[Field::Password, Field::Name, Field::PersonalText],
+            "league" => &[Field::Password],
+            _ => {
+                let mut known: BTreeSet<&str> = configured.keys().map(String::as_str).collect();
+                known.extend(["public", "league"]);
+                let known: Vec<_> = known.into_iter().collect();
+                return Err(anyhow!(
+                    "No redaction profile called {name} (known ones: {})",
+                    known.join(", ")
+                ));
+            }
+        };
+        Ok(Self {
+            fields: fields.iter().copied().collect(),
+        })
+    }
+
+    /// The same profile, also blanking `field`
+    pub fn with(mut self, field: Field) -> Self {
+        self.fields.insert(field);
+        self
+    }
+
+    /// Does this profile blank the field at the given dotted path?
+    pub fn covers(&self, path: &str) -> bool {
+        self.fields.iter().any(|f| f.name() == path)
+    }
+
+    pub fn apply(&self, mut book: Logbook) -> Logbook {
+        for field in &self.fields {
+            field.blank(&mut book);
+        }
+        book
+    }
+
+    pub fn apply_entries(&self, entries: Vec<Entry>) -> Vec<Entry> {
+        entries
+            .into_iter()
+            .map(|e| Entry {
+                logbook: self.apply(e.logbook),
+                ..e
+            })
+            .collect()
+    }
+
+    /// Fill in redacted fields left blank in an edited logbook from the one it replaces,
+    /// since whoever edited it never saw them.
+    pub fn restore(&self, book: &mut Logbook, existing: &Logbook) {
+        for field in &self.fields {
+            if field.is_blank(book) {
+                field.copy(book, existing);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blanks_and_restores() {
+        let configured = toml::from_str::<BTreeMap<String, Profile>>(
+            "tournament = { fields = [\"squadron\", \"personal_text\"] }",
+        )
+        .unwrap();
+        assert!(Profile::named("nope", &configured).is_err());
+        let public = Profile::named("public", &configured).unwrap();
+        assert!(public.covers("name") && !public.covers("squadron"));
+
+        let book = Logbook {
+            name: "Jane Doe".to_owned(),
+            squadron: "80th FS".to_owned(),
+            personal_text: "Hi".to_owned(),
+            ..Logbook::default()
+        };
+        let tournament = Profile::named("tournament", &configured).unwrap();
+        let mut shown = tournament.apply(book.clone());
+        assert_eq!(shown.name, "Jane Doe");
+        assert!(shown.squadron.is_empty() && shown.personal_text.is_empty());
+
+        shown.personal_text = "Hello".to_owned();
+        tournament.restore(&mut shown, &book);
+        assert_eq!(shown.squadron, "80th FS");
+        assert_eq!(shown.personal_text, "Hello");
+    }
+}
@@ -0,0 +1,239 @@
+use std::io::prelude::*;
+
+use anyhow::{Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use image::imageops::FilterType;
+use log::*;
+use serde::Serialize;
+
+use crate::logbook::Logbook;
+use crate::table::{Format, Table};
+
+/// Where BMS looks for pilot pictures and squadron patches.
+#[derive(Debug, Clone)]
+pub struct ArtDirs {
+    pub pictures: Utf8PathBuf,
+    pub patches: Utf8PathBuf,
+}
+
+impl ArtDirs {
+    /// The art directories that sit alongside a `User/Config` directory.
+    pub fn for_config_dir(config_dir: &Utf8Path) -> Self {
+        let user = config_dir.parent().unwrap_or(config_dir);
+        Self {
+            pictures: user.join("Pictures"),
+            patches: user.join("Patches"),
+        }
+    }
+}
+
+/// The result of looking for a file referenced by a logbook.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Lookup {
+    Found(Utf8PathBuf),
+    /// A file exists, but its name only matches if you ignore case.
+    /// Windows doesn't care, but squadron file shares and Linux hosts might.
+    CaseMismatch(Utf8PathBuf),
+    Missing,
+}
+
+/// Look for the given file in the directory.
+pub fn find(dir: &Utf8Path, file: &Utf8Path) -> Lookup {
+    let path = dir.join(file);
+    let wanted = match path.file_name() {
+        Some(n) => n,
+        None => return Lookup::Missing,
+    };
+    let parent = path.parent().unwrap_or(dir);
+
+    let entries = match parent.read_dir_utf8() {
+        Ok(e) => e,
+        Err(_) => return Lookup::Missing,
+    };
+
+    let mut mismatch = None;
+    for entry in entries.flatten() {
+        if entry.file_name() == wanted {
+            return Lookup::Found(entry.path().to_owned());
+        }
+        if entry.file_name().eq_ignore_ascii_case(wanted) {
+            mismatch = Some(entry.path().to_owned());
+        }
+    }
+    match mismatch {
+        Some(p) => Lookup::CaseMismatch(p),
+        None => Lookup::Missing,
+    }
+}
+
+/// A picture or patch a logbook refers to, and where we looked for it
+#[derive(Debug)]
+pub struct Resource<'a> {
+    /// `picture` or `patch`
+    pub kind: &'static str,
+    pub file: &'a Utf8Path,
+    pub dir: &'a Utf8Path,
+    pub lookup: Lookup,
+}
+
+/// Look for the logbook's picture and patch, skipping any it doesn't set.
+pub fn check<'a>(book: &'a Logbook, art: &'a ArtDirs) -> Vec<Resource<'a>> {
+    [
+        ("picture", &book.picture_file, &art.pictures),
+        ("patch", &book.patch_file, &art.patches),
+    ]
+    .into_iter()
+    .filter(|(_, file, _)| !file.as_str().is_empty())
+    .map(|(kind, file, dir)| Resource {
+        kind,
+        file,
+        dir,
+        lookup: find(dir, file),
+    })
+    .collect()
+}
+
+/// Convert an image (PNG or JPG) into a pilot picture:
+/// a JPG `width` by `height` pixels, cropped to fit, named `{name}.jpg` in the pictures directory.
+///
+/// Returns the new picture's file name, for the logbook's `picture_file`.
+pub fn import_picture(
+    from: &Utf8Path,
+    art: &ArtDirs,
+    name: &str,
+    (width, height): (u32, u32),
+    dry_run: bool,
+) -> Result<Utf8PathBuf> {
+    let image = image::open(from).with_context(|| format!("Couldn't load {from}"))?;
+    let file = Utf8PathBuf::from(format!("{name}.jpg"));
+    let to = art.pictures.join(&file);
+    if dry_run {
+        writeln!(std::io::stdout().lock(), "Would write {to}")?;
+        return Ok(file);
+    }
+
+    let picture = image.resize_to_fill(width, height, FilterType::Lanczos3);
+    std::fs::create_dir_all(&art.pictures)
+        .with_context(|| format!("Couldn't create {}", art.pictures))?;
+    // JPGs have no alpha channel.
+    picture
+        .into_rgb8()
+        .save_with_format(&to, image::ImageFormat::Jpeg)
+        .with_context(|| format!("Couldn't write {to}"))?;
+    debug!("Wrote {to}");
+    Ok(file)
+}
+
+/// A squadron patch BMS can use
+#[derive(Debug, Serialize)]
+pub struct Patch {
+    pub file: Utf8PathBuf,
+    /// Dimensions, if we can read the image (BMP, JPG, or PNG)
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// Every file in the patches directory, sorted by name.
+pub fn patches(art: &ArtDirs) -> Result<Vec<Patch>> {
+    let dir = &art.patches;
+    let mut patches = Vec::new();
+    for entry in dir
+        .read_dir_utf8()
+        .with_context(|| format!("Couldn't read {dir}"))?
+    {
+        let entry = entry.with_context(|| format!("Couldn't read {dir}"))?;
+        if !entry.file_type().map(|t| t.is_file()).unwrap_or(false) {
+            continue;
+        }
+        let (width, height) = match image::image_dimensions(entry.path()) {
+            Ok((w, h)) => (Some(w), Some(h)),
+            Err(e) => {
+                debug!("Couldn't size {}: {e}", entry.path());
+                (None, None)
+            }
+        };
+        patches.push(Patch {
+            file: entry.file_name().into(),
+            width,
+            height,
+        });
+    }
+    patches.sort_by(|a, b| a.file.cmp(&b.file));
+    Ok(patches)
+}
+
+/// Save a PNG thumbnail of each patch we can read into `to_dir`.
+pub fn write_thumbnails(art: &ArtDirs, patches: &[Patch], to_dir: &Utf8Path) -> Result<()> {
+    std::fs::create_dir_all(to_dir).with_context(|| format!("Couldn't create {to_dir}"))?;
+    for patch in patches.iter().filter(|p| p.width.is_some()) {
+        let from = art.patches.join(&patch.file);
+        let to = to_dir.join(&patch.file).with_extension("png");
+        image::open(&from)
+            .with_context(|| format!("Couldn't load {from}"))?
+            .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+            .save_with_format(&to, image::ImageFormat::Png)
+            .with_context(|| format!("Couldn't write {to}"))?;
+        debug!("Wrote {to}");
+    }
+    Ok(())
+}
+
+/// Thumbnails fit in a square this many pixels wide.
+const THUMBNAIL_SIZE: u32 = 64;
+
+pub fn write_patches<W: Write>(
+    w: &mut W,
+    patches: &[Patch],
+    format: Format,
+    pretty: bool,
+) -> Result<()> {
+    if let Format::Json = format {
+        return crate::write_json(w, &patches, pretty);
+    }
+
+    let mut t = Table::new(["File", "Size"]);
+    for p in patches {
+        let size = match (p.width, p.height) {
+            (Some(w), Some(h)) => format!("{w}x{h}"),
+            _ => "-".to_owned(),
+        };
+        t.push(vec![p.file.to_string(), size]);
+    }
+    t.write(w, format, "Squadron patches")
+}
+
+/// Copy the logbook's picture and patch into `to_dir`,
+/// pointing `picture_file` and `patch_file` at the copies (relative to `to_dir`).
+///
+/// Missing files are left out with a warning, and their fields left alone.
+pub fn extract(book: &mut Logbook, art: &ArtDirs, to_dir: &Utf8Path) -> Result<()> {
+    let mut copied = Vec::new();
+    for resource in check(book, art) {
+        let from = match resource.lookup {
+            Lookup::Found(from) | Lookup::CaseMismatch(from) => from,
+            Lookup::Missing => {
+                warn!(
+                    "Leaving out {} {}; it isn't in {}",
+                    resource.kind, resource.file, resource.dir
+                );
+                continue;
+            }
+        };
+        let relative = Utf8Path::new(resource.dir.file_name().unwrap_or(resource.kind))
+            .join(from.file_name().unwrap());
+        let to = to_dir.join(&relative);
+        std::fs::create_dir_all(to.parent().unwrap())
+            .with_context(|| format!("Couldn't create {}", to.parent().unwrap()))?;
+        std::fs::copy(&from, &to).with_context(|| format!("Couldn't copy {from} to {to}"))?;
+        debug!("Copied {from} to {to}");
+        copied.push((resource.kind, relative));
+    }
+
+    for (kind, relative) in copied {
+        match kind {
+            "picture" => book.picture_file = relative,
+            _ => book.patch_file = relative,
+        }
+    }
+    Ok(())
+}
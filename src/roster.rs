@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+use std::io::prelude::*;
+
+use anyhow::Result;
+use camino::Utf8PathBuf;
+use serde::Serialize;
+
+use crate::batch::Entry;
+use crate::humanize;
+use crate::lang;
+use crate::logbook::Rank;
+use crate::table::{self, Table};
+
+#[derive(clap::ValueEnum, Debug, Copy, Clone)]
+pub enum Format {
+    Table,
+    Json,
+}
+
+/// A squadron's totals and the pilots flying for it.
+#[derive(Debug, Serialize)]
+pub struct Squadron {
+    pub name: String,
+    pub pilots: usize,
+    pub total_hours: f32,
+    pub total_kills: i32,
+    /// Mean rank index, where 0 is Second Lieutenant
+    pub average_rank: f32,
+    pub members: Vec<Member>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Member {
+    pub callsign: String,
+    pub name: String,
+    pub rank: Rank,
+    pub flight_hours: f32,
+    pub kills: i16,
+    pub file: Utf8PathBuf,
+}
+
+/// Group pilots by their squadron field, sorted by squadron name.
+///
+/// Kills are campaign air-to-air kills, matching the leaderboard.
+pub fn squadrons(entries: &[Entry]) -> Vec<Squadron> {
+    let mut groups: BTreeMap<&str, Vec<&Entry>> = BTreeMap::new();
+    for e in entries {
+        groups.entry(&e.logbook.squadron).or_default().push(e);
+    }
+
+    groups
+        .into_iter()
+        .map(|(name, entries)| {
+            let mut members: Vec<Member> = entries
+                .iter()
+                .map(|e| Member {
+                    callsign: e.logbook.callsign.clone(),
+                    name: e.logbook.name.clone(),
+                    rank: e.logbook.rank,
+                    flight_hours: e.logbook.flight_hours,
+                    kills: e.logbook.campaign_stats.kills,
+                    file: e.path.clone(),
+                })
+                .collect();
+            // Senior pilots first
+            members.sort_by(|a, b| {
+                b.rank
+                    .cmp(&a.rank)
+                    .then_with(|| a.callsign.cmp(&b.callsign))
+            });
+
+            let rank_sum: i32 = members.iter().map(|m| i32::from(m.rank)).sum();
+            Squadron {
+                name: name.to_owned(),
+                pilots: members.len(),
+                total_hours: members.iter().map(|m| m.flight_hours).sum(),
+                total_kills: members.iter().map(|m| m.kills as i32).sum(),
+                average_rank: rank_sum as f32 / members.len() as f32,
+                members,
+            }
+        })
+        .collect()
+}
+
+/// The rank nearest to a squadron's average
+pub fn nearest_rank(average: f32) -> Rank {
+    Rank::try_from(average.round() as i32).unwrap_or_default()
+}
+
+pub fn write<W: Write>(
+    w: &mut W,
+    squadrons: &[Squadron],
+    format: Format,
+    pretty: bool,
+) -> Result<()> {
+    match format {
+        Format::Json => crate::write_json(w, &squadrons, pretty),
+        Format::Table => {
+            for (i, s) in squadrons.iter().enumerate() {
+                if i > 0 {
+                    writeln!(w)?;
+                }
+                let name = if s.name.is_empty() {
+                    "(no squadron)"
+                } else {
+                    &s.name
+                };
+                writeln!(
+                    w,
+                    "{name}: {} pilots, {} hours, {} kills, average rank {} ({:.1})",
+                    s.pilots,
+                    humanize::hours(s.total_hours),
+                    humanize::count(s.total_kills),
+                    lang::rank(nearest_rank(s.average_rank)),
+                    s.average_rank
+                )?;
+
+                let mut t = Table::new(["Callsign", "Name", "Rank", "Hours", "Kills"]);
+                for m in &s.members {
+                    t.push(vec![
+                        m.callsign.clone(),
+                        m.name.clone(),
+                        lang::rank(m.rank),
+                        humanize::hours(m.flight_hours),
+                        humanize::count(m.kills),
+                    ]);
+                }
+                t.write(w, table::Format::Table, "Roster")?;
+            }
+            Ok(())
+        }
+    }
+}
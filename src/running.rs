@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use camino::Utf8Path;
+use log::*;
+use sysinfo::{ProcessExt, System, SystemExt};
+
+/// What to do when BMS is running and we want to write a logbook.
+///
+/// BMS rewrites the logbooks it has open when it exits,
+/// clobbering anything written in the meantime.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WhenRunning {
+    /// Bail with an error
+    Refuse,
+    /// Wait for BMS to exit, then write
+    Wait,
+    /// Write anyways
+    Force,
+}
+
+const PROCESS_NAME: &str = "Falcon BMS";
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Check that it's safe to write the given logbook, waiting for BMS to exit if asked.
+pub fn guard(path: &Utf8Path, policy: WhenRunning) -> Result<()> {
+    let mut sys = System::new();
+    let mut warned = false;
+
+    loop {
+        sys.refresh_processes();
+        let running = sys
+            .processes_by_name(PROCESS_NAME)
+            .next()
+            .map(|p| p.name().to_owned());
+
+        let process = match running {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+
+        match policy {
+            WhenRunning::Force => {
+                warn!("{process} is running and may overwrite {path} when it exits");
+                return Ok(());
+            }
+            WhenRunning::Refuse => {
+                return Err(crate::exit::Running(format!(
+                    "{process} is running and would overwrite {path} when it exits. \
+                     Close it first, use --wait to write once it exits, or --force to write anyways."
+                ))
+                .into())
+            }
+            WhenRunning::Wait => {
+                if !warned {
+                    warn!("Waiting for {process} to exit before writing {path}");
+                    warned = true;
+                }
+                std::thread::sleep(POLL_INTERVAL);
+            }
+        }
+    }
+}
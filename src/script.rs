@@ -0,0 +1,30 @@
+use anyhow::{anyhow, Context, Result};
+use camino::Utf8Path;
+use rhai::{Dynamic, Engine, Scope};
+
+use crate::logbook::Logbook;
+
+/// Run a Rhai script that can change the logbook, which it sees as `book`.
+///
+/// The script edits `book` in place (e.g., `book.campaign_stats.kills += 1;`)
+/// and can `throw` to abort without writing anything.
+pub fn transform(book: &Logbook, script: &Utf8Path) -> Result<Logbook> {
+    let source =
+        std::fs::read_to_string(script).with_context(|| format!("Couldn't read {script}"))?;
+
+    let engine = Engine::new();
+    let mut scope = Scope::new();
+    let book = rhai::serde::to_dynamic(book).map_err(|e| anyhow!("{e}"))?;
+    scope.push("book", book);
+
+    engine
+        .run_with_scope(&mut scope, &source)
+        .map_err(|e| anyhow!("{script}: {e}"))?;
+
+    let book: Dynamic = scope
+        .get_value("book")
+        .ok_or_else(|| anyhow!("{script} removed `book`"))?;
+    // Go through JSON since Rhai won't narrow its floats back down to f32.
+    let book: serde_json::Value = rhai::serde::from_dynamic(&book).map_err(|e| anyhow!("{e}"))?;
+    serde_json::from_value(book).with_context(|| format!("{script} left an invalid logbook"))
+}
@@ -0,0 +1,154 @@
+//! `read --encrypt`: exports sealed with AES-256-GCM under a passphrase,
+//! so profiles (passwords and all) can be backed up somewhere untrusted.
+//! `write` opens them again.
+//!
+//! A sealed export is still JSON, recording how its key was derived:
+//!
+//! ```json
+//! {"bms_logcat_sealed": 1, "kdf": "pbkdf2-sha256", "iterations": 600000,
+//!  "salt": "...", "nonce": "...", "ciphertext": "..."}
+//! ```
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::{anyhow, bail, Result};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use bms_logcat::layout::to_hex;
+
+use crate::signature::from_hex;
+
+const VERSION: u32 = 1;
+const KDF: &str = "pbkdf2-sha256";
+/// OWASP's recommendation for PBKDF2-HMAC-SHA256
+const ITERATIONS: u32 = 600_000;
+/// Iterations [`open`] will do: fewer would be too weak to have been [`seal`]ed,
+/// and more would just be a way to hang whoever opens the export.
+const ALLOWED_ITERATIONS: std::ops::RangeInclusive<u32> = 100_000..=10_000_000;
+const SALT_LEN: usize = 16;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Sealed {
+    bms_logcat_sealed: u32,
+    kdf: String,
+    iterations: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+fn key(passphrase: &str, salt: &[u8], iterations: u32) -> [u8; 32] {
+    let mut key = [0; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, iterations, &mut key);
+    key
+}
+
+/// Encrypt `plaintext`, giving the sealed JSON.
+pub fn seal(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    seal_with(plaintext, passphrase, ITERATIONS)
+}
+
+fn seal_with(plaintext: &[u8], passphrase: &str, iterations: u32) -> Result<Vec<u8>> {
+    let mut salt = [0; SALT_LEN];
+    let mut nonce = [0; 12];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let key = key(passphrase, &salt, iterations);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| anyhow!("Couldn't encrypt"))?;
+
+    let sealed = Sealed {
+        bms_logcat_sealed: VERSION,
+        kdf: KDF.to_owned(),
+        iterations,
+        salt: to_hex(&salt),
+        nonce: to_hex(&nonce),
+        ciphertext: to_hex(&ciphertext),
+    };
+    let mut json = serde_json::to_vec_pretty(&sealed)?;
+    json.push(b'\n');
+    Ok(json)
+}
+
+/// Whether `text` is something [`seal`] wrote.
+pub fn is_sealed(text: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(text)
+        .is_ok_and(|v| v.get("bms_logcat_sealed").is_some())
+}
+
+/// Decrypt what [`seal`] wrote.
+pub fn open(text: &str, passphrase: &str) -> Result<Vec<u8>> {
+    let sealed: Sealed = serde_json::from_str(text)?;
+    if sealed.bms_logcat_sealed != VERSION || sealed.kdf != KDF {
+        bail!(
+            "Don't know how to open version {} ({}) exports",
+            sealed.bms_logcat_sealed,
+            sealed.kdf
+        );
+    }
+    let hex = |field: &str, s: &str| from_hex(s).ok_or_else(|| anyhow!("{field} isn't hex"));
+    let salt = hex("salt", &sealed.salt)?;
+    let nonce = hex("nonce", &sealed.nonce)?;
+    let ciphertext = hex("ciphertext", &sealed.ciphertext)?;
+    if nonce.len() != 12 {
+        bail!("nonce should be 12 bytes");
+    }
+    if salt.len() != SALT_LEN {
+        bail!("salt should be {SALT_LEN} bytes");
+    }
+    if !ALLOWED_ITERATIONS.contains(&sealed.iterations) {
+        bail!(
+            "iterations should be from {} to {}, not {}",
+            ALLOWED_ITERATIONS.start(),
+            ALLOWED_ITERATIONS.end(),
+            sealed.iterations
+        );
+    }
+
+    let key = key(passphrase, &salt, sealed.iterations);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    cipher
+        .decrypt(Nonce::from_slice(&nonce), &ciphertext[..])
+        .map_err(|_| anyhow!("Wrong passphrase, or the export was changed"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_what_it_seals() {
+        // As few iterations as it'll open, since debug builds are slow at them.
+        let iterations = *ALLOWED_ITERATIONS.start();
+        let sealed = seal_with(b"{\"name\": \"Jane\"}", "hunter2", iterations).unwrap();
+        let text = std::str::from_utf8(&sealed).unwrap();
+        assert!(is_sealed(text));
+        assert_eq!(open(text, "hunter2").unwrap(), b"{\"name\": \"Jane\"}");
+        assert!(open(text, "hunter3").is_err());
+    }
+
+    #[test]
+    fn refuses_odd_parameters() {
+        let sealed = |iterations: u32, salt: &str| {
+            serde_json::to_string(&Sealed {
+                bms_logcat_sealed: VERSION,
+                kdf: KDF.to_owned(),
+                iterations,
+                salt: salt.to_owned(),
+                nonce: "00".repeat(12),
+                ciphertext: "00".repeat(16),
+            })
+            .unwrap()
+        };
+        let salt = "00".repeat(SALT_LEN);
+        let error = |text: String| open(&text, "hunter2").unwrap_err().to_string();
+        assert!(error(sealed(10, &salt)).starts_with("iterations"));
+        assert!(error(sealed(u32::MAX, &salt)).starts_with("iterations"));
+        assert!(error(sealed(ITERATIONS, "00")).starts_with("salt"));
+    }
+}
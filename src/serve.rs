@@ -0,0 +1,1000 @@
+//! A small REST API over a directory of logbooks,
+//! plus a web page at `/` for editing them in the browser.
+//! `/ws` is a WebSocket that sends an event each time a logbook in the directory changes.
+//! `/openapi.json` describes the API, generated from the same [`ROUTES`] that serve it.
+//!
+//! - `GET /pilots` lists every logbook in the directory.
+//! - `GET /pilots/{callsign}` gets one.
+//! - `PUT /pilots/{callsign}` writes one from the JSON in the request body,
+//!   creating `{callsign}.lbk` if there isn't a logbook for that callsign yet.
+//! - `GET`/`PUT /pilots/{callsign}/lbk` download and upload the .lbk file itself.
+//! - `GET /leaderboard` ranks everyone.
+//! - `GET /metrics` has everyone's stats for Prometheus to scrape.
+//! - `GET /export.zip` downloads every logbook at once (admins only).
+//!
+//! Every logbook the server replaces is backed up to `.versions/` first.
+//!
+//! See [`Auth`] for locking it down, and [`redact`](crate::redact) for what's left out.
+
+use std::collections::HashMap;
+use std::io::{prelude::*, Cursor};
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::ValueEnum;
+use log::*;
+use schemars::gen::SchemaSettings;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use tiny_http::{Header, Method, ReadWrite, Request, Response};
+use tungstenite::{handshake::derive_accept_key, protocol::Role, Message, WebSocket};
+use zip::{write::FileOptions, CompressionMethod};
+
+use crate::leaderboard::{self, SortKey};
+use crate::notify::Discord;
+use crate::{
+    backup, batch, diff,
+    logbook::{Logbook, ParseOptions},
+    metrics, redact, watch, LogbookOptions,
+};
+
+pub struct Server<'a> {
+    dir: Utf8PathBuf,
+    options: &'a LogbookOptions,
+    auth: Auth,
+    hub: Hub,
+}
+
+/// Who can use the API
+#[derive(Debug, Default)]
+pub struct Auth {
+    /// Tokens that grant access to everything but `/` and `/openapi.json`,
+    /// given as `Authorization: Bearer <token>` (or a `?token=` query parameter for `/ws`,
+    /// since browsers can't set headers on WebSockets).
+    /// If there aren't any, anyone can use the API.
+    pub tokens: Vec<String>,
+    /// Tokens that also grant access to admin-only endpoints, like `/export.zip`
+    pub admin_tokens: Vec<String>,
+    /// Changing a pilot's logbook takes their logbook password
+    /// (as `X-Pilot-Password`), and passwords are redacted from everything the API sends.
+    pub require_password: bool,
+}
+
+impl Auth {
+    fn open(&self) -> bool {
+        self.tokens.is_empty() && self.admin_tokens.is_empty()
+    }
+
+    fn token_ok(&self, request: &Request) -> bool {
+        self.open()
+            || given_tokens(request).iter().any(|given| {
+                self.tokens
+                    .iter()
+                    .chain(&self.admin_tokens)
+                    .any(|t| same(t, given))
+            })
+    }
+
+    fn is_admin(&self, request: &Request) -> bool {
+        self.open()
+            || given_tokens(request)
+                .iter()
+                .any(|given| self.admin_tokens.iter().any(|t| same(t, given)))
+    }
+
+    /// Can this request change (or download the raw file of) the given pilot's logbook?
+    ///
+    /// Only a real admin token skips the password; an open server still asks for it.
+    fn pilot_ok(&self, request: &Request, book: &Logbook) -> bool {
+        if !self.require_password || (!self.admin_tokens.is_empty() && self.is_admin(request)) {
+            return true;
+        }
+        let given = request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv("X-Pilot-Password"))
+            .map(|h| h.value.as_str())
+            .unwrap_or_default();
+        same(given, &book.password)
+    }
+}
+
+fn given_tokens(request: &Request) -> Vec<String> {
+    let from_header = request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .and_then(|h| h.value.as_str().strip_prefix("Bearer "))
+        .map(str::to_owned);
+    // Browsers can't set headers on WebSockets, so take it in the URL there.
+    let url = request.url();
+    let from_query = if url.split('?').next() == Some("/ws") {
+        query_param(url, "token")
+    } else {
+        None
+    };
+    [from_header, from_query].into_iter().flatten().collect()
+}
+
+/// The request's URL with any `?token=` blanked out, to keep tokens out of the logs
+fn loggable(url: &str) -> String {
+    let Some((path, query)) = url.split_once('?') else {
+        return url.to_owned();
+    };
+    let query: Vec<String> = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some(("token", _)) => "token=REDACTED".to_owned(),
+            _ => pair.to_owned(),
+        })
+        .collect();
+    format!("{path}?{}", query.join("&"))
+}
+
+/// Get a parameter from the URL's query string.
+fn query_param(url: &str, name: &str) -> Option<String> {
+    let (_, query) = url.split_once('?')?;
+    query.split('&').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        (key == name).then(|| percent_decode(value))
+    })
+}
+
+/// Compare secrets without bailing at the first difference,
+/// so response times don't give away how much of a guess was right.
+fn same(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+/// The logbook editor, built into the binary so there's nothing else to install.
+const INDEX_HTML: &str = include_str!("web/index.html");
+
+/// What to send back: a status code and a body
+struct Reply {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Reply {
+    fn json<T: Serialize>(status: u16, value: &T) -> Result<Self> {
+        Ok(Self {
+            status,
+            content_type: "application/json",
+            body: serde_json::to_vec(value)?,
+        })
+    }
+
+    fn error(status: u16, message: impl std::fmt::Display) -> Self {
+        let body = serde_json::json!({ "error": message.to_string() });
+        Self {
+            status,
+            content_type: "application/json",
+            body: body.to_string().into_bytes(),
+        }
+    }
+
+    fn html(body: &str) -> Self {
+        Self::bytes("text/html; charset=utf-8", body.as_bytes().to_vec())
+    }
+
+    fn bytes(content_type: &'static str, body: Vec<u8>) -> Self {
+        Self {
+            status: 200,
+            content_type,
+            body,
+        }
+    }
+}
+
+impl<'a> Server<'a> {
+    pub fn new(
+        dir: Utf8PathBuf,
+        options: &'a LogbookOptions,
+        auth: Auth,
+        redact: redact::Profile,
+        discord: Option<Discord>,
+    ) -> Self {
+        let redact = if auth.require_password {
+            redact.with(redact::Field::Password)
+        } else {
+            redact
+        };
+        let hub = Hub {
+            redact,
+            discord,
+            ..Hub::default()
+        };
+        Self {
+            dir,
+            options,
+            auth,
+            hub,
+        }
+    }
+
+    /// Serve requests on the given address (e.g., `127.0.0.1:8080`) until killed.
+    pub fn run(&self, addr: &str) -> Result<()> {
+        let server =
+            tiny_http::Server::http(addr).map_err(|e| anyhow!("Couldn't listen on {addr}: {e}"))?;
+        info!("Serving logbooks in {} on http://{addr}", self.dir);
+
+        self.hub
+            .remember(&self.dir, &self.options.filter, self.options.parsing())?;
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                let watched = watch::on_change(&self.dir, |path| {
+                    self.hub.changed(path, self.options.parsing())
+                });
+                if let Err(e) = watched {
+                    error!("Stopped sending /ws events: {e:#}");
+                }
+            });
+            self.serve(&server);
+        });
+        Ok(())
+    }
+
+    fn serve(&self, server: &tiny_http::Server) {
+        for mut request in server.incoming_requests() {
+            let is_ws = request.url().split('?').next() == Some("/ws");
+            // Let handle() turn away WebSockets without a good token.
+            if is_ws && is_websocket(&request) && self.auth.token_ok(&request) {
+                if let Err(e) = self.hub.accept(request) {
+                    warn!("Couldn't open a WebSocket: {e:#}");
+                }
+                continue;
+            }
+
+            let reply = self.handle(&mut request).unwrap_or_else(|e| {
+                error!("{} {}: {e:#}", request.method(), loggable(request.url()));
+                Reply::error(500, format!("{e:#}"))
+            });
+            debug!(
+                "{} {} -> {}",
+                request.method(),
+                loggable(request.url()),
+                reply.status
+            );
+
+            let response = Response::from_data(reply.body)
+                .with_status_code(reply.status)
+                .with_header(content_type(reply.content_type));
+            if let Err(e) = request.respond(response) {
+                warn!("Couldn't respond to a request: {e}");
+            }
+        }
+    }
+
+    fn handle(&self, request: &mut Request) -> Result<Reply> {
+        let url = request.url().to_owned();
+        let path = url.split('?').next().unwrap_or_default();
+        let segments: Vec<String> = path
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(percent_decode)
+            .collect();
+
+        let mut path_matched = false;
+        for route in ROUTES {
+            if let Some(params) = route.matches(&segments) {
+                if route.method == *request.method() {
+                    if !route.public && !self.auth.token_ok(request) {
+                        return Ok(Reply::error(401, "Missing or invalid API token"));
+                    }
+                    if route.admin && !self.auth.is_admin(request) {
+                        return Ok(Reply::error(403, "Needs an admin token"));
+                    }
+                    return (route.handler)(self, &params, request);
+                }
+                path_matched = true;
+            }
+        }
+
+        if path_matched {
+            Ok(Reply::error(405, "Method not allowed"))
+        } else {
+            Ok(Reply::error(404, format!("Nothing at {path}")))
+        }
+    }
+
+    fn pilots(&self) -> Result<Vec<Logbook>> {
+        let mut books = Vec::new();
+        for path in batch::logbooks_in(&self.dir, &self.options.filter)? {
+            match crate::read_logbook(&path, self.options) {
+                Ok(book) => books.push(self.hub.redact.apply(book)),
+                Err(e) => warn!("Skipping {path}: {e:#}"),
+            }
+        }
+        Ok(books)
+    }
+
+    /// Find the logbook for the given callsign (ignoring case) and where it lives.
+    fn find(&self, callsign: &str) -> Result<Option<(Utf8PathBuf, Logbook)>> {
+        for path in batch::logbooks_in(&self.dir, &self.options.filter)? {
+            match crate::read_logbook(&path, self.options) {
+                Ok(book) if book.callsign.eq_ignore_ascii_case(callsign) => {
+                    return Ok(Some((path, book)))
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Skipping {path}: {e:#}"),
+            }
+        }
+        Ok(None)
+    }
+
+    fn get(&self, callsign: &str) -> Result<Reply> {
+        match self.find(callsign)? {
+            Some((_, book)) => Reply::json(200, &self.hub.redact.apply(book)),
+            None => Ok(Reply::error(404, format!("No pilot called {callsign}"))),
+        }
+    }
+
+    fn put(&self, callsign: &str, request: &mut Request) -> Result<Reply> {
+        let mut body = String::new();
+        request
+            .as_reader()
+            .read_to_string(&mut body)
+            .context("Couldn't read the request body")?;
+        match serde_json::from_str(&body) {
+            Ok(book) => self.store(callsign, book, true, request),
+            Err(e) => Ok(Reply::error(400, format!("Invalid logbook: {e}"))),
+        }
+    }
+
+    fn upload(&self, callsign: &str, request: &mut Request) -> Result<Reply> {
+        let mut body = Vec::new();
+        request
+            .as_reader()
+            .read_to_end(&mut body)
+            .context("Couldn't read the request body")?;
+        match Logbook::parse_bytes_with(&body, self.options.parsing()) {
+            Ok(book) => self.store(callsign, book, false, request),
+            Err(e) => Ok(Reply::error(400, format!("Invalid logbook: {e:#}"))),
+        }
+    }
+
+    fn download(&self, callsign: &str, request: &Request) -> Result<Reply> {
+        let (path, book) = match self.find(callsign)? {
+            Some(found) => found,
+            None => return Ok(Reply::error(404, format!("No pilot called {callsign}"))),
+        };
+        if !self.auth.pilot_ok(request, &book) {
+            return Ok(Reply::error(403, "Wrong or missing X-Pilot-Password"));
+        }
+        let bytes = std::fs::read(&path).with_context(|| format!("Couldn't read {path}"))?;
+        Ok(Reply::bytes("application/octet-stream", bytes))
+    }
+
+    /// Write a pilot's logbook, backing up the one it replaces.
+    /// Write an uploaded logbook, which is `from_json` that may have been redacted
+    /// or a whole `.lbk` file.
+    fn store(
+        &self,
+        callsign: &str,
+        mut book: Logbook,
+        from_json: bool,
+        request: &Request,
+    ) -> Result<Reply> {
+        if !book.callsign.eq_ignore_ascii_case(callsign) {
+            return Ok(Reply::error(
+                400,
+                format!("Logbook is for {}, not {callsign}", book.callsign),
+            ));
+        }
+
+        let (path, status) = match self.find(callsign)? {
+            Some((path, existing)) => {
+                if !self.auth.pilot_ok(request, &existing) {
+                    return Ok(Reply::error(403, "Wrong or missing X-Pilot-Password"));
+                }
+                // We never showed redacted fields, so don't take blank ones as changes.
+                if from_json {
+                    self.hub.redact.restore(&mut book, &existing);
+                }
+                (path, 200)
+            }
+            None => match new_path(&self.dir, &book.callsign) {
+                Some(path) => (path, 201),
+                None => return Ok(Reply::error(400, "Callsign isn't usable as a file name")),
+            },
+        };
+        // Keep every version, whatever --backup says, and only once.
+        crate::write_logbook_with(&book, &path, self.options, backup::Policy::Versions)?;
+        info!("Wrote {path}");
+        Reply::json(status, &self.hub.redact.apply(book))
+    }
+
+    fn leaderboard(&self, request: &Request) -> Result<Reply> {
+        let sort = match query_param(request.url(), "sort") {
+            Some(s) => match SortKey::from_str(&s, true) {
+                Ok(sort) => sort,
+                Err(e) => return Ok(Reply::error(400, e)),
+            },
+            None => SortKey::Kills,
+        };
+        let entries = self.hub.redact.apply_entries(batch::read_dir(
+            &self.dir,
+            &self.options.filter,
+            self.options.parsing(),
+        )?);
+        Reply::json(200, &leaderboard::standings(&entries, sort))
+    }
+
+    /// Zip up every logbook in the directory.
+    fn export(&self) -> Result<Reply> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        for path in batch::logbooks_in(&self.dir, &self.options.filter)? {
+            let bytes = std::fs::read(&path).with_context(|| format!("Couldn't read {path}"))?;
+            zip.start_file(path.file_name().unwrap(), options)?;
+            zip.write_all(&bytes)?;
+        }
+        let zip = zip.finish()?.into_inner();
+        Ok(Reply::bytes("application/zip", zip))
+    }
+}
+
+/// Pushes logbook changes to everyone connected to `/ws`.
+#[derive(Default)]
+struct Hub {
+    clients: Mutex<Vec<WebSocket<Box<dyn ReadWrite + Send>>>>,
+    /// The last version of each logbook we saw, to find what changed
+    known: Mutex<HashMap<Utf8PathBuf, Logbook>>,
+    /// What to leave out of events, and everything else the API sends
+    redact: redact::Profile,
+    discord: Option<Discord>,
+}
+
+/// What `/ws` clients get when a logbook changes
+#[derive(Serialize)]
+struct Event<'a> {
+    file: &'a Utf8Path,
+    callsign: &'a str,
+    changes: Vec<diff::Change>,
+    logbook: &'a Logbook,
+}
+
+impl Hub {
+    /// Read the logbooks in `dir` so the first change to each can be diffed.
+    fn remember(
+        &self,
+        dir: &Utf8Path,
+        filter: &batch::Filter,
+        parsing: ParseOptions,
+    ) -> Result<()> {
+        let mut known = self.known.lock().unwrap();
+        for entry in batch::read_dir(dir, filter, parsing)? {
+            known.insert(entry.path, entry.logbook);
+        }
+        Ok(())
+    }
+
+    fn accept(&self, request: Request) -> Result<()> {
+        let key = request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv("Sec-WebSocket-Key"))
+            .map(|h| h.value.as_bytes().to_owned())
+            .ok_or_else(|| anyhow!("No Sec-WebSocket-Key"))?;
+        let response = Response::empty(101).with_header(
+            Header::from_bytes("Sec-WebSocket-Accept", derive_accept_key(&key)).unwrap(),
+        );
+        let stream = request.upgrade("websocket", response);
+
+        let socket = WebSocket::from_raw_socket(stream, Role::Server, None);
+        let mut clients = self.clients.lock().unwrap();
+        clients.push(socket);
+        debug!("{} WebSocket client(s) connected", clients.len());
+        Ok(())
+    }
+
+    fn changed(&self, path: &Utf8Path, parsing: ParseOptions) -> Result<()> {
+        let book = batch::parse_file(path, parsing)?;
+        let mut known = self.known.lock().unwrap();
+        let mut changes = match known.get(path) {
+            Some(old) => diff::changes(old, &book)?,
+            None => diff::changes(&Logbook::default(), &book)?,
+        };
+        if let Some(discord) = &self.discord {
+            if let Err(e) = discord.changed(known.get(path), &book) {
+                warn!("{e:#}");
+            }
+        }
+
+        changes.retain(|c| !self.redact.covers(&c.field));
+        let shown = self.redact.apply(book.clone());
+        known.insert(path.to_owned(), book);
+        drop(known);
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        let event = serde_json::to_string(&Event {
+            file: path,
+            callsign: &shown.callsign,
+            changes,
+            logbook: &shown,
+        })?;
+
+        // Drop clients we can't reach anymore.
+        self.clients.lock().unwrap().retain_mut(|client| {
+            match client.write_message(Message::Text(event.clone())) {
+                Ok(()) => true,
+                Err(e) => {
+                    debug!("Dropping WebSocket client: {e}");
+                    false
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+fn is_websocket(request: &Request) -> bool {
+    request
+        .headers()
+        .iter()
+        .any(|h| h.field.equiv("Upgrade") && h.value.as_str().eq_ignore_ascii_case("websocket"))
+}
+
+/// What a request or response body holds, for the OpenAPI document
+#[derive(Debug, Copy, Clone)]
+enum Body {
+    Logbook,
+    Logbooks,
+    Error,
+    Html,
+    OpenApi,
+    Events,
+    Lbk,
+    Zip,
+    Leaderboard,
+    Metrics,
+}
+
+type Handler = fn(&Server, &[String], &mut Request) -> Result<Reply>;
+
+/// An endpoint: how to match it, what to run, and how to describe it.
+struct Route {
+    method: Method,
+    /// Path with `{param}` placeholders, as OpenAPI writes them
+    path: &'static str,
+    summary: &'static str,
+    request: Option<Body>,
+    /// Request headers it looks at, and what for
+    headers: &'static [(&'static str, &'static str)],
+    /// Query parameters it takes, and what for
+    query: &'static [(&'static str, &'static str)],
+    responses: &'static [(u16, &'static str, Body)],
+    /// Doesn't need an API token
+    public: bool,
+    /// Needs an admin token
+    admin: bool,
+    handler: Handler,
+}
+
+impl Route {
+    /// If the path segments fit this route, return the ones filling its placeholders.
+    fn matches(&self, segments: &[String]) -> Option<Vec<String>> {
+        let pattern: Vec<&str> = self.path.split('/').filter(|s| !s.is_empty()).collect();
+        if pattern.len() != segments.len() {
+            return None;
+        }
+        let mut params = Vec::new();
+        for (p, s) in pattern.iter().zip(segments) {
+            if p.starts_with('{') {
+                params.push(s.clone());
+            } else if p != s {
+                return None;
+            }
+        }
+        Some(params)
+    }
+}
+
+const ROUTES: &[Route] = &[
+    Route {
+        method: Method::Get,
+        path: "/",
+        summary: "The logbook editor",
+        request: None,
+        headers: &[],
+        query: &[],
+        responses: &[(200, "The editor page", Body::Html)],
+        public: true,
+        admin: false,
+        handler: |_, _, _| Ok(Reply::html(INDEX_HTML)),
+    },
+    Route {
+        method: Method::Get,
+        path: "/openapi.json",
+        summary: "This API's OpenAPI document",
+        request: None,
+        headers: &[],
+        query: &[],
+        responses: &[(200, "OpenAPI 3.0 document", Body::OpenApi)],
+        public: true,
+        admin: false,
+        handler: |_, _, _| Reply::json(200, &openapi()),
+    },
+    Route {
+        method: Method::Get,
+        path: "/ws",
+        summary: "WebSocket that sends an event each time a logbook changes",
+        request: None,
+        headers: &[],
+        query: &[],
+        responses: &[
+            (101, "Switching to WebSocket", Body::Events),
+            (426, "Not a WebSocket request", Body::Error),
+        ],
+        // WebSocket requests never get this far; see Server::serve().
+        public: false,
+        admin: false,
+        handler: |_, _, _| Ok(Reply::error(426, "Connect to /ws with a WebSocket")),
+    },
+    Route {
+        method: Method::Get,
+        path: "/pilots",
+        summary: "Every logbook in the directory",
+        request: None,
+        headers: &[],
+        query: &[],
+        responses: &[(200, "The logbooks", Body::Logbooks)],
+        public: false,
+        admin: false,
+        handler: |server, _, _| Reply::json(200, &server.pilots()?),
+    },
+    Route {
+        method: Method::Get,
+        path: "/pilots/{callsign}",
+        summary: "The logbook for a callsign (ignoring case)",
+        request: None,
+        headers: &[],
+        query: &[],
+        responses: &[
+            (200, "The logbook", Body::Logbook),
+            (404, "No logbook has that callsign", Body::Error),
+        ],
+        public: false,
+        admin: false,
+        handler: |server, params, _| server.get(&params[0]),
+    },
+    Route {
+        method: Method::Put,
+        path: "/pilots/{callsign}",
+        summary: "Write the logbook for a callsign, creating it if needed",
+        request: Some(Body::Logbook),
+        headers: &[(
+            "X-Pilot-Password",
+            "The logbook's current password, if the server requires it. \
+             A blank password in the body keeps the current one.",
+        )],
+        query: &[],
+        responses: &[
+            (200, "Updated the existing logbook", Body::Logbook),
+            (201, "Created a new logbook", Body::Logbook),
+            (
+                400,
+                "The logbook is invalid or for another callsign",
+                Body::Error,
+            ),
+        ],
+        public: false,
+        admin: false,
+        handler: |server, params, request| server.put(&params[0], request),
+    },
+    Route {
+        method: Method::Get,
+        path: "/pilots/{callsign}/lbk",
+        summary: "Download the logbook file for a callsign",
+        request: None,
+        headers: &[(
+            "X-Pilot-Password",
+            "The logbook's current password, if the server requires it. \
+             Admin tokens don't need it.",
+        )],
+        query: &[],
+        responses: &[
+            (200, "The .lbk file", Body::Lbk),
+            (403, "Wrong or missing X-Pilot-Password", Body::Error),
+            (404, "No logbook has that callsign", Body::Error),
+        ],
+        public: false,
+        admin: false,
+        handler: |server, params, request| server.download(&params[0], request),
+    },
+    Route {
+        method: Method::Put,
+        path: "/pilots/{callsign}/lbk",
+        summary: "Upload the logbook file for a callsign, backing up the one it replaces",
+        request: Some(Body::Lbk),
+        headers: &[(
+            "X-Pilot-Password",
+            "The logbook's current password, if the server requires it. \
+             Admin tokens don't need it.",
+        )],
+        query: &[],
+        responses: &[
+            (200, "Updated the existing logbook", Body::Logbook),
+            (201, "Created a new logbook", Body::Logbook),
+            (
+                400,
+                "The logbook is invalid or for another callsign",
+                Body::Error,
+            ),
+            (403, "Wrong or missing X-Pilot-Password", Body::Error),
+        ],
+        public: false,
+        admin: false,
+        handler: |server, params, request| server.upload(&params[0], request),
+    },
+    Route {
+        method: Method::Get,
+        path: "/leaderboard",
+        summary: "Every pilot in the directory, best first",
+        request: None,
+        headers: &[],
+        query: &[("sort", "kills (default), hours, score, or kd")],
+        responses: &[
+            (200, "The standings", Body::Leaderboard),
+            (400, "Unknown sort", Body::Error),
+        ],
+        public: false,
+        admin: false,
+        handler: |server, _, request| server.leaderboard(request),
+    },
+    Route {
+        method: Method::Get,
+        path: "/metrics",
+        summary: "Every pilot's stats as Prometheus gauges",
+        request: None,
+        headers: &[],
+        query: &[],
+        responses: &[(200, "Prometheus text exposition format", Body::Metrics)],
+        public: false,
+        admin: false,
+        handler: |server, _, _| {
+            let entries = server.hub.redact.apply_entries(batch::read_dir(
+                &server.dir,
+                &server.options.filter,
+                server.options.parsing(),
+            )?);
+            let body = metrics::render(&entries).into_bytes();
+            Ok(Reply::bytes(metrics::CONTENT_TYPE, body))
+        },
+    },
+    Route {
+        method: Method::Get,
+        path: "/export.zip",
+        summary: "Every logbook file in the directory",
+        request: None,
+        headers: &[],
+        query: &[],
+        responses: &[
+            (200, "A zip of the .lbk files", Body::Zip),
+            (403, "Needs an admin token", Body::Error),
+        ],
+        public: false,
+        admin: true,
+        handler: |server, _, _| server.export(),
+    },
+];
+
+/// Describe the API as an OpenAPI 3.0 document.
+pub fn openapi() -> Value {
+    let mut gen = SchemaSettings::openapi3().into_generator();
+    let logbook = serde_json::to_value(gen.subschema_for::<Logbook>()).unwrap();
+    let standing = serde_json::to_value(gen.subschema_for::<leaderboard::Standing>()).unwrap();
+    let error = json!({
+        "type": "object",
+        "properties": { "error": { "type": "string" } },
+        "required": ["error"],
+    });
+
+    let event = json!({
+        "type": "object",
+        "description": "Sent as a text message each time a logbook changes",
+        "properties": {
+            "file": { "type": "string" },
+            "callsign": { "type": "string" },
+            "changes": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "field": { "type": "string" },
+                        "old": {},
+                        "new": {},
+                    },
+                },
+            },
+            "logbook": logbook,
+        },
+    });
+
+    let content = |body: Body| match body {
+        Body::Logbook => json!({ "application/json": { "schema": logbook } }),
+        Body::Logbooks => json!({
+            "application/json": { "schema": { "type": "array", "items": logbook } }
+        }),
+        Body::Error => json!({ "application/json": { "schema": error } }),
+        Body::Html => json!({ "text/html": { "schema": { "type": "string" } } }),
+        Body::OpenApi => json!({ "application/json": { "schema": { "type": "object" } } }),
+        Body::Events => json!({ "application/json": { "schema": event } }),
+        Body::Lbk => json!({
+            "application/octet-stream": { "schema": { "type": "string", "format": "binary" } }
+        }),
+        Body::Zip => json!({
+            "application/zip": { "schema": { "type": "string", "format": "binary" } }
+        }),
+        Body::Leaderboard => json!({
+            "application/json": { "schema": { "type": "array", "items": standing } }
+        }),
+        Body::Metrics => json!({ metrics::CONTENT_TYPE: { "schema": { "type": "string" } } }),
+    };
+
+    let mut paths = Map::new();
+    for route in ROUTES {
+        let mut operation = json!({
+            "summary": route.summary,
+            "responses": route.responses.iter().map(|(status, description, body)| {
+                (status.to_string(), json!({
+                    "description": description,
+                    "content": content(*body),
+                }))
+            }).collect::<Map<String, Value>>(),
+        });
+
+        let mut params: Vec<Value> = route
+            .path
+            .split('/')
+            .filter_map(|s| s.strip_prefix('{')?.strip_suffix('}'))
+            .map(|name| {
+                json!({
+                    "name": name,
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "string" },
+                })
+            })
+            .collect();
+        let optional = |location: &str, (name, description): &(&str, &str)| {
+            json!({
+                "name": name,
+                "in": location,
+                "description": description,
+                "schema": { "type": "string" },
+            })
+        };
+        params.extend(route.headers.iter().map(|h| optional("header", h)));
+        params.extend(route.query.iter().map(|q| optional("query", q)));
+        if !params.is_empty() {
+            operation["parameters"] = params.into();
+        }
+        if !route.public {
+            operation["security"] = json!([{ "token": [] }]);
+            operation["responses"]["401"] = json!({
+                "description": "Missing or invalid API token",
+                "content": content(Body::Error),
+            });
+        }
+        if let Some(body) = route.request {
+            operation["requestBody"] = json!({ "required": true, "content": content(body) });
+        }
+
+        let method = route.method.as_str().to_ascii_lowercase();
+        paths
+            .entry(route.path)
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+            .unwrap()
+            .insert(method, operation);
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "bms-logcat",
+            "description": "Read and write Falcon BMS logbooks",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": {
+            "schemas": gen.take_definitions(),
+            "securitySchemes": {
+                "token": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "Only needed if the server was given API tokens",
+                },
+            },
+        },
+    })
+}
+
+/// Where a new logbook for the callsign goes, so long as that stays inside `dir`.
+fn new_path(dir: &Utf8Path, callsign: &str) -> Option<Utf8PathBuf> {
+    let bad = callsign.is_empty() || callsign.contains(['/', '\\', ':']) || callsign == "..";
+    (!bad).then(|| dir.join(format!("{callsign}.lbk")))
+}
+
+fn content_type(value: &str) -> Header {
+    Header::from_bytes("Content-Type", value).unwrap()
+}
+
+/// Decode `%XX` escapes in a URL path segment.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiny_http::TestRequest;
+
+    fn with_password(password: Option<&str>) -> Request {
+        let mut request = TestRequest::new().with_method(Method::Put);
+        if let Some(pw) = password {
+            request = request.with_header(format!("X-Pilot-Password: {pw}").parse().unwrap());
+        }
+        request.into()
+    }
+
+    #[test]
+    fn open_servers_still_check_passwords() {
+        let auth = Auth {
+            require_password: true,
+            ..Auth::default()
+        };
+        let book = Logbook {
+            password: "secret".to_owned(),
+            ..Logbook::default()
+        };
+        assert!(!auth.pilot_ok(&with_password(None), &book));
+        assert!(!auth.pilot_ok(&with_password(Some("guess")), &book));
+        assert!(auth.pilot_ok(&with_password(Some("secret")), &book));
+    }
+
+    #[test]
+    fn keeps_tokens_out_of_urls() {
+        assert_eq!(loggable("/ws?token=hunter2&x=1"), "/ws?token=REDACTED&x=1");
+        assert_eq!(
+            loggable("/leaderboard?sort=kills"),
+            "/leaderboard?sort=kills"
+        );
+
+        let auth = Auth {
+            tokens: vec!["hunter2".to_owned()],
+            ..Auth::default()
+        };
+        let request = |path: &str| -> Request { TestRequest::new().with_path(path).into() };
+        assert!(auth.token_ok(&request("/ws?token=hunter2")));
+        assert!(!auth.token_ok(&request("/pilots?token=hunter2")));
+    }
+}
@@ -0,0 +1,155 @@
+//! A logbook laid out for people to read in a terminal.
+
+use std::fmt;
+
+use crate::logbook::Logbook;
+
+/// A logbook's identity, stats, and medals, in sections.
+///
+/// [`Logbook`]'s [`Display`](fmt::Display) gives this without color.
+pub struct Show<'a> {
+    book: &'a Logbook,
+    color: bool,
+}
+
+impl Logbook {
+    /// Render the logbook for a terminal, with ANSI colors if `color`.
+    pub fn show(&self, color: bool) -> Show<'_> {
+        Show { book: self, color }
+    }
+}
+
+impl fmt::Display for Logbook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.show(false).fmt(f)
+    }
+}
+
+const BOLD: &str = "\x1b[1m";
+const HEADING: &str = "\x1b[1;36m";
+const LABEL: &str = "\x1b[2m";
+const MEDAL: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+impl Show<'_> {
+    fn paint(&self, style: &str, text: impl fmt::Display) -> String {
+        if self.color {
+            format!("{style}{text}{RESET}")
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn heading(&self, f: &mut fmt::Formatter, title: &str) -> fmt::Result {
+        writeln!(f, "{}", self.paint(HEADING, title))
+    }
+
+    fn row(&self, f: &mut fmt::Formatter, label: &str, value: impl fmt::Display) -> fmt::Result {
+        // Pad before painting, since the escapes would throw the width off.
+        writeln!(f, "  {} {value}", self.paint(LABEL, format!("{label:<13}")))
+    }
+}
+
+impl fmt::Display for Show<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let b = self.book;
+        let d = &b.dogfight_stats;
+        let c = &b.campaign_stats;
+
+        writeln!(
+            f,
+            "{} \"{}\"",
+            self.paint(BOLD, &b.name),
+            self.paint(BOLD, &b.callsign)
+        )?;
+        self.row(f, "Rank", format!("{:?}", b.rank))?;
+        if !b.squadron.is_empty() {
+            self.row(f, "Squadron", &b.squadron)?;
+        }
+        self.row(f, "Commissioned", &b.commissioned)?;
+        self.row(f, "Flight hours", format!("{:.1}", b.flight_hours))?;
+        self.row(f, "Ace factor", b.ace_factor)?;
+        self.row(f, "Voice", b.voice)?;
+        if !b.personal_text.is_empty() {
+            self.row(f, "About", &b.personal_text)?;
+        }
+
+        writeln!(f)?;
+        self.heading(f, "Dogfight")?;
+        self.row(
+            f,
+            "Matches",
+            format!(
+                "{} won, {} lost ({} won, {} lost against humans)",
+                d.matches_won,
+                d.matches_lost,
+                d.matches_won_versus_humans,
+                d.matches_lost_versus_humans
+            ),
+        )?;
+        self.row(f, "Kills", format!("{} ({} human)", d.kills, d.human_kills))?;
+        self.row(
+            f,
+            "Killed",
+            format!("{} ({} by humans)", d.killed, d.killed_versus_humans),
+        )?;
+
+        writeln!(f)?;
+        self.heading(f, "Campaign")?;
+        self.row(
+            f,
+            "Games",
+            format!(
+                "{} won, {} lost, {} tied",
+                c.games_won, c.game_lost, c.games_tied
+            ),
+        )?;
+        self.row(
+            f,
+            "Missions",
+            format!(
+                "{} ({} in a row, {} since the last friendly kill)",
+                c.missions, c.consecutive_missions, c.missions_since_last_friendly_kill
+            ),
+        )?;
+        self.row(
+            f,
+            "Score",
+            format!(
+                "{} ({} from missions)",
+                c.total_score, c.total_mission_score
+            ),
+        )?;
+        self.row(
+            f,
+            "Kills",
+            format!(
+                "{} air ({} human), {} ground, {} static, {} naval",
+                c.kills, c.human_kills, c.air_to_ground_kills, c.static_kills, c.naval_kills
+            ),
+        )?;
+        self.row(
+            f,
+            "Killed",
+            format!("{} ({} by humans)", c.killed, c.killed_versus_humans),
+        )?;
+        self.row(
+            f,
+            "Mistakes",
+            format!(
+                "{} self kills, {} friendly kills",
+                c.self_kills, c.friendly_kills
+            ),
+        )?;
+
+        writeln!(f)?;
+        self.heading(f, "Medals")?;
+        if b.medals.is_empty() {
+            writeln!(f, "  None")?;
+        }
+        for medal in &b.medals {
+            writeln!(f, "  {}", self.paint(MEDAL, format!("{medal:?}")))?;
+        }
+        Ok(())
+    }
+}
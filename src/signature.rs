@@ -0,0 +1,86 @@
+//! HMAC signatures over logbooks, so virtual squadron leagues can tell
+//! whether a submitted logbook's stats changed after it was signed.
+//!
+//! The signature covers the logbook's JSON (with sorted keys) rather than its bytes,
+//! so re-saving it (which may zero padding or drop trailing bytes) doesn't break it,
+//! but changing any field does, down to a trailing space or a `\` in a path.
+
+use anyhow::{anyhow, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::canonical;
+use crate::logbook::Logbook;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Names the algorithm in sidecar files, in case it ever changes.
+const PREFIX: &str = "hmac-sha256:";
+
+/// Where a logbook's signature goes: `{logbook}.sig`
+pub fn sidecar(logbook: &Utf8Path) -> Utf8PathBuf {
+    format!("{logbook}.sig").into()
+}
+
+fn mac(book: &Logbook, key: &str) -> Result<HmacSha256> {
+    let content = serde_json::to_vec(&canonical::sort_keys(serde_json::to_value(book)?))?;
+    let mut mac = HmacSha256::new_from_slice(key.as_bytes())
+        .map_err(|e| anyhow!("Couldn't use the signing key: {e}"))?;
+    mac.update(&content);
+    Ok(mac)
+}
+
+/// The signature, as written to the sidecar file
+pub fn sign(book: &Logbook, key: &str) -> Result<String> {
+    let tag = mac(book, key)?.finalize().into_bytes();
+    let hex: String = tag.iter().map(|b| format!("{b:02x}")).collect();
+    Ok(format!("{PREFIX}{hex}"))
+}
+
+/// Whether `signature` (a sidecar file's contents) matches the logbook.
+pub fn verify(book: &Logbook, key: &str, signature: &str) -> Result<bool> {
+    let hex = signature
+        .trim()
+        .strip_prefix(PREFIX)
+        .with_context(|| crate::exit::Unparsable(format!("Signature isn't {PREFIX}...")))?;
+    let Some(tag) = from_hex(hex) else {
+        return Err(crate::exit::Unparsable("Signature isn't hex".to_owned()).into());
+    };
+    // verify_slice compares in constant time.
+    Ok(mac(book, key)?.verify_slice(&tag).is_ok())
+}
+
+/// `0a1b` as bytes, or `None` if it isn't hex
+pub fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| match std::str::from_utf8(pair) {
+            Ok(pair) if pair.len() == 2 => u8::from_str_radix(pair, 16).ok(),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catches_changes() {
+        let mut book = Logbook::default();
+        let sig = sign(&book, "key").unwrap();
+        assert!(verify(&book, "key", &sig).unwrap());
+        assert!(!verify(&book, "other key", &sig).unwrap());
+
+        book.campaign_stats.kills += 1;
+        assert!(!verify(&book, "key", &sig).unwrap());
+        assert!(verify(&book, "key", "sha1:abcd").is_err());
+
+        // Nothing is normalized away before signing.
+        book.picture_file = "a\\b".into();
+        let sig = sign(&book, "key").unwrap();
+        book.picture_file = "a/b".into();
+        assert!(!verify(&book, "key", &sig).unwrap());
+    }
+}
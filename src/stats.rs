@@ -0,0 +1,77 @@
+use serde::Serialize;
+
+use crate::logbook::Logbook;
+
+/// Metrics computed from a logbook's raw counters.
+///
+/// Ratios whose denominator is zero come out as `None` (`null` in JSON)
+/// rather than infinity or NaN.
+#[derive(Debug, Serialize)]
+pub struct DerivedStats {
+    /// Dogfight kills per death (kills if never killed)
+    pub dogfight_kill_death_ratio: Option<f32>,
+    /// Percentage of dogfight matches won
+    pub dogfight_win_percent: Option<f32>,
+    /// Campaign kills per death (kills if never killed)
+    pub campaign_kill_death_ratio: Option<f32>,
+    /// Campaign games won percentage
+    pub campaign_win_percent: Option<f32>,
+    pub average_mission_score: Option<f32>,
+    /// Air-to-air kills per campaign mission
+    pub kills_per_mission: Option<f32>,
+    /// Friendly kills per campaign mission
+    pub friendly_kill_rate: Option<f32>,
+}
+
+impl DerivedStats {
+    pub fn new(book: &Logbook) -> Self {
+        let d = &book.dogfight_stats;
+        let c = &book.campaign_stats;
+
+        let matches = d.matches_won as i32 + d.matches_lost as i32;
+        let games = c.games_won as i32 + c.game_lost as i32 + c.games_tied as i32;
+
+        Self {
+            dogfight_kill_death_ratio: kill_death(d.kills, d.killed),
+            dogfight_win_percent: ratio(d.matches_won as i32 * 100, matches),
+            campaign_kill_death_ratio: kill_death(c.kills, c.killed),
+            campaign_win_percent: ratio(c.games_won as i32 * 100, games),
+            average_mission_score: ratio(c.total_mission_score, c.missions as i32),
+            kills_per_mission: ratio(c.kills as i32, c.missions as i32),
+            friendly_kill_rate: ratio(c.friendly_kills as i32, c.missions as i32),
+        }
+    }
+}
+
+/// Book and derived stats, flattened into a single JSON object.
+#[derive(Debug, Serialize)]
+pub struct WithDerived<'a> {
+    #[serde(flatten)]
+    pub logbook: &'a Logbook,
+    pub derived: DerivedStats,
+}
+
+impl<'a> WithDerived<'a> {
+    pub fn new(logbook: &'a Logbook) -> Self {
+        Self {
+            logbook,
+            derived: DerivedStats::new(logbook),
+        }
+    }
+}
+
+fn ratio(numerator: i32, denominator: i32) -> Option<f32> {
+    if denominator <= 0 {
+        None
+    } else {
+        Some(numerator as f32 / denominator as f32)
+    }
+}
+
+fn kill_death(kills: i16, killed: i16) -> Option<f32> {
+    if kills <= 0 && killed <= 0 {
+        None
+    } else {
+        ratio(kills as i32, (killed as i32).max(1))
+    }
+}
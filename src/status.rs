@@ -0,0 +1,217 @@
+//! `status`: which logbooks in a directory changed, appeared, or disappeared
+//! since the last `status`, going by hashes of their contents,
+//! e.g., to see what a flying session or a BMS update touched.
+//!
+//! Hashes are kept per directory in `status.json` beside the config file.
+
+use std::collections::BTreeMap;
+use std::io::prelude::*;
+
+use anyhow::{anyhow, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use log::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use bms_logcat::layout::to_hex;
+
+use crate::batch;
+use crate::logbook::{Logbook, ParseOptions};
+
+/// What the last `status` saw, by directory and then file name
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct State {
+    dirs: BTreeMap<Utf8PathBuf, BTreeMap<String, Tracked>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Tracked {
+    sha256: String,
+    callsign: String,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    Changed,
+    New,
+    Gone,
+}
+
+/// A logbook that isn't how the last `status` saw it
+#[derive(Debug, Serialize)]
+pub struct Status {
+    pub file: String,
+    pub callsign: String,
+    pub status: Kind,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    /// Whether this is the first `status` of this directory
+    pub first_check: bool,
+    pub changes: Vec<Status>,
+    pub unchanged: usize,
+}
+
+/// `status.json` in the user's config directory, beside `config.toml`
+pub fn default_path() -> Result<Utf8PathBuf> {
+    Ok(crate::config::default_path()?.with_file_name("status.json"))
+}
+
+impl State {
+    /// Load the state at `path`, or an empty one if there isn't any yet.
+    pub fn load(path: &Utf8Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text)
+                .with_context(|| crate::exit::Unparsable(format!("Couldn't parse {path}"))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Couldn't read {path}")),
+        }
+    }
+
+    pub fn save(&self, path: &Utf8Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_str().is_empty() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Couldn't create {parent}"))?;
+            }
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json + "\n").with_context(|| format!("Couldn't write {path}"))
+    }
+
+    /// Compare the logbooks in `dir` against what was recorded for it,
+    /// then record them as they are now.
+    pub fn check(
+        &mut self,
+        dir: &Utf8Path,
+        filter: &batch::Filter,
+        parsing: ParseOptions,
+    ) -> Result<Report> {
+        let dir = dir
+            .canonicalize_utf8()
+            .with_context(|| format!("Couldn't find {dir}"))?;
+        let mut now = BTreeMap::new();
+        for path in batch::logbooks_in(&dir, filter)? {
+            let bytes = batch::read_bytes(&path)?;
+            let callsign = match Logbook::parse_bytes_with(&bytes, parsing.clone()) {
+                Ok(book) => book.callsign,
+                Err(e) => {
+                    warn!("Couldn't parse {path}: {e:#}");
+                    String::new()
+                }
+            };
+            let name = match crate::archive::split(&path) {
+                Some((_, member)) => member.to_owned(),
+                None => path
+                    .strip_prefix(&dir)
+                    .map_err(|_| anyhow!("{path} isn't in {dir}"))?
+                    .to_string(),
+            };
+            let tracked = Tracked {
+                sha256: to_hex(&Sha256::digest(&bytes)),
+                callsign,
+            };
+            now.insert(name, tracked);
+        }
+
+        let before = self.dirs.get(&dir);
+        let first_check = before.is_none();
+        let (changes, unchanged) = compare(before.unwrap_or(&BTreeMap::new()), &now);
+        self.dirs.insert(dir, now);
+        Ok(Report {
+            first_check,
+            changes,
+            unchanged,
+        })
+    }
+}
+
+/// What changed from `before` to `now`, and how many are the same
+fn compare(
+    before: &BTreeMap<String, Tracked>,
+    now: &BTreeMap<String, Tracked>,
+) -> (Vec<Status>, usize) {
+    let mut changes = Vec::new();
+    let mut unchanged = 0;
+    for (file, tracked) in now {
+        let status = match before.get(file) {
+            Some(old) if old.sha256 == tracked.sha256 => {
+                unchanged += 1;
+                continue;
+            }
+            Some(_) => Kind::Changed,
+            None => Kind::New,
+        };
+        changes.push(Status {
+            file: file.clone(),
+            callsign: tracked.callsign.clone(),
+            status,
+        });
+    }
+    for (file, tracked) in before {
+        if !now.contains_key(file) {
+            changes.push(Status {
+                file: file.clone(),
+                callsign: tracked.callsign.clone(),
+                status: Kind::Gone,
+            });
+        }
+    }
+    (changes, unchanged)
+}
+
+/// Print one line per change, then how many logbooks didn't change.
+pub fn write_text<W: Write>(w: &mut W, report: &Report) -> Result<()> {
+    if report.first_check {
+        writeln!(w, "First status of this directory; every logbook is new.")?;
+    }
+    for s in &report.changes {
+        let status = match s.status {
+            Kind::Changed => "changed",
+            Kind::New => "new",
+            Kind::Gone => "gone",
+        };
+        writeln!(w, "{status:<8} {} ({})", s.file, s.callsign)?;
+    }
+    writeln!(w, "{} unchanged", report.unchanged)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_changes() {
+        let tracked = |sha256: &str, callsign: &str| Tracked {
+            sha256: sha256.to_owned(),
+            callsign: callsign.to_owned(),
+        };
+        let before = BTreeMap::from([
+            ("a.lbk".to_owned(), tracked("aa", "Viper")),
+            ("b.lbk".to_owned(), tracked("bb", "Rattler")),
+            ("d.lbk".to_owned(), tracked("dd", "Iceman")),
+        ]);
+        let now = BTreeMap::from([
+            ("a.lbk".to_owned(), tracked("a2", "Viper")),
+            ("c.lbk".to_owned(), tracked("cc", "Maverick")),
+            ("d.lbk".to_owned(), tracked("dd", "Iceman")),
+        ]);
+        let (changes, unchanged) = compare(&before, &now);
+        let seen: Vec<_> = changes
+            .iter()
+            .map(|s| (s.callsign.as_str(), s.status))
+            .collect();
+        assert_eq!(
+            seen,
+            [
+                ("Viper", Kind::Changed),
+                ("Maverick", Kind::New),
+                ("Rattler", Kind::Gone)
+            ]
+        );
+        assert_eq!(unchanged, 1);
+    }
+}
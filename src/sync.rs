@@ -0,0 +1,100 @@
+//! Merging two copies of the same pilot's logbook, e.g., from two machines.
+//!
+//! Cumulative counters (hours, kills, missions...), rank, and medals take the most
+//! either copy has. Everything else - identity fields like the name, picture, and password,
+//! plus streaks that reset - comes from the newer copy.
+
+use std::io::prelude::*;
+
+use anyhow::Result;
+use camino::Utf8Path;
+use serde_json::Value;
+
+use crate::diff;
+use crate::logbook::Logbook;
+
+/// Stats that can go down, so the newer copy's are the right ones
+const STREAKS: &[&str] = &["consecutive_missions", "missions_since_last_friendly_kill"];
+
+/// Where a merged field came from
+#[derive(Debug)]
+pub enum Source<'a> {
+    File(&'a Utf8Path),
+    /// Neither copy alone, like a union of both copies' medals
+    Both,
+}
+
+/// A field the copies disagreed on, and what the merge took
+#[derive(Debug)]
+pub struct Taken<'a> {
+    pub field: String,
+    pub value: Value,
+    pub from: Source<'a>,
+}
+
+/// Merge two copies of a logbook, returning the result and a report of each field
+/// they disagreed on.
+pub fn merge<'a>(
+    (newer_path, newer): (&'a Utf8Path, &Logbook),
+    (older_path, older): (&'a Utf8Path, &Logbook),
+) -> Result<(Logbook, Vec<Taken<'a>>)> {
+    let mut merged = newer.clone();
+    merged.flight_hours = newer.flight_hours.max(older.flight_hours);
+    merged.rank = newer.rank.max(older.rank);
+    merged.medals.extend(older.medals.iter().copied());
+
+    // Take the larger of every counter in the stats.
+    let mut value = serde_json::to_value(&merged)?;
+    let older_value = serde_json::to_value(older)?;
+    for section in ["dogfight_stats", "campaign_stats"] {
+        let stats = value[section].as_object_mut().unwrap();
+        for (key, n) in stats.iter_mut() {
+            let theirs = &older_value[section][key];
+            if !STREAKS.contains(&key.as_str()) && theirs.as_f64() > n.as_f64() {
+                *n = theirs.clone();
+            }
+        }
+    }
+    let merged: Logbook = serde_json::from_value(value)?;
+
+    let newer_fields = diff::flatten(&serde_json::to_value(newer)?);
+    let merged_fields = diff::flatten(&serde_json::to_value(&merged)?);
+    let mut report = Vec::new();
+    for change in diff::changes(newer, older)? {
+        let value = merged_fields
+            .iter()
+            .find(|(f, _)| *f == change.field)
+            .map(|(_, v)| v.clone())
+            .unwrap_or_default();
+        let from = if change.new.as_ref() == Some(&value) {
+            Source::File(older_path)
+        } else if newer_fields
+            .iter()
+            .any(|(f, v)| *f == change.field && *v == value)
+        {
+            Source::File(newer_path)
+        } else {
+            Source::Both
+        };
+        report.push(Taken {
+            field: change.field,
+            value,
+            from,
+        });
+    }
+    Ok((merged, report))
+}
+
+/// Print the report one field per line, as `field: value (from file)`.
+pub fn write_report<W: Write>(w: &mut W, report: &[Taken]) -> Result<()> {
+    if report.is_empty() {
+        writeln!(w, "The logbooks already match")?;
+    }
+    for taken in report {
+        match &taken.from {
+            Source::File(path) => writeln!(w, "{}: {} (from {path})", taken.field, taken.value)?,
+            Source::Both => writeln!(w, "{}: {} (from both)", taken.field, taken.value)?,
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,163 @@
+use std::io::prelude::*;
+
+use anyhow::{bail, Result};
+
+use crate::lang;
+
+/// How to render tabular reports.
+#[derive(clap::ValueEnum, Debug, Copy, Clone)]
+pub enum Format {
+    Table,
+    Csv,
+    Json,
+    Html,
+}
+
+/// A simple grid of text, rendered as an aligned table, CSV, or HTML.
+#[derive(Debug, Default)]
+pub struct Table {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new<S: Into<String>>(headers: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            headers: headers.into_iter().map(Into::into).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, row: Vec<String>) {
+        debug_assert_eq!(row.len(), self.headers.len());
+        self.rows.push(row);
+    }
+
+    /// Render as text, CSV, or HTML, with headers in the `--lang`.
+    ///
+    /// JSON is left to callers, who have richer data to serialize than strings.
+    pub fn write<W: Write>(&self, w: &mut W, format: Format, title: &str) -> Result<()> {
+        let headers = self
+            .headers
+            .iter()
+            .map(|h| lang::label(h).to_owned())
+            .collect();
+        if crate::canonical::enabled() {
+            let trim = |row: &Vec<String>| row.iter().map(|c| c.trim_end().to_owned()).collect();
+            let trimmed = Table {
+                headers: trim(&headers),
+                rows: self.rows.iter().map(trim).collect(),
+            };
+            return trimmed.write_as(w, format, title);
+        }
+        let localized = Table {
+            headers,
+            rows: self.rows.clone(),
+        };
+        localized.write_as(w, format, title)
+    }
+
+    fn write_as<W: Write>(&self, w: &mut W, format: Format, title: &str) -> Result<()> {
+        match format {
+            Format::Table => self.write_text(w),
+            Format::Csv => self.write_csv(w),
+            Format::Html => self.write_html(w, title),
+            Format::Json => bail!("Can't render {title} as a JSON table"),
+        }
+    }
+
+    /// Write a plain-text table with columns padded to line up.
+    pub fn write_text<W: Write>(&self, w: &mut W) -> Result<()> {
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.chars().count()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let write_row = |w: &mut W, row: &[String]| -> Result<()> {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect::<Vec<_>>()
+                .join("  ");
+            writeln!(w, "{}", line.trim_end())?;
+            Ok(())
+        };
+
+        write_row(w, &self.headers)?;
+        let rule: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+        write_row(w, &rule)?;
+        for row in &self.rows {
+            write_row(w, row)?;
+        }
+        Ok(())
+    }
+
+    pub fn write_csv<W: Write>(&self, w: &mut W) -> Result<()> {
+        let mut csv = csv::Writer::from_writer(w);
+        csv.write_record(&self.headers)?;
+        for row in &self.rows {
+            csv.write_record(row)?;
+        }
+        csv.flush()?;
+        Ok(())
+    }
+
+    /// Write a standalone HTML page containing the table.
+    pub fn write_html<W: Write>(&self, w: &mut W, title: &str) -> Result<()> {
+        writeln!(w, "<!DOCTYPE html>")?;
+        writeln!(w, "<html lang=\"{}\">", lang::get().tag())?;
+        writeln!(w, "<head>")?;
+        writeln!(w, "<meta charset=\"utf-8\">")?;
+        writeln!(w, "<title>{}</title>", escape_html(title))?;
+        writeln!(w, "</head>")?;
+        writeln!(w, "<body>")?;
+        writeln!(w, "<table>")?;
+        writeln!(w, "<thead>")?;
+        write_html_row(w, "th", &self.headers)?;
+        writeln!(w, "</thead>")?;
+        writeln!(w, "<tbody>")?;
+        for row in &self.rows {
+            write_html_row(w, "td", row)?;
+        }
+        writeln!(w, "</tbody>")?;
+        writeln!(w, "</table>")?;
+        writeln!(w, "</body>")?;
+        writeln!(w, "</html>")?;
+        Ok(())
+    }
+}
+
+fn write_html_row<W: Write>(w: &mut W, tag: &str, cells: &[String]) -> Result<()> {
+    write!(w, "<tr>")?;
+    for cell in cells {
+        write!(w, "<{tag}>{}</{tag}>", escape_html(cell))?;
+    }
+    writeln!(w, "</tr>")?;
+    Ok(())
+}
+
+pub fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Format an optional ratio for display, or `-` when it's undefined.
+pub fn optional(value: Option<f32>, precision: usize) -> String {
+    match value {
+        Some(v) => format!("{v:.precision$}"),
+        None => "-".to_owned(),
+    }
+}
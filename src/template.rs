@@ -0,0 +1,121 @@
+//! `--name-template`: file names for commands that write one file per logbook,
+//! like `{callsign}_{rank}.json`, filled in from each logbook's fields.
+//!
+//! Placeholders are field names as `read` spells them in snake case, dotted for
+//! nested ones (`{campaign_stats.kills}`), or `{file}` for the logbook's own file
+//! name without its extension.
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use serde_json::Value;
+
+use crate::logbook::Logbook;
+
+#[derive(Debug, Clone)]
+pub struct NameTemplate(String);
+
+impl FromStr for NameTemplate {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let template = Self(s.to_owned());
+        // Every logbook has the same fields, so a default one finds bad placeholders.
+        template.expand(&Logbook::default(), Utf8Path::new("pilot.lbk"))?;
+        Ok(template)
+    }
+}
+
+impl NameTemplate {
+    /// The file name for the given logbook, read from `source`
+    pub fn render(&self, book: &Logbook, source: &Utf8Path) -> Result<Utf8PathBuf> {
+        let name = self.expand(book, source)?;
+        if name.is_empty() {
+            bail!("{} gave an empty file name for {source}", self.0);
+        }
+        Ok(name.into())
+    }
+
+    fn expand(&self, book: &Logbook, source: &Utf8Path) -> Result<String> {
+        let fields = serde_json::to_value(book)?;
+        let mut name = String::new();
+        let mut rest = self.0.as_str();
+        while let Some(open) = rest.find(['{', '}']) {
+            if rest[open..].starts_with('}') {
+                bail!("Unmatched }} in {}", self.0);
+            }
+            name.push_str(&rest[..open]);
+            let close = rest[open..]
+                .find('}')
+                .ok_or_else(|| anyhow!("Unmatched {{ in {}", self.0))?;
+            let placeholder = &rest[open + 1..open + close];
+            name.push_str(&safe(&fill(placeholder, &fields, source)?));
+            rest = &rest[open + close + 1..];
+        }
+        name.push_str(rest);
+        Ok(name)
+    }
+}
+
+fn fill(placeholder: &str, fields: &Value, source: &Utf8Path) -> Result<String> {
+    if placeholder == "file" {
+        return Ok(source.file_stem().unwrap_or_default().to_owned());
+    }
+    let pointer = format!("/{}", placeholder.replace('.', "/"));
+    match fields.pointer(&pointer) {
+        Some(Value::String(s)) => Ok(s.clone()),
+        Some(Value::Array(items)) => Ok(items
+            .iter()
+            .map(|i| i.as_str().map_or_else(|| i.to_string(), str::to_owned))
+            .collect::<Vec<_>>()
+            .join("-")),
+        Some(v @ (Value::Number(_) | Value::Bool(_))) => Ok(v.to_string()),
+        _ => bail!("{{{placeholder}}} isn't a logbook field"),
+    }
+}
+
+/// Keep values from adding directories or characters Windows won't take.
+fn safe(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>()
+        .replace("..", "__")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_in_fields() {
+        let mut book = Logbook {
+            callsign: "Viper".to_owned(),
+            squadron: "80th/FS".to_owned(),
+            ..Logbook::default()
+        };
+        book.campaign_stats.kills = 12;
+        let template: NameTemplate = "{callsign}_{rank}_{campaign_stats.kills}.json"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            template.render(&book, "a/b.lbk".into()).unwrap(),
+            "Viper_SecondLt_12.json"
+        );
+        let template: NameTemplate = "{squadron}/{file}".parse().unwrap();
+        assert_eq!(
+            template.render(&book, "a/b.lbk".into()).unwrap(),
+            "80th_FS/b"
+        );
+
+        assert!("{nope}.json".parse::<NameTemplate>().is_err());
+        assert!("{callsign.json".parse::<NameTemplate>().is_err());
+        assert!("callsign}.json".parse::<NameTemplate>().is_err());
+        assert!("{dogfight_stats}".parse::<NameTemplate>().is_err());
+    }
+}
@@ -0,0 +1,73 @@
+//! `format_version` in our logbook JSON, so exports from older releases
+//! keep loading as the model changes.
+//!
+//! When the JSON changes shape, add a migration from the last version to the new one.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+const KEY: &str = "format_version";
+
+/// Updates a logbook's fields from one version to the next
+type Migration = fn(&mut Map<String, Value>) -> Result<()>;
+
+/// Changes to the JSON, oldest first: `MIGRATIONS[n]` turns version `n` into `n + 1`.
+const MIGRATIONS: &[Migration] = &[
+    // 0 -> 1: JSON from before we wrote `format_version`. The fields haven't changed.
+    |_| Ok(()),
+];
+
+/// The version of the JSON we write
+pub const CURRENT: u64 = MIGRATIONS.len() as u64;
+
+/// Serialize a logbook (or something wrapping one, like `read --with-options` output)
+/// with `format_version` as its first field.
+pub fn to_value<T: Serialize>(book: &T) -> Result<Value> {
+    Ok(stamp(serde_json::to_value(book)?))
+}
+
+/// Add `format_version` to the front of a serialized logbook.
+pub fn stamp(value: Value) -> Value {
+    match value {
+        Value::Object(fields) => {
+            let mut stamped = Map::with_capacity(fields.len() + 1);
+            stamped.insert(KEY.to_owned(), CURRENT.into());
+            stamped.extend(fields);
+            Value::Object(stamped)
+        }
+        v => v,
+    }
+}
+
+/// Bring JSON written by any release up to date, removing its `format_version`.
+///
+/// JSON without one predates it. A `{"logbook": ..., "options": ...}` wrapper
+/// has its logbook migrated.
+pub fn migrate(mut value: Value) -> Result<Value> {
+    let fields = match value.as_object_mut() {
+        Some(f) => f,
+        None => return Ok(value),
+    };
+    let version = match fields.remove(KEY) {
+        None => 0,
+        Some(v) => v
+            .as_u64()
+            .ok_or_else(|| anyhow!("{KEY} should be a whole number, not {v}"))?,
+    };
+    if version > CURRENT {
+        return Err(crate::exit::Unparsable(format!(
+            "This JSON is {KEY} {version}, from a newer bms-logcat; this one reads up to {CURRENT}"
+        ))
+        .into());
+    }
+    let book = if fields.get("logbook").is_some_and(Value::is_object) {
+        fields["logbook"].as_object_mut().unwrap()
+    } else {
+        fields
+    };
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(book)?;
+    }
+    Ok(value)
+}
@@ -0,0 +1,59 @@
+//! wasm-bindgen wrappers (the `wasm` feature), so a web page can read and write
+//! logbooks entirely in the browser.
+//!
+//! Logbooks cross into JavaScript as JSON: the fields `bms-logcat read` gives, in snake case,
+//! but without its `format_version`. `writeLogbook` takes that back, and, unlike
+//! `bms-logcat write`, neither JSON5 nor JSON from older format versions:
+//!
+//! ```js
+//! import init, { parseLogbook, writeLogbook } from "./bms_logcat.js";
+//!
+//! await init();
+//! const book = JSON.parse(parseLogbook(new Uint8Array(await file.arrayBuffer())));
+//! book.squadron = "80th FS";
+//! const bytes = writeLogbook(JSON.stringify(book));
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+use crate::logbook::{Logbook, Validation};
+
+fn err(e: impl Into<anyhow::Error>) -> JsError {
+    JsError::new(&format!("{:#}", e.into()))
+}
+
+/// Parse a `.lbk` file's bytes into logbook JSON.
+#[wasm_bindgen(js_name = parseLogbook)]
+pub fn parse_logbook(bytes: &[u8]) -> Result<String, JsError> {
+    let book = Logbook::parse_bytes(bytes).map_err(err)?;
+    serde_json::to_string(&book).map_err(err)
+}
+
+/// Turn logbook JSON back into a `.lbk` file's bytes,
+/// throwing if BMS wouldn't take some value.
+#[wasm_bindgen(js_name = writeLogbook)]
+pub fn write_logbook(json: &str) -> Result<Vec<u8>, JsError> {
+    let book: Logbook = serde_json::from_str(json).map_err(err)?;
+    let mut bytes = Vec::new();
+    book.write(&mut bytes, Validation::Strict).map_err(err)?;
+    Ok(bytes)
+}
+
+/// JSON for a new pilot, commissioned today (in UTC).
+#[wasm_bindgen(js_name = newLogbook)]
+pub fn new_logbook(name: &str, callsign: &str) -> Result<String, JsError> {
+    let book = crate::LogbookBuilder::new(name, callsign)
+        .map_err(err)?
+        .build();
+    serde_json::to_string(&book).map_err(err)
+}
+
+/// Every field of a `.lbk` file's bytes, as a JSON array of
+/// `{field, offset, raw, value}`, like `bms-logcat inspect --format json`.
+#[wasm_bindgen(js_name = inspectLogbook)]
+pub fn inspect_logbook(bytes: &[u8]) -> Result<String, JsError> {
+    let events: Vec<_> = crate::layout::events(bytes, &Default::default())
+        .map_err(err)?
+        .collect();
+    serde_json::to_string(&events).map_err(err)
+}
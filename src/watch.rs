@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use log::*;
+use notify::{RecursiveMode, Watcher};
+
+use crate::batch::{self, Entry};
+use crate::history::History;
+use crate::logbook::{Logbook, ParseOptions};
+use crate::metrics;
+use crate::notify::Discord;
+use crate::redact;
+
+/// What to do each time a logbook changes.
+#[derive(Default)]
+pub struct Actions {
+    /// Export fresh JSON to this directory
+    pub json_dir: Option<Utf8PathBuf>,
+    /// Record a snapshot in this history database
+    pub history: Option<History>,
+    /// POST the logbook's JSON to this URL
+    pub webhook: Option<String>,
+    /// What to leave out of the JSON posted to the webhook
+    pub redact: redact::Profile,
+    /// Post pilot updates to this Discord webhook
+    pub discord: Option<Discord>,
+    /// Serve Prometheus metrics on this address
+    pub metrics: Option<String>,
+}
+
+/// BMS writes logbooks in a few pieces; wait for it to settle before reading.
+const SETTLE_TIME: Duration = Duration::from_millis(500);
+
+/// Watch the given directory, running the actions on every logbook that changes.
+///
+/// Runs until the watcher fails.
+pub fn watch(
+    dir: &Utf8Path,
+    filter: &batch::Filter,
+    parsing: ParseOptions,
+    actions: &Actions,
+) -> Result<()> {
+    // Remember how each logbook looked so we can say what changed
+    // and have stats on hand for metrics.
+    let mut known: HashMap<Utf8PathBuf, Logbook> = HashMap::new();
+    if actions.discord.is_some() || actions.metrics.is_some() {
+        for entry in batch::read_dir(dir, filter, parsing.clone())? {
+            known.insert(entry.path, entry.logbook);
+        }
+    }
+    let known = Mutex::new(known);
+    let metrics = actions.metrics.as_deref().map(metrics::bind).transpose()?;
+
+    std::thread::scope(|scope| {
+        if let Some(server) = &metrics {
+            let known = &known;
+            scope.spawn(move || {
+                metrics::serve(server, || {
+                    let mut entries: Vec<Entry> = known
+                        .lock()
+                        .unwrap()
+                        .iter()
+                        .map(|(path, book)| Entry {
+                            path: path.clone(),
+                            logbook: book.clone(),
+                        })
+                        .collect();
+                    entries.sort_by(|a, b| a.path.cmp(&b.path));
+                    entries
+                })
+            });
+        }
+
+        let watched = on_change(dir, |path| {
+            let old = known.lock().unwrap().get(path).cloned();
+            let book = handle(path, parsing.clone(), actions, old.as_ref())?;
+            known.lock().unwrap().insert(path.to_owned(), book);
+            Ok(())
+        });
+        // Stop serving metrics too, or the scope would wait on it forever.
+        if let Some(server) = &metrics {
+            server.unblock();
+        }
+        watched
+    })
+}
+
+/// Watch the given directory, calling `f` with each logbook once it's done changing.
+///
+/// Errors from `f` are logged and don't stop the watch,
+/// which runs until the watcher fails.
+pub fn on_change<F: FnMut(&Utf8Path) -> Result<()>>(dir: &Utf8Path, mut f: F) -> Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher =
+        notify::recommended_watcher(tx).context("Couldn't start the filesystem watcher")?;
+    watcher
+        .watch(dir.as_std_path(), RecursiveMode::NonRecursive)
+        .with_context(|| format!("Couldn't watch {dir}"))?;
+    info!("Watching {dir} for logbook changes");
+
+    // Logbooks with changes we haven't handled yet, and when they last changed
+    let mut pending: HashMap<Utf8PathBuf, Instant> = HashMap::new();
+
+    loop {
+        match rx.recv_timeout(SETTLE_TIME) {
+            Ok(event) => {
+                let event = event.context("Filesystem watcher failed")?;
+                if !(event.kind.is_create() || event.kind.is_modify()) {
+                    continue;
+                }
+                for path in event.paths {
+                    let path = match Utf8PathBuf::try_from(path) {
+                        Ok(p) => p,
+                        Err(e) => {
+                            debug!("Ignoring non-UTF-8 path {}", e.into_path_buf().display());
+                            continue;
+                        }
+                    };
+                    if is_logbook(&path) {
+                        trace!("{path} changed");
+                        pending.insert(path, Instant::now());
+                    }
+                }
+            }
+            // Watchers don't always say when the directory itself goes away.
+            Err(mpsc::RecvTimeoutError::Timeout) if !dir.is_dir() => {
+                return Err(anyhow!("{dir} is gone"));
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                return Err(anyhow!("Filesystem watcher stopped"));
+            }
+        }
+
+        let settled: Vec<Utf8PathBuf> = pending
+            .iter()
+            .filter(|(_, changed)| changed.elapsed() >= SETTLE_TIME)
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in settled {
+            pending.remove(&path);
+            if let Err(e) = f(&path) {
+                warn!("{e:#}");
+            }
+        }
+    }
+}
+
+fn is_logbook(path: &Utf8Path) -> bool {
+    path.extension()
+        .map(|e| e.eq_ignore_ascii_case("lbk"))
+        .unwrap_or(false)
+}
+
+fn handle(
+    path: &Utf8Path,
+    parsing: ParseOptions,
+    actions: &Actions,
+    old: Option<&Logbook>,
+) -> Result<Logbook> {
+    let book = batch::parse_file(path, parsing)?;
+    info!("{} ({path}) changed", book.callsign);
+
+    if let Some(dir) = &actions.json_dir {
+        let json_path = dir.join(path.with_extension("json").file_name().unwrap());
+        let mut json = Vec::new();
+        crate::write_json(&mut json, &crate::version::to_value(&book)?, true)?;
+        std::fs::write(&json_path, json).with_context(|| format!("Couldn't write {json_path}"))?;
+        debug!("Exported {json_path}");
+    }
+
+    if let Some(history) = &actions.history {
+        history.record(path, &book)?;
+        debug!("Recorded a snapshot of {}", book.callsign);
+    }
+
+    if let Some(url) = &actions.webhook {
+        post_json(url, &actions.redact.apply(book.clone()))?;
+        debug!("Posted {} to {url}", book.callsign);
+    }
+
+    if let Some(discord) = &actions.discord {
+        discord.changed(old, &book)?;
+    }
+
+    Ok(book)
+}
+
+fn post_json(url: &str, book: &Logbook) -> Result<()> {
+    ureq::post(url)
+        .set("Content-Type", "application/json")
+        .send_string(&serde_json::to_string(&crate::version::to_value(book)?)?)
+        .with_context(|| format!("Couldn't post to {url}"))?;
+    Ok(())
+}
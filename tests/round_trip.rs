@@ -0,0 +1,140 @@
+//! Logbooks read back exactly as they were written, across everything BMS can hold,
+//! and the checked-in fixtures still read and write the same bytes.
+//!
+//! The fixtures were written by this crate, not by BMS (there are no real BMS logbooks
+//! we can check in), so they catch the format drifting between versions of this crate
+//! rather than proving it matches what BMS writes.
+
+use std::collections::BTreeSet;
+
+use bms_logcat::logbook::VOICES;
+use bms_logcat::{CampaignStats, DogfightStats, Logbook, Medals, Rank, Validation};
+use proptest::prelude::*;
+
+const MEDALS: [Medals; 6] = [
+    Medals::AirForceCross,
+    Medals::SilverStar,
+    Medals::DistinguishedFlyingCross,
+    Medals::AirMedal,
+    Medals::KoreaCampaign,
+    Medals::Longevity,
+];
+
+/// Text of up to `max` bytes, without the NULs that would end it early
+fn text(max: usize) -> impl Strategy<Value = String> {
+    proptest::string::string_regex(&format!("[^\\x00]{{0,{max}}}"))
+        .unwrap()
+        .prop_map(move |mut s| {
+            while s.len() > max {
+                s.pop();
+            }
+            s
+        })
+}
+
+fn dogfight_stats() -> impl Strategy<Value = DogfightStats> {
+    any::<[i16; 8]>().prop_map(|[w, l, wh, lh, k, kd, hk, kh]| DogfightStats {
+        matches_won: w,
+        matches_lost: l,
+        matches_won_versus_humans: wh,
+        matches_lost_versus_humans: lh,
+        kills: k,
+        killed: kd,
+        human_kills: hk,
+        killed_versus_humans: kh,
+    })
+}
+
+fn campaign_stats() -> impl Strategy<Value = CampaignStats> {
+    (any::<[i16; 15]>(), any::<[i32; 2]>()).prop_map(|(c, [score, mission_score])| {
+        let [gw, gl, gt, m, cm, k, kd, hk, kh, sk, agk, stk, nk, fk, msf] = c;
+        CampaignStats {
+            games_won: gw,
+            game_lost: gl,
+            games_tied: gt,
+            missions: m,
+            total_score: score,
+            total_mission_score: mission_score,
+            consecutive_missions: cm,
+            kills: k,
+            killed: kd,
+            human_kills: hk,
+            killed_versus_humans: kh,
+            self_kills: sk,
+            air_to_ground_kills: agk,
+            static_kills: stk,
+            naval_kills: nk,
+            friendly_kills: fk,
+            missions_since_last_friendly_kill: msf,
+        }
+    })
+}
+
+prop_compose! {
+    fn logbook()(
+        (name, callsign, password, commissioned, options_file) in
+            (text(20), text(12), text(10), text(12), text(12)),
+        flight_hours in any::<f32>(),
+        ace_factor in any::<f32>(),
+        rank in (0..7i32).prop_map(|r| Rank::try_from(r).unwrap()),
+        dogfight_stats in dogfight_stats(),
+        campaign_stats in campaign_stats(),
+        medals in proptest::collection::btree_set(0..MEDALS.len(), 0..=MEDALS.len()),
+        (picture_file, patch_file, personal_text, squadron) in
+            (text(32), text(32), text(120), text(19)),
+        voice in 0..VOICES,
+    ) -> Logbook {
+        Logbook {
+            name,
+            callsign,
+            password,
+            commissioned,
+            options_file: options_file.into(),
+            flight_hours,
+            ace_factor,
+            rank,
+            dogfight_stats,
+            campaign_stats,
+            medals: medals.into_iter().map(|i| MEDALS[i]).collect::<BTreeSet<_>>(),
+            picture_file: picture_file.into(),
+            patch_file: patch_file.into(),
+            personal_text,
+            squadron,
+            voice,
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn parse_inverts_write(book in logbook()) {
+        let bytes = book.to_bytes().unwrap();
+        let parsed = Logbook::parse_bytes_with(&bytes, Validation::Strict).unwrap();
+
+        // JSON compares everything but the floats' bits (NaN isn't even equal to itself).
+        prop_assert_eq!(
+            serde_json::to_value(&parsed).unwrap(),
+            serde_json::to_value(&book).unwrap()
+        );
+        prop_assert_eq!(parsed.flight_hours.to_bits(), book.flight_hours.to_bits());
+        prop_assert_eq!(parsed.ace_factor.to_bits(), book.ace_factor.to_bits());
+        prop_assert_eq!(parsed.to_bytes().unwrap(), bytes);
+    }
+}
+
+/// Logbooks an earlier version of this crate wrote still read and write the same way.
+#[test]
+fn reads_and_writes_what_earlier_versions_did() {
+    for name in ["rookie", "veteran"] {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+        let lbk = std::fs::read(format!("{dir}/{name}.lbk")).unwrap();
+        let json = std::fs::read_to_string(format!("{dir}/{name}.json")).unwrap();
+        let expected: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        let parsed = Logbook::parse_bytes(&lbk).unwrap();
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), expected, "{name}");
+
+        let from_json: Logbook = serde_json::from_value(expected).unwrap();
+        assert_eq!(from_json.to_bytes().unwrap(), lbk, "{name}");
+    }
+}